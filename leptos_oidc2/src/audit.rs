@@ -0,0 +1,102 @@
+/*
+* The MIT License (MIT)
+*
+* Copyright (c) 2023 Daniél Kerkmann <daniel@kerkmann.dev>
+*
+* Permission is hereby granted, free of charge, to any person obtaining a copy
+* of this software and associated documentation files (the "Software"), to deal
+* in the Software without restriction, including without limitation the rights
+* to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+* copies of the Software, and to permit persons to whom the Software is
+* furnished to do so, subject to the following conditions:
+*
+* The above copyright notice and this permission notice shall be included in all
+* copies or substantial portions of the Software.
+*
+* THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+* IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+* FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+* AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+* LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+* OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+* SOFTWARE.
+*/
+
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    error::AuthError,
+    metrics::observe,
+    storage::{read_audit_log, write_audit_log, Storage},
+    utils::now,
+};
+
+/// The maximum amount of events kept in the audit log. Older events are
+/// dropped first.
+pub const AUDIT_LOG_CAPACITY: usize = 50;
+
+/// An enumeration representing the kind of a recorded authentication event.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum AuthEventKind {
+    Login,
+    Refresh,
+    Logout,
+    Failure { code: String },
+}
+
+/// A structure representing a recorded authentication event.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct AuthEvent {
+    pub timestamp: NaiveDateTime,
+    #[serde(flatten)]
+    pub kind: AuthEventKind,
+}
+
+/// Converts an `AuthError` into a failure event. Provider errors keep their
+/// OAuth error code (e.g. `invalid_grant`).
+impl From<&AuthError> for AuthEventKind {
+    fn from(error: &AuthError) -> Self {
+        let code = match (error, error.provider_error()) {
+            (_, Some(response)) => response.error.clone(),
+            (AuthError::ThirdPartyCookiesBlocked, None) => {
+                "third_party_cookies_blocked".to_string()
+            }
+            (_, None) => error.to_string(),
+        };
+        AuthEventKind::Failure { code }
+    }
+}
+
+/// Appends an event to the audit log and passes it to the metrics, dropping
+/// the oldest events if the log exceeds `AUDIT_LOG_CAPACITY`. Failing to record an event is logged, but
+/// never interrupts the authentication flow.
+pub(crate) fn record_event(storage: &dyn Storage, kind: AuthEventKind) {
+    observe(&kind);
+    let result = read_audit_log(storage).and_then(|mut events| {
+        events.push(AuthEvent {
+            timestamp: now(),
+            kind,
+        });
+        let overflow = events.len().saturating_sub(AUDIT_LOG_CAPACITY);
+        events.drain(..overflow);
+        write_audit_log(storage, &events)
+    });
+
+    if let Err(error) = result {
+        leptos::logging::error!("Unable to record auth event: {error:#?}");
+    }
+}
+
+/// Records either the given success event or the failure of the result.
+pub(crate) fn record_result<T>(
+    storage: &dyn Storage,
+    result: &Result<T, AuthError>,
+    success: AuthEventKind,
+) {
+    match result {
+        Ok(_) => record_event(storage, success),
+        Err(error) => record_event(storage, error.into()),
+    }
+}
@@ -0,0 +1,517 @@
+/*
+* The MIT License (MIT)
+*
+* Copyright (c) 2023 Daniél Kerkmann <daniel@kerkmann.dev>
+*
+* Permission is hereby granted, free of charge, to any person obtaining a copy
+* of this software and associated documentation files (the "Software"), to deal
+* in the Software without restriction, including without limitation the rights
+* to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+* copies of the Software, and to permit persons to whom the Software is
+* furnished to do so, subject to the following conditions:
+*
+* The above copyright notice and this permission notice shall be included in all
+* copies or substantial portions of the Software.
+*
+* THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+* IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+* FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+* AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+* LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+* OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+* SOFTWARE.
+*/
+
+use std::{collections::HashMap, sync::Arc, time::Duration};
+
+use url::Url;
+
+use crate::{
+    client::{ClientAuthMethod, HttpClient},
+    error::AuthConfigError,
+    idle::IdleTimeout,
+    maintenance::MaintenanceWindow,
+    profile::ProviderProfile,
+    scopes::Scopes,
+    silent::SilentRenewal,
+    sliding::SlidingSession,
+    storage::StorageKind,
+    AuthParameters, LogoutBehavior, ResponseMode, ResponseType,
+};
+
+/// A builder for `AuthParameters`, created by `AuthParameters::builder`. The
+/// endpoints, the client ID and the redirect URIs are required, everything
+/// else is optional. `build` validates that every URL is an absolute URL.
+#[derive(Debug, Default, Clone)]
+#[must_use]
+pub struct AuthParametersBuilder {
+    issuer: Option<String>,
+    auth_endpoint: Option<String>,
+    token_endpoint: Option<String>,
+    logout_endpoint: Option<String>,
+    jwks_uri: Option<String>,
+    revocation_endpoint: Option<String>,
+    check_session_iframe: Option<String>,
+    introspection_endpoint: Option<String>,
+    account_deletion_endpoint: Option<String>,
+    userinfo_endpoint: Option<String>,
+    leeway: Duration,
+    refresh_before_expiry: Duration,
+    expected_issuer: Option<String>,
+    expected_audience: Option<String>,
+    sliding_session: Option<SlidingSession>,
+    idle_timeout: Option<IdleTimeout>,
+    client_id: Option<String>,
+    client_secret: Option<String>,
+    client_auth_method: ClientAuthMethod,
+    redirect_uri: Option<String>,
+    post_logout_redirect_uri: Option<String>,
+    silent_redirect_uri: Option<String>,
+    silent_renewal: SilentRenewal,
+    scope: Option<Scopes>,
+    audience: Option<String>,
+    prompt: Option<String>,
+    max_age: Option<u64>,
+    login_hint: Option<String>,
+    ui_locales: Option<String>,
+    extra_params: Vec<(String, String)>,
+    storage: StorageKind,
+    storage_key: Option<String>,
+    callback_param_overrides: HashMap<String, String>,
+    maintenance_windows: Vec<MaintenanceWindow>,
+    maintenance_url: Option<String>,
+    role_claim: Option<String>,
+    permission_claim: Option<String>,
+    logout_without_id_token_hint: bool,
+    frontchannel_logout_session_required: bool,
+    http_client: Option<Arc<dyn HttpClient>>,
+    logout_behavior: LogoutBehavior,
+    keep_callback_params: bool,
+    profile: ProviderProfile,
+    response_mode: ResponseMode,
+    response_type: ResponseType,
+    pushed_authorization_request_endpoint: Option<String>,
+    pushed_authorization: bool,
+    dpop: bool,
+    report_metrics: bool,
+}
+
+impl AuthParametersBuilder {
+    /// Sets the issuer, which is used to validate tokens.
+    pub fn issuer(mut self, issuer: impl Into<String>) -> Self {
+        self.issuer = Some(issuer.into());
+        self
+    }
+
+    /// Sets the authorization endpoint (required).
+    pub fn auth_endpoint(mut self, auth_endpoint: impl Into<String>) -> Self {
+        self.auth_endpoint = Some(auth_endpoint.into());
+        self
+    }
+
+    /// Sets the token endpoint (required).
+    pub fn token_endpoint(mut self, token_endpoint: impl Into<String>) -> Self {
+        self.token_endpoint = Some(token_endpoint.into());
+        self
+    }
+
+    /// Sets the end session endpoint (required).
+    pub fn logout_endpoint(mut self, logout_endpoint: impl Into<String>) -> Self {
+        self.logout_endpoint = Some(logout_endpoint.into());
+        self
+    }
+
+    /// Sets the URI of the JSON Web Key Set.
+    pub fn jwks_uri(mut self, jwks_uri: impl Into<String>) -> Self {
+        self.jwks_uri = Some(jwks_uri.into());
+        self
+    }
+
+    /// Sets the token revocation endpoint.
+    pub fn revocation_endpoint(mut self, revocation_endpoint: impl Into<String>) -> Self {
+        self.revocation_endpoint = Some(revocation_endpoint.into());
+        self
+    }
+
+    /// Sets the token introspection endpoint.
+    pub fn introspection_endpoint(mut self, introspection_endpoint: impl Into<String>) -> Self {
+        self.introspection_endpoint = Some(introspection_endpoint.into());
+        self
+    }
+
+    /// Sets the endpoint of the backend deleting the account of the user.
+    pub fn account_deletion_endpoint(
+        mut self,
+        account_deletion_endpoint: impl Into<String>,
+    ) -> Self {
+        self.account_deletion_endpoint = Some(account_deletion_endpoint.into());
+        self
+    }
+
+    /// Sets the userinfo endpoint.
+    pub fn userinfo_endpoint(mut self, userinfo_endpoint: impl Into<String>) -> Self {
+        self.userinfo_endpoint = Some(userinfo_endpoint.into());
+        self
+    }
+
+    /// Sets the OP iframe of the session management.
+    pub fn check_session_iframe(mut self, check_session_iframe: impl Into<String>) -> Self {
+        self.check_session_iframe = Some(check_session_iframe.into());
+        self
+    }
+
+    /// Sets the client ID (required).
+    pub fn client_id(mut self, client_id: impl Into<String>) -> Self {
+        self.client_id = Some(client_id.into());
+        self
+    }
+
+    /// Sets the secret of a confidential client and how it's sent to the
+    /// token endpoint. It ends up in the WASM bundle, only set it for
+    /// first-party apps of providers which don't allow public clients.
+    pub fn client_secret(
+        mut self,
+        client_secret: impl Into<String>,
+        method: ClientAuthMethod,
+    ) -> Self {
+        self.client_secret = Some(client_secret.into());
+        self.client_auth_method = method;
+        self
+    }
+
+    /// Sets the redirect URI after the login (required).
+    pub fn redirect_uri(mut self, redirect_uri: impl Into<String>) -> Self {
+        self.redirect_uri = Some(redirect_uri.into());
+        self
+    }
+
+    /// Sets the redirect URI after the logout (required).
+    pub fn post_logout_redirect_uri(mut self, post_logout_redirect_uri: impl Into<String>) -> Self {
+        self.post_logout_redirect_uri = Some(post_logout_redirect_uri.into());
+        self
+    }
+
+    /// Sets the page loaded in the hidden iframe of `Auth::silent_login`.
+    pub fn silent_redirect_uri(mut self, silent_redirect_uri: impl Into<String>) -> Self {
+        self.silent_redirect_uri = Some(silent_redirect_uri.into());
+        self
+    }
+
+    /// Sets how `Auth::silent_login` renews the session, defaults to
+    /// `SilentRenewal::Auto`.
+    pub fn silent_renewal(mut self, silent_renewal: SilentRenewal) -> Self {
+        self.silent_renewal = silent_renewal;
+        self
+    }
+
+    /// Sets the scopes, either as `Scopes` or as a space separated string,
+    /// defaults to `openid`.
+    pub fn scope(mut self, scope: impl Into<Scopes>) -> Self {
+        self.scope = Some(scope.into());
+        self
+    }
+
+    /// Sets the `audience` of the authorization request.
+    pub fn audience(mut self, audience: impl Into<String>) -> Self {
+        self.audience = Some(audience.into());
+        self
+    }
+
+    /// Sets the `prompt` of the authorization request, e.g. `login`.
+    pub fn prompt(mut self, prompt: impl Into<String>) -> Self {
+        self.prompt = Some(prompt.into());
+        self
+    }
+
+    /// Sets the `max_age` in seconds of the authorization request.
+    pub fn max_age(mut self, max_age: u64) -> Self {
+        self.max_age = Some(max_age);
+        self
+    }
+
+    /// Sets the `login_hint` of the authorization request.
+    pub fn login_hint(mut self, login_hint: impl Into<String>) -> Self {
+        self.login_hint = Some(login_hint.into());
+        self
+    }
+
+    /// Sets the `ui_locales` of the authorization request.
+    pub fn ui_locales(mut self, ui_locales: impl Into<String>) -> Self {
+        self.ui_locales = Some(ui_locales.into());
+        self
+    }
+
+    /// Appends an additional parameter to the authorization request.
+    pub fn extra_param(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.extra_params.push((key.into(), value.into()));
+        self
+    }
+
+    /// Sets the storage backend, defaults to the local storage.
+    pub fn storage(mut self, storage: StorageKind) -> Self {
+        self.storage = storage;
+        self
+    }
+
+    /// Sets the key of the token storage, defaults to `auth_<client_id>`.
+    pub fn storage_key(mut self, storage_key: impl Into<String>) -> Self {
+        self.storage_key = Some(storage_key.into());
+        self
+    }
+
+    /// Renames the non-standard callback parameter `name` of the provider to
+    /// the standard name, e.g. `authorization_code` to `code`.
+    pub fn callback_param_override(
+        mut self,
+        name: impl Into<String>,
+        standard_name: impl Into<String>,
+    ) -> Self {
+        self.callback_param_overrides
+            .insert(name.into(), standard_name.into());
+        self
+    }
+
+    /// Adds a scheduled maintenance window of the provider.
+    pub fn maintenance_window(mut self, window: MaintenanceWindow) -> Self {
+        self.maintenance_windows.push(window);
+        self
+    }
+
+    /// Sets the URL returning a JSON list of maintenance windows.
+    pub fn maintenance_url(mut self, maintenance_url: impl Into<String>) -> Self {
+        self.maintenance_url = Some(maintenance_url.into());
+        self
+    }
+
+    /// Sets the dot separated path of the roles claim in the access token,
+    /// defaults to `realm_access.roles`.
+    pub fn role_claim(mut self, role_claim: impl Into<String>) -> Self {
+        self.role_claim = Some(role_claim.into());
+        self
+    }
+
+    /// Sets the dot separated path of the permissions claim in the access
+    /// token, defaults to `permissions`.
+    pub fn permission_claim(mut self, permission_claim: impl Into<String>) -> Self {
+        self.permission_claim = Some(permission_claim.into());
+        self
+    }
+
+    /// Sends unauthenticated users to the end session endpoint anyway, for
+    /// providers which accept a logout without `id_token_hint`. By default
+    /// they are redirected straight to the post logout redirect URI.
+    pub fn logout_without_id_token_hint(mut self, enabled: bool) -> Self {
+        self.logout_without_id_token_hint = enabled;
+        self
+    }
+
+    /// Requires the `iss` and `sid` parameters in front-channel logout
+    /// requests, see `Auth::front_channel_logout`.
+    pub fn frontchannel_logout_session_required(mut self, required: bool) -> Self {
+        self.frontchannel_logout_session_required = required;
+        self
+    }
+
+    /// Sets the HTTP client used for every request to the provider, e.g. a
+    /// `reqwest::Client` with a proxy, custom headers or timeouts, or the
+    /// `GlooNetClient` of the `gloo-net` feature.
+    pub fn http_client(mut self, http_client: impl HttpClient + 'static) -> Self {
+        self.http_client = Some(Arc::new(http_client));
+        self
+    }
+
+    /// Sets what a logout ends, defaults to the session at the provider.
+    pub fn logout_behavior(mut self, logout_behavior: LogoutBehavior) -> Self {
+        self.logout_behavior = logout_behavior;
+        self
+    }
+
+    /// Keeps the callback parameters, like the `code`, in the URL. By default
+    /// they are removed once the callback was handled.
+    pub fn keep_callback_params(mut self, keep: bool) -> Self {
+        self.keep_callback_params = keep;
+        self
+    }
+
+    /// Sets the provider profile, which adapts the requests to the quirks of
+    /// the provider, defaults to `ProviderProfile::Generic`.
+    pub fn profile(mut self, profile: ProviderProfile) -> Self {
+        self.profile = profile;
+        self
+    }
+
+    /// Sets the tolerated clock skew between the browser and the provider,
+    /// defaults to zero.
+    pub fn leeway(mut self, leeway: Duration) -> Self {
+        self.leeway = leeway;
+        self
+    }
+
+    /// Sets the time before the expiry of the access token, in which
+    /// `Auth::access_token` already refreshes it in the background.
+    pub fn refresh_before_expiry(mut self, refresh_before_expiry: Duration) -> Self {
+        self.refresh_before_expiry = refresh_before_expiry;
+        self
+    }
+
+    /// Sets the issuer every decoded token has to be issued by, defaults to
+    /// the issuer.
+    pub fn expected_issuer(mut self, expected_issuer: impl Into<String>) -> Self {
+        self.expected_issuer = Some(expected_issuer.into());
+        self
+    }
+
+    /// Sets the audience every decoded access token has to be issued for,
+    /// usually the API.
+    pub fn expected_audience(mut self, expected_audience: impl Into<String>) -> Self {
+        self.expected_audience = Some(expected_audience.into());
+        self
+    }
+
+    /// Enables the sliding session, which refreshes the tokens on user
+    /// activity.
+    pub fn sliding_session(mut self, sliding_session: SlidingSession) -> Self {
+        self.sliding_session = Some(sliding_session);
+        self
+    }
+
+    /// Enables the idle timeout, which refreshes, warns or logs out once the
+    /// user is idle.
+    pub fn idle_timeout(mut self, idle_timeout: IdleTimeout) -> Self {
+        self.idle_timeout = Some(idle_timeout);
+        self
+    }
+
+    /// Sets how the provider returns the callback parameters, defaults to the
+    /// query string.
+    pub fn response_mode(mut self, response_mode: ResponseMode) -> Self {
+        self.response_mode = response_mode;
+        self
+    }
+
+    /// Sets the `response_type` of the authorization request, defaults to
+    /// the authorization code flow.
+    pub fn response_type(mut self, response_type: ResponseType) -> Self {
+        self.response_type = response_type;
+        self
+    }
+
+    /// Sets the pushed authorization request endpoint (RFC 9126).
+    pub fn pushed_authorization_request_endpoint(mut self, endpoint: impl Into<String>) -> Self {
+        self.pushed_authorization_request_endpoint = Some(endpoint.into());
+        self
+    }
+
+    /// Pushes the authorization request to the pushed authorization request
+    /// endpoint before the login. Disabled by default.
+    pub fn pushed_authorization(mut self, pushed_authorization: bool) -> Self {
+        self.pushed_authorization = pushed_authorization;
+        self
+    }
+
+    /// Binds the tokens to a key of the browser with DPoP (RFC 9449).
+    /// Requires the `dpop` feature.
+    pub fn dpop(mut self, dpop: bool) -> Self {
+        self.dpop = dpop;
+        self
+    }
+
+    /// Forwards the auth events of the browser to the recorder of the server,
+    /// see `metrics::set_metrics`.
+    pub fn report_metrics(mut self, report: bool) -> Self {
+        self.report_metrics = report;
+        self
+    }
+
+    /// Validates the parameters and builds the `AuthParameters`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a required parameter is missing, a URL is not a
+    /// valid absolute URL or `AuthParameters::validate` finds any problems.
+    pub fn build(self) -> Result<AuthParameters, AuthConfigError> {
+        let parameters = AuthParameters {
+            issuer: optional_url("issuer", self.issuer)?,
+            auth_endpoint: required_url("auth_endpoint", self.auth_endpoint)?,
+            token_endpoint: required_url("token_endpoint", self.token_endpoint)?,
+            logout_endpoint: required_url("logout_endpoint", self.logout_endpoint)?,
+            jwks_uri: optional_url("jwks_uri", self.jwks_uri)?,
+            revocation_endpoint: optional_url("revocation_endpoint", self.revocation_endpoint)?,
+            check_session_iframe: optional_url("check_session_iframe", self.check_session_iframe)?,
+            introspection_endpoint: optional_url(
+                "introspection_endpoint",
+                self.introspection_endpoint,
+            )?,
+            account_deletion_endpoint: optional_url(
+                "account_deletion_endpoint",
+                self.account_deletion_endpoint,
+            )?,
+            userinfo_endpoint: optional_url("userinfo_endpoint", self.userinfo_endpoint)?,
+            leeway: self.leeway,
+            refresh_before_expiry: self.refresh_before_expiry,
+            expected_issuer: self.expected_issuer,
+            expected_audience: self.expected_audience,
+            sliding_session: self.sliding_session,
+            idle_timeout: self.idle_timeout,
+            client_id: self
+                .client_id
+                .ok_or(AuthConfigError::Missing("client_id"))?,
+            client_secret: self.client_secret,
+            client_auth_method: self.client_auth_method,
+            redirect_uri: required_url("redirect_uri", self.redirect_uri)?,
+            post_logout_redirect_uri: required_url(
+                "post_logout_redirect_uri",
+                self.post_logout_redirect_uri,
+            )?,
+            silent_redirect_uri: optional_url("silent_redirect_uri", self.silent_redirect_uri)?,
+            silent_renewal: self.silent_renewal,
+            scope: self.scope.unwrap_or_default(),
+            audience: self.audience,
+            prompt: self.prompt,
+            max_age: self.max_age,
+            login_hint: self.login_hint,
+            ui_locales: self.ui_locales,
+            extra_params: self.extra_params,
+            storage: self.storage,
+            storage_key: self.storage_key,
+            callback_param_overrides: self.callback_param_overrides,
+            maintenance_windows: self.maintenance_windows,
+            maintenance_url: optional_url("maintenance_url", self.maintenance_url)?,
+            role_claim: self.role_claim,
+            permission_claim: self.permission_claim,
+            logout_without_id_token_hint: self.logout_without_id_token_hint,
+            frontchannel_logout_session_required: self.frontchannel_logout_session_required,
+            http_client: self.http_client,
+            logout_behavior: self.logout_behavior,
+            keep_callback_params: self.keep_callback_params,
+            profile: self.profile,
+            response_mode: self.response_mode,
+            response_type: self.response_type,
+            pushed_authorization_request_endpoint: self.pushed_authorization_request_endpoint,
+            pushed_authorization: self.pushed_authorization,
+            dpop: self.dpop,
+            report_metrics: self.report_metrics,
+        };
+        parameters.validate().map_err(AuthConfigError::Invalid)?;
+
+        Ok(parameters)
+    }
+}
+
+/// Validates a required URL parameter.
+fn required_url(field: &'static str, value: Option<String>) -> Result<String, AuthConfigError> {
+    optional_url(field, value)?.ok_or(AuthConfigError::Missing(field))
+}
+
+/// Validates an optional URL parameter.
+fn optional_url(
+    field: &'static str,
+    value: Option<String>,
+) -> Result<Option<String>, AuthConfigError> {
+    match value {
+        Some(value) if Url::parse(&value).is_err() => {
+            Err(AuthConfigError::InvalidUrl { field, value })
+        }
+        value => Ok(value),
+    }
+}
@@ -0,0 +1,78 @@
+/*
+* The MIT License (MIT)
+*
+* Copyright (c) 2023 Daniél Kerkmann <daniel@kerkmann.dev>
+*
+* Permission is hereby granted, free of charge, to any person obtaining a copy
+* of this software and associated documentation files (the "Software"), to deal
+* in the Software without restriction, including without limitation the rights
+* to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+* copies of the Software, and to permit persons to whom the Software is
+* furnished to do so, subject to the following conditions:
+*
+* The above copyright notice and this permission notice shall be included in all
+* copies or substantial portions of the Software.
+*
+* THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+* IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+* FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+* AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+* LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+* OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+* SOFTWARE.
+*/
+
+use serde::Serialize;
+
+/// A structure describing the version and the compiled protocol capabilities
+/// of this crate. It's meant to be included in bug reports and diagnostics.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct Capabilities {
+    pub version: &'static str,
+    pub pkce: bool,
+    pub nonce: bool,
+    pub implicit_flow: bool,
+    pub discovery: bool,
+    pub jwks: bool,
+    pub dpop: bool,
+    pub par: bool,
+    pub ssr: bool,
+    pub compression: bool,
+    pub encryption: bool,
+    pub preview: bool,
+    pub components: bool,
+    pub grants: bool,
+    pub web_push: bool,
+    pub storage_backends: &'static [&'static str],
+}
+
+/// Returns the version and the compiled protocol capabilities of this crate.
+#[must_use]
+pub fn capabilities() -> Capabilities {
+    Capabilities {
+        version: env!("CARGO_PKG_VERSION"),
+        pkce: true,
+        nonce: true,
+        implicit_flow: true,
+        discovery: true,
+        jwks: true,
+        dpop: cfg!(feature = "dpop"),
+        par: true,
+        ssr: cfg!(feature = "ssr"),
+        compression: cfg!(feature = "compression"),
+        encryption: cfg!(feature = "encryption"),
+        preview: cfg!(feature = "preview"),
+        components: cfg!(feature = "components"),
+        grants: cfg!(feature = "grants"),
+        web_push: cfg!(feature = "web-push"),
+        storage_backends: &[
+            "local",
+            "session",
+            "in_memory",
+            "cookie",
+            "custom",
+            #[cfg(feature = "leptos-use")]
+            "leptos_use",
+        ],
+    }
+}
@@ -22,12 +22,25 @@
 * SOFTWARE.
 */
 
+use std::{rc::Rc, time::Duration};
+
 use leptos::{
-    component, expect_context, view, AttributeValue, Children, ChildrenFn, IntoAttribute, IntoView,
-    Show, Transition, ViewFn,
+    component, create_effect, create_memo, create_rw_signal, ev::MouseEvent, on_cleanup,
+    set_interval_with_handle, spawn_local, view, AttributeValue, Children, ChildrenFn,
+    IntoAttribute, IntoView, Resource, Show, Signal, SignalGet, SignalGetUntracked, SignalSet,
+    Transition, ViewFn,
 };
+use leptos_router::{use_location, use_query_map, Redirect, Route, SsrMode};
 
-use crate::Auth;
+use crate::{
+    context::{provide_auth, use_auth},
+    error::AuthFailure,
+    interstitial::RedirectKind,
+    registry::use_auth_registry,
+    requirement::Requirement,
+    return_to::{current_route, remember_return_to},
+    Auth, AuthParameters, LogoutBehavior, Scopes,
+};
 
 /// A transparent component representing authenticated user status.
 /// It provides a way to conditionally render its children based on the user's authentication status.
@@ -39,14 +52,14 @@ pub fn Authenticated(
     #[prop(optional, into)] loading: ViewFn,
     #[prop(optional, into)] unauthenticated: ViewFn,
 ) -> impl IntoView {
-    let auth = expect_context::<Auth>();
+    let auth = use_auth();
     let unauthenticated = move || unauthenticated.run();
-    let authenticated = move || auth.authenticated();
+    let authenticated = Signal::derive(move || auth.authenticated());
 
     view! {
         <Transition fallback=loading>
             <Show
-                when=authenticated.clone()
+                when=move || authenticated.get()
                 fallback=unauthenticated.clone()
                 children=children.clone()
             />
@@ -62,7 +75,7 @@ pub fn AuthLoading(
     children: ChildrenFn,
     #[prop(optional, into)] fallback: ViewFn,
 ) -> impl IntoView {
-    let auth = expect_context::<Auth>();
+    let auth = use_auth();
     let loading = move || auth.loading();
 
     view! {
@@ -77,7 +90,7 @@ pub fn AuthLoading(
 #[must_use]
 #[component(transparent)]
 pub fn AuthLoaded(children: ChildrenFn, #[prop(optional, into)] fallback: ViewFn) -> impl IntoView {
-    let auth = expect_context::<Auth>();
+    let auth = use_auth();
     let loaded = move || !auth.loading();
 
     view! {
@@ -87,38 +100,584 @@ pub fn AuthLoaded(children: ChildrenFn, #[prop(optional, into)] fallback: ViewFn
     }
 }
 
+/// A component providing an `Auth` instance as context to its children, the
+/// components inside act on it:
+/// - With `parameters`, it initializes the instance, the declarative
+///   counterpart of `Auth::init`, or of `Auth::init_lazy` with `lazy`. Like
+///   `Auth::init`, it has to be rendered inside a `Router`.
+/// - With `parameters` and an `id`, it initializes one of several providers
+///   and registers it under the ID, like `Auth::init_provider`.
+/// - With an `id` only, it selects the provider registered under the ID.
+///
+/// With a `loading` view, the view is rendered instead of the children
+/// while the authentication state is loading.
+///
+/// # Panics
+///
+/// Panics with an `id` only, if no instance is registered under it, and if
+/// neither `id` nor `parameters` are set.
+#[must_use]
+#[component]
+pub fn AuthProvider(
+    children: ChildrenFn,
+    #[prop(optional, into)] id: Option<String>,
+    #[prop(optional)] parameters: Option<AuthParameters>,
+    #[prop(optional)] lazy: bool,
+    #[prop(optional, into)] loading: Option<ViewFn>,
+) -> impl IntoView {
+    let auth = match (id, parameters) {
+        // A registered provider is always initialized right away.
+        (Some(id), Some(parameters)) => Auth::init_provider(&id, parameters),
+        (None, Some(parameters)) if lazy => Auth::init_lazy(parameters),
+        (None, Some(parameters)) => Auth::init(parameters),
+        (Some(id), None) => use_auth_registry()
+            .get(&id)
+            .unwrap_or_else(|| panic!("no Auth instance registered for provider '{id}'")),
+        (None, None) => panic!("AuthProvider requires an id or parameters"),
+    };
+    provide_auth(auth.clone());
+
+    match loading {
+        Some(loading) => view! {
+            <Show when=move || !auth.loading() fallback=loading>
+                {children()}
+            </Show>
+        }
+        .into_view(),
+        None => children().into_view(),
+    }
+}
+
+/// A component handling the front-channel logout requests of the provider,
+/// see `Auth::front_channel_logout`. Render it on the route registered as
+/// `frontchannel_logout_uri`, ideally without the rest of the app, because
+/// the provider loads it in an iframe. It renders nothing.
+#[must_use]
+#[component]
+pub fn FrontChannelLogout() -> impl IntoView {
+    let auth = use_auth();
+    let query = use_query_map();
+    create_effect(move |handled: Option<bool>| {
+        if handled == Some(true) {
+            return true;
+        }
+        // The request is handled once the stored session was loaded.
+        if auth.loading() {
+            return false;
+        }
+        let query = query.get_untracked();
+        if let Err(error) = auth.front_channel_logout(
+            query.get("iss").map(String::as_str),
+            query.get("sid").map(String::as_str),
+        ) {
+            leptos::logging::error!("Rejected front-channel logout: {error}");
+        }
+        true
+    });
+}
+
 /// A transparent component representing a login link.
 /// It generates a login URL and renders a link with the provided children and optional CSS class.
+/// If `unavailable` is set, it's rendered instead during a maintenance window of the provider.
+/// After the login, the app returns to `return_to` or, by default, to the current route.
+/// With a pushed authorization request endpoint, the request is pushed on click
+/// and the link points to the `redirect_uri` instead, so opening it in a new tab
+/// doesn't skip the pushed request and the request parameters stay out of the DOM.
 #[must_use]
 #[component(transparent)]
 pub fn LoginLink(
     children: Children,
     #[prop(optional, into)] class: Option<AttributeValue>,
+    #[prop(optional, into)] unavailable: Option<ViewFn>,
+    #[prop(optional, into)] return_to: Option<String>,
 ) -> impl IntoView {
-    let auth = expect_context::<Auth>();
-    let login_url = move || auth.login_url();
-
-    view! {
-        <a href=login_url class=class>
+    let auth = use_auth();
+    let maintenance = auth.maintenance_mode();
+    // Without a login URL, e.g. if the code verifier can't be stored, the
+    // link has no target instead of logging in without PKCE.
+    let login_url = {
+        let auth = auth.clone();
+        move || {
+            if auth.requires_pushed_login() {
+                return Some(auth.parameters.redirect_uri.clone());
+            }
+            auth.login_url()
+                .map_err(|error| leptos::logging::error!("Unable to prepare login: {error:#?}"))
+                .ok()
+        }
+    };
+    let on_click = move |event: MouseEvent| {
+        let return_to = return_to.clone().or_else(current_route);
+        // A pushed authorization request has to be sent before the redirect.
+        if auth.requires_pushed_login() {
+            event.prevent_default();
+            auth.login_with_return_to(return_to.as_deref());
+        } else {
+            remember_return_to(
+                auth.parameters.storage.transient_backend(),
+                &auth.parameters.token_storage_key(),
+                return_to.as_deref(),
+            );
+        }
+    };
+    let link = view! {
+        <a href=login_url class=class on:click=on_click>
             {children()}
         </a>
+    };
+
+    match unavailable {
+        Some(unavailable) => view! {
+            <Show when=move || maintenance.get().is_none() fallback=unavailable>
+                {link.clone()}
+            </Show>
+        }
+        .into_view(),
+        None => link.into_view(),
     }
 }
 
 /// A transparent component representing a logout link.
 /// It generates a logout URL and renders a link with the provided children and optional CSS class.
+/// The optional `logout_hint` and `ui_locales` are passed on to the provider.
+/// With `LogoutBehavior::Both`, the local tokens are removed before leaving.
 #[must_use]
 #[component(transparent)]
 pub fn LogoutLink(
     children: Children,
     #[prop(optional, into)] class: Option<AttributeValue>,
+    #[prop(optional, into)] logout_hint: Option<String>,
+    #[prop(optional, into)] ui_locales: Option<String>,
 ) -> impl IntoView {
-    let auth = expect_context::<Auth>();
-    let logout_url = move || auth.logout_url();
+    let auth = use_auth();
+    let logout_url = {
+        let auth = auth.clone();
+        let logout_hint = logout_hint.clone();
+        let ui_locales = ui_locales.clone();
+        move || auth.logout_url_with_hints(logout_hint.as_deref(), ui_locales.as_deref())
+    };
+    let on_click = move |event: MouseEvent| {
+        if auth.parameters.logout_behavior != LogoutBehavior::Both {
+            return;
+        }
+        event.prevent_default();
+        auth.logout_with_hints(logout_hint.as_deref(), ui_locales.as_deref());
+    };
 
     view! {
-        <a href=logout_url class=class>
+        <a href=logout_url class=class on:click=on_click>
             {children()}
         </a>
     }
 }
+
+/// A transparent component representing a login button, e.g. inside forms,
+/// dropdown menus or keyboard-driven UIs, where a link doesn't fit. On click
+/// it redirects to the login page of the provider, see `Auth::login`. During
+/// a maintenance window of the provider the button is disabled. After the
+/// login, the app returns to `return_to` or, by default, to the current route.
+#[must_use]
+#[component(transparent)]
+pub fn LoginButton(
+    children: Children,
+    #[prop(optional, into)] class: Option<AttributeValue>,
+    #[prop(optional, into)] return_to: Option<String>,
+) -> impl IntoView {
+    let auth = use_auth();
+    let maintenance = auth.maintenance_mode();
+    let on_click = move |_: MouseEvent| {
+        auth.login_with_return_to(return_to.clone().or_else(current_route).as_deref());
+    };
+
+    view! {
+        <button
+            type="button"
+            class=class
+            disabled=move || maintenance.get().is_some()
+            on:click=on_click
+        >
+            {children()}
+        </button>
+    }
+}
+
+/// A transparent component representing a logout button, the counterpart of
+/// `LogoutLink`. On click it logs out according to the `LogoutBehavior`, see
+/// `Auth::logout_with_hints`.
+#[must_use]
+#[component(transparent)]
+pub fn LogoutButton(
+    children: Children,
+    #[prop(optional, into)] class: Option<AttributeValue>,
+    #[prop(optional, into)] logout_hint: Option<String>,
+    #[prop(optional, into)] ui_locales: Option<String>,
+) -> impl IntoView {
+    let auth = use_auth();
+    let on_click = move |_: MouseEvent| {
+        auth.logout_with_hints(logout_hint.as_deref(), ui_locales.as_deref());
+    };
+
+    view! {
+        <button type="button" class=class on:click=on_click>
+            {children()}
+        </button>
+    }
+}
+
+/// A component announcing programmatic redirects to the provider, e.g. by
+/// `LoginButton`, `LogoutButton` or `Auth::login`. While it's mounted, these
+/// redirects wait `seconds` (defaults to 3) and it renders a notice like
+/// "Redirecting you to Acme SSO…" with a countdown and a cancel button. It
+/// renders nothing as long as no redirect is pending.
+#[must_use]
+#[component]
+pub fn RedirectingToLogin(
+    #[prop(into)] provider_name: String,
+    #[prop(default = 3)] seconds: u64,
+    #[prop(optional, into)] class: Option<String>,
+) -> impl IntoView {
+    let auth = use_auth();
+    let pending = auth.pending_redirect();
+    let remaining = create_rw_signal(seconds);
+
+    if leptos::leptos_dom::is_browser() {
+        let unregister = auth.register_interstitial();
+        let interval = set_interval_with_handle(
+            {
+                let auth = auth.clone();
+                move || {
+                    if pending.get_untracked().is_none() {
+                        return;
+                    }
+                    let left = remaining.get_untracked().saturating_sub(1);
+                    remaining.set(left);
+                    if left == 0 {
+                        auth.confirm_redirect();
+                    }
+                }
+            },
+            Duration::from_secs(1),
+        );
+        on_cleanup(move || {
+            unregister();
+            if let Ok(interval) = interval {
+                interval.clear();
+            }
+        });
+    }
+    // Every new redirect gets the whole delay.
+    create_effect({
+        let auth = auth.clone();
+        move |_| {
+            if pending.get().is_some() {
+                remaining.set(seconds);
+                if seconds == 0 {
+                    auth.confirm_redirect();
+                }
+            }
+        }
+    });
+
+    let message = move || {
+        pending.get().map(|pending| match pending.kind {
+            RedirectKind::Login => format!("Redirecting you to {provider_name}…"),
+            RedirectKind::Logout => format!("Signing you out of {provider_name}…"),
+        })
+    };
+    let cancel = {
+        let auth = auth.clone();
+        move |_: MouseEvent| auth.cancel_redirect()
+    };
+    let confirm = move |_: MouseEvent| auth.confirm_redirect();
+
+    view! {
+        <Show when=move || pending.get().is_some()>
+            <div class=class.clone() role="alertdialog" aria-live="polite">
+                <p>{message.clone()}</p>
+                <p>{move || remaining.get()} " s"</p>
+                <button type="button" on:click=cancel.clone()>"Cancel"</button>
+                <button type="button" on:click=confirm.clone()>"Continue"</button>
+            </div>
+        </Show>
+    }
+}
+
+/// A transparent component wrapping a view which is backed by a resource.
+/// If the resource fails with an invalid or expired access token (see
+/// [`AuthFailure`]), the access token is renewed once, by a refresh or a
+/// silent login in the iframe (see `Auth::silent_login`), and the resource is
+/// refetched afterwards. While retrying, the optional loading view is
+/// rendered. The renewal never redirects to the interactive login: if it
+/// fails (e.g. with `login_required`), the retry fails, or the resource fails
+/// for any other reason, the fallback view is rendered instead of the
+/// children.
+#[must_use]
+#[component(transparent)]
+pub fn RetryOnAuthError<S, T, E>(
+    resource: Resource<S, Result<T, E>>,
+    children: ChildrenFn,
+    #[prop(optional, into)] loading: ViewFn,
+    #[prop(optional, into)] fallback: ViewFn,
+) -> impl IntoView
+where
+    S: Clone + 'static,
+    T: Clone + 'static,
+    E: AuthFailure + Clone + 'static,
+{
+    let auth = use_auth();
+    let retried = create_rw_signal(false);
+    let renewing = create_rw_signal(false);
+    // `None` while loading, `Some(None)` on success and `Some(Some(_))` with
+    // the auth failure classification of the error otherwise.
+    let auth_failure = move || {
+        resource
+            .get()
+            .map(|result| result.err().map(|error| error.is_auth_failure()))
+    };
+
+    create_effect(move |_| match auth_failure() {
+        Some(Some(true)) if !retried.get_untracked() => {
+            retried.set(true);
+            renewing.set(true);
+            let auth = auth.clone();
+            spawn_local(async move {
+                let renewed = auth.renew_access_token().await;
+                renewing.set(false);
+                // Without a new access token, the retry would fail again.
+                if renewed {
+                    resource.refetch();
+                }
+            });
+        }
+        Some(None) => retried.set(false),
+        _ => {}
+    });
+
+    let failed = move || matches!(auth_failure(), Some(Some(_)));
+    let retrying = move || renewing.get() || (retried.get() && resource.loading().get());
+
+    view! {
+        <Show
+            when=move || !failed()
+            fallback=move || if retrying() { loading.run() } else { fallback.run() }
+            children=children.clone()
+        />
+    }
+}
+
+/// A transparent component rendering its children only if the given
+/// requirement is met, see `Requirement`.
+#[must_use]
+#[component(transparent)]
+pub fn AuthGuard(
+    children: ChildrenFn,
+    #[prop(into)] requirement: Requirement,
+    #[prop(optional, into)] fallback: ViewFn,
+) -> impl IntoView {
+    let auth = use_auth();
+    let access = move || auth.check_access(&requirement);
+
+    view! {
+        <Show when=access fallback=fallback children=children />
+    }
+}
+
+/// A transparent component rendering a link, if the given requirement is
+/// met, see `Requirement`. Otherwise, the children are rendered in a disabled
+/// `span` with the optional tooltip, so navigation menus keep their layout.
+#[must_use]
+#[component(transparent)]
+pub fn AuthorizedA(
+    children: ChildrenFn,
+    #[prop(into)] href: String,
+    #[prop(into)] requirement: Requirement,
+    #[prop(optional, into)] class: Option<String>,
+    #[prop(optional, into)] tooltip: Option<String>,
+) -> impl IntoView {
+    let auth = use_auth();
+    let access = move || auth.check_access(&requirement);
+    let disabled = {
+        let (children, class) = (children.clone(), class.clone());
+        move || {
+            view! {
+                <span class=class.clone() aria-disabled="true" title=tooltip.clone()>
+                    {children()}
+                </span>
+            }
+        }
+    };
+
+    view! {
+        <Show when=access fallback=disabled>
+            <a href=href.clone() class=class.clone()>
+                {children()}
+            </a>
+        </Show>
+    }
+}
+
+/// The access to an `AuthRoute`, which decides what it renders and where it
+/// redirects to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum RouteAccess {
+    Loading,
+    Login,
+    Denied,
+    MissingScopes(Scopes),
+    Granted,
+}
+
+/// A transparent route, which is only rendered if the given requirement is
+/// met (defaults to `Requirement::Authenticated`), e.g. a role. Unlike
+/// `ProtectedRoute`, it waits for the authentication state while it's loading
+/// and renders the optional loading view meanwhile, so a navigation at
+/// startup isn't redirected before the session was restored.
+///
+/// If the requirement isn't met, it redirects to the `redirect_path`. Without
+/// a `redirect_path`, unauthenticated users are sent to the login and return
+/// to the route afterwards (see `Auth::login_with_return_to`), authenticated
+/// users who don't meet the requirement see the `forbidden` view.
+///
+/// If `required_scopes` weren't granted yet, the route starts an incremental
+/// authorization for them (see `Auth::request_scopes`) and is shown again
+/// after the callback. If the provider denies them, it's handled like an
+/// unmet requirement.
+///
+/// The redirects to the provider are started once per change of the access,
+/// in the browser only, the loading view is rendered meanwhile.
+#[must_use]
+#[component(transparent)]
+pub fn AuthRoute<P, F, IV>(
+    path: P,
+    view: F,
+    #[prop(optional, into)] redirect_path: Option<String>,
+    #[prop(optional, into)] requirement: Option<Requirement>,
+    #[prop(optional, into)] required_scopes: Option<Scopes>,
+    #[prop(optional, into)] loading: ViewFn,
+    #[prop(optional, into)] forbidden: ViewFn,
+    #[prop(optional)] ssr: SsrMode,
+    #[prop(optional)] children: Option<Children>,
+) -> impl IntoView
+where
+    P: std::fmt::Display,
+    F: Fn() -> IV + 'static,
+    IV: IntoView,
+{
+    let requirement = requirement.unwrap_or(Requirement::Authenticated);
+    let view = Rc::new(view);
+    let guarded = move || {
+        let auth = use_auth();
+        let location = use_location();
+        let requirement = requirement.clone();
+        let required_scopes = required_scopes.clone();
+        let redirect_path = redirect_path.clone();
+        let loading = loading.clone();
+        let forbidden = forbidden.clone();
+        let view = Rc::clone(&view);
+        let current_route = move || {
+            format!(
+                "{}{}",
+                location.pathname.get_untracked(),
+                location.search.get_untracked()
+            )
+        };
+        // Set once the provider didn't grant the required scopes.
+        let scopes_denied = create_rw_signal(false);
+        let access = {
+            let auth = auth.clone();
+            let required_scopes = required_scopes.clone();
+            let login = redirect_path.is_none();
+            create_memo(move |_| {
+                let missing_scopes = required_scopes
+                    .as_ref()
+                    .map(|scopes| auth.missing_required_scopes(scopes))
+                    .unwrap_or_else(Scopes::new);
+                if auth.loading() {
+                    RouteAccess::Loading
+                } else if !auth.authenticated() && login {
+                    RouteAccess::Login
+                } else if !auth.check_access(&requirement) {
+                    RouteAccess::Denied
+                } else if missing_scopes.is_empty() {
+                    RouteAccess::Granted
+                } else if scopes_denied.get() {
+                    RouteAccess::Denied
+                } else {
+                    RouteAccess::MissingScopes(missing_scopes)
+                }
+            })
+        };
+
+        // Effects don't run on the server, and the memo only changes once per
+        // access, so rendering again doesn't start another redirect.
+        create_effect(move |_| match access.get() {
+            RouteAccess::Login => auth.login_with_return_to(Some(&current_route())),
+            RouteAccess::MissingScopes(scopes) => {
+                if !auth.request_scopes(&scopes, &current_route()) {
+                    scopes_denied.set(true);
+                }
+            }
+            RouteAccess::Granted if required_scopes.is_some() => auth.finish_scope_request(),
+            _ => {}
+        });
+
+        move || match access.get() {
+            RouteAccess::Loading | RouteAccess::Login | RouteAccess::MissingScopes(_) => {
+                loading.run()
+            }
+            RouteAccess::Denied => match &redirect_path {
+                Some(redirect_path) => {
+                    view! { <Redirect path=redirect_path.clone() /> }.into_view()
+                }
+                None => forbidden.run(),
+            },
+            RouteAccess::Granted => (*view)().into_view(),
+        }
+    };
+
+    match children {
+        Some(children) => {
+            view! { <Route path=path view=guarded ssr=ssr>{children()}</Route> }.into_view()
+        }
+        None => view! { <Route path=path view=guarded ssr=ssr /> }.into_view(),
+    }
+}
+
+/// A transparent component rendering its children only if the access token
+/// contains the given role. The roles are read from the claim configured by
+/// `AuthParameters::role_claim`, e.g. `realm_access.roles` for Keycloak.
+#[must_use]
+#[component(transparent)]
+pub fn HasRole(
+    children: ChildrenFn,
+    #[prop(into)] role: String,
+    #[prop(optional, into)] fallback: ViewFn,
+) -> impl IntoView {
+    let auth = use_auth();
+    let requirement = Requirement::role(role);
+    let has_role = move || auth.check_access(&requirement);
+
+    view! {
+        <Show when=has_role fallback=fallback children=children />
+    }
+}
+
+/// A transparent component rendering its children only if the access token
+/// contains the given permission. The permissions are read from the claim
+/// configured by `AuthParameters::permission_claim`, e.g. `permissions` for
+/// Auth0.
+#[must_use]
+#[component(transparent)]
+pub fn HasPermission(
+    children: ChildrenFn,
+    #[prop(into)] permission: String,
+    #[prop(optional, into)] fallback: ViewFn,
+) -> impl IntoView {
+    let auth = use_auth();
+    let requirement = Requirement::permission(permission);
+    let has_permission = move || auth.check_access(&requirement);
+
+    view! {
+        <Show when=has_permission fallback=fallback children=children />
+    }
+}
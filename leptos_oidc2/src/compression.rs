@@ -0,0 +1,89 @@
+/*
+* The MIT License (MIT)
+*
+* Copyright (c) 2023 Daniél Kerkmann <daniel@kerkmann.dev>
+*
+* Permission is hereby granted, free of charge, to any person obtaining a copy
+* of this software and associated documentation files (the "Software"), to deal
+* in the Software without restriction, including without limitation the rights
+* to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+* copies of the Software, and to permit persons to whom the Software is
+* furnished to do so, subject to the following conditions:
+*
+* The above copyright notice and this permission notice shall be included in all
+* copies or substantial portions of the Software.
+*
+* THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+* IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+* FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+* AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+* LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+* OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+* SOFTWARE.
+*/
+
+#[cfg(feature = "compression")]
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+
+use crate::error::{AuthError, StorageErrorKind};
+
+/// The prefix marking a compressed payload. JSON payloads always start with
+/// `{` or `[`, so uncompressed payloads of older versions are still read.
+const COMPRESSED_PREFIX: &str = "deflate:";
+
+/// The compression level, a balance between size and speed.
+#[cfg(feature = "compression")]
+const COMPRESSION_LEVEL: u8 = 6;
+
+/// The maximum size of a decompressed payload. The payloads, like the token
+/// storage, are far smaller, the limit only stops a small, highly compressed
+/// payload (e.g. sent to `write_auth_cookie`) from exhausting the memory.
+#[cfg(feature = "compression")]
+const MAX_DECOMPRESSED_BYTES: usize = 1 << 20;
+
+/// Compresses the payload with deflate and encodes it as base64url, prefixed
+/// with `COMPRESSED_PREFIX`.
+#[cfg(feature = "compression")]
+pub(crate) fn compress(payload: &str) -> String {
+    let compressed = miniz_oxide::deflate::compress_to_vec(payload.as_bytes(), COMPRESSION_LEVEL);
+    format!("{COMPRESSED_PREFIX}{}", URL_SAFE_NO_PAD.encode(compressed))
+}
+
+/// Returns the payload as is, the compression is disabled.
+#[cfg(not(feature = "compression"))]
+pub(crate) fn compress(payload: &str) -> String {
+    payload.to_string()
+}
+
+/// Decompresses the payload, if it's compressed. Uncompressed payloads are
+/// returned as is.
+///
+/// # Errors
+///
+/// Returns an error if the compressed payload is corrupted, or if it can't
+/// be decompressed because the `compression` feature is disabled.
+pub(crate) fn decompress(payload: String) -> Result<String, AuthError> {
+    match payload.strip_prefix(COMPRESSED_PREFIX) {
+        Some(compressed) => inflate(compressed),
+        None => Ok(payload),
+    }
+}
+
+/// Decodes and inflates a compressed payload.
+#[cfg(feature = "compression")]
+fn inflate(compressed: &str) -> Result<String, AuthError> {
+    let compressed = URL_SAFE_NO_PAD
+        .decode(compressed)
+        .map_err(|_| AuthError::Storage(StorageErrorKind::Corrupted))?;
+    let payload =
+        miniz_oxide::inflate::decompress_to_vec_with_limit(&compressed, MAX_DECOMPRESSED_BYTES)
+            .map_err(|_| AuthError::Storage(StorageErrorKind::Corrupted))?;
+
+    String::from_utf8(payload).map_err(|_| AuthError::Storage(StorageErrorKind::Corrupted))
+}
+
+/// Fails, because the `compression` feature is disabled.
+#[cfg(not(feature = "compression"))]
+fn inflate(_compressed: &str) -> Result<String, AuthError> {
+    Err(AuthError::Storage(StorageErrorKind::Corrupted))
+}
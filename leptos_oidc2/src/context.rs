@@ -0,0 +1,65 @@
+/*
+* The MIT License (MIT)
+*
+* Copyright (c) 2023 Daniél Kerkmann <daniel@kerkmann.dev>
+*
+* Permission is hereby granted, free of charge, to any person obtaining a copy
+* of this software and associated documentation files (the "Software"), to deal
+* in the Software without restriction, including without limitation the rights
+* to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+* copies of the Software, and to permit persons to whom the Software is
+* furnished to do so, subject to the following conditions:
+*
+* The above copyright notice and this permission notice shall be included in all
+* copies or substantial portions of the Software.
+*
+* THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+* IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+* FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+* AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+* LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+* OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+* SOFTWARE.
+*/
+
+use leptos::{provide_context, use_context};
+
+use crate::Auth;
+
+/// The context value holding the `Auth` instance. Apps look it up with
+/// `use_auth`, which explains a failed lookup instead of the generic panic
+/// of `expect_context`.
+#[derive(Debug, Clone)]
+pub struct AuthContext(pub Auth);
+
+/// Provides the given instance as `AuthContext`.
+pub(crate) fn provide_auth(auth: Auth) {
+    provide_context(AuthContext(auth));
+}
+
+/// Returns the `Auth` instance of the current context, if any.
+#[must_use]
+pub fn try_use_auth() -> Option<Auth> {
+    use_context::<AuthContext>().map(|AuthContext(auth)| auth)
+}
+
+/// Returns the `Auth` instance of the current context.
+///
+/// # Panics
+///
+/// Panics if there is no `AuthContext`, with a diagnostic of the likely
+/// causes: `Auth::init` wasn't called in a parent component, or another
+/// crate of the app is compiled against another version of this crate, whose
+/// context type differs.
+#[must_use]
+pub fn use_auth() -> Auth {
+    try_use_auth().unwrap_or_else(|| {
+        panic!(
+            "No AuthContext found (leptos_oidc {}). Call `Auth::init` or render an \
+             `AuthProvider` in a parent component. If it is initialized, another crate \
+             of the app likely depends on another version of leptos_oidc, check \
+             `cargo tree --duplicates`.",
+            env!("CARGO_PKG_VERSION"),
+        )
+    })
+}
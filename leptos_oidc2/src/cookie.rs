@@ -0,0 +1,209 @@
+/*
+* The MIT License (MIT)
+*
+* Copyright (c) 2023 Daniél Kerkmann <daniel@kerkmann.dev>
+*
+* Permission is hereby granted, free of charge, to any person obtaining a copy
+* of this software and associated documentation files (the "Software"), to deal
+* in the Software without restriction, including without limitation the rights
+* to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+* copies of the Software, and to permit persons to whom the Software is
+* furnished to do so, subject to the following conditions:
+*
+* The above copyright notice and this permission notice shall be included in all
+* copies or substantial portions of the Software.
+*
+* THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+* IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+* FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+* AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+* LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+* OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+* SOFTWARE.
+*/
+
+use leptos::{server, spawn_local, ServerFnError};
+
+use crate::{
+    error::AuthError,
+    state::AuthStateSerde,
+    storage::{InMemoryStorage, Storage, TokenStorage, AUDIT_LOG_KEY},
+};
+
+/// The name of the `HttpOnly` cookie the token storage is kept in. It's
+/// fixed on the server, so the server functions can't be used to read or
+/// write other cookies.
+pub const AUTH_COOKIE_NAME: &str = "leptos_oidc_tokens";
+
+/// A storage backend for server side rendering. The token storage is kept in
+/// an `HttpOnly` cookie, which is written by a server function, so the server
+/// is able to render the authenticated state. Scripts can't read the cookie,
+/// that's why the browser keeps a copy in memory. After a reload, the browser
+/// only gets the state without the tokens (`AuthStateSerde`), the tokens stay
+/// on the server and can be read by the server functions of the app with
+/// `read_auth_cookie`. The audit log is kept in memory only.
+///
+/// Note: Cookies are limited to about 4KB, very large tokens might not fit.
+/// There is a single cookie, so only one provider can use this storage.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct CookieStorage;
+
+impl Storage for CookieStorage {
+    fn get(&self, key: &str) -> Result<Option<String>, AuthError> {
+        InMemoryStorage.get(key)
+    }
+
+    fn set(&self, key: &str, value: &str) -> Result<(), AuthError> {
+        if key != AUDIT_LOG_KEY {
+            let token_storage_json = value.to_string();
+            spawn_local(async move {
+                if let Err(error) = write_auth_cookie(Some(token_storage_json)).await {
+                    leptos::logging::error!("Unable to write auth cookie: {error:#?}");
+                }
+            });
+        }
+        InMemoryStorage.set(key, value)
+    }
+
+    fn remove(&self, key: &str) -> Result<(), AuthError> {
+        if key != AUDIT_LOG_KEY {
+            spawn_local(async move {
+                if let Err(error) = write_auth_cookie(None).await {
+                    leptos::logging::error!("Unable to remove auth cookie: {error:#?}");
+                }
+            });
+        }
+        InMemoryStorage.remove(key)
+    }
+}
+
+/// Reads the token storage out of the `HttpOnly` cookie of the current
+/// request. It's meant for the server functions of the app, which call APIs
+/// with the access token on behalf of the user. It's no server function
+/// itself, the tokens never leave the server.
+#[cfg(feature = "ssr")]
+#[must_use]
+pub fn read_auth_cookie() -> Option<TokenStorage> {
+    use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+
+    use crate::compression::decompress;
+
+    let parts = leptos::use_context::<http::request::Parts>()?;
+    let prefix = format!("{AUTH_COOKIE_NAME}=");
+
+    parts
+        .headers
+        .get_all(http::header::COOKIE)
+        .iter()
+        .filter_map(|header| header.to_str().ok())
+        .flat_map(|header| header.split(';'))
+        .filter_map(|cookie| cookie.trim().strip_prefix(prefix.as_str()))
+        .filter_map(|value| URL_SAFE_NO_PAD.decode(value).ok())
+        .filter_map(|value| String::from_utf8(value).ok())
+        .filter_map(|value| decompress(value).ok())
+        .find_map(|value| serde_json::from_str(&value).ok())
+}
+
+/// Reads the authentication state out of the `HttpOnly` cookie of the current
+/// request, without the tokens. The browser restores the session with it and
+/// it can be embedded into the server rendered HTML, see `AuthStateSerde`.
+///
+/// # Errors
+///
+/// Returns an error if the request isn't available on the server.
+#[server(ReadAuthState, "/api")]
+pub async fn read_auth_state() -> Result<AuthStateSerde, ServerFnError> {
+    if leptos::use_context::<http::request::Parts>().is_none() {
+        return Err(ServerFnError::ServerError(
+            "missing request parts".to_string(),
+        ));
+    }
+
+    Ok(match read_auth_cookie() {
+        Some(tokens) => (&tokens).into(),
+        None => AuthStateSerde::Unauthenticated,
+    })
+}
+
+/// Returns whether the request was sent by a page of the app itself, and not
+/// by another site, e.g. a form posting a session of the attacker (login
+/// CSRF). Browsers send `Sec-Fetch-Site` with every request, older ones at
+/// least the `Origin` of a `POST`, which has to match the host of the
+/// request. Requests with neither header are rejected.
+#[cfg(feature = "ssr")]
+fn is_same_origin(parts: &http::request::Parts) -> bool {
+    if let Some(site) = parts.headers.get("sec-fetch-site") {
+        return site.as_bytes() == b"same-origin";
+    }
+
+    let host = parts
+        .headers
+        .get(http::header::HOST)
+        .and_then(|host| host.to_str().ok())
+        .or_else(|| parts.uri.authority().map(http::uri::Authority::as_str));
+    let origin = parts
+        .headers
+        .get(http::header::ORIGIN)
+        .and_then(|origin| origin.to_str().ok())
+        .and_then(|origin| url::Url::parse(origin).ok())
+        .and_then(|origin| {
+            let host = origin.host_str()?;
+            Some(match origin.port() {
+                Some(port) => format!("{host}:{port}"),
+                None => host.to_string(),
+            })
+        });
+
+    matches!((origin, host), (Some(origin), Some(host)) if origin.eq_ignore_ascii_case(host))
+}
+
+/// Writes the token storage into the `HttpOnly` cookie, or removes the cookie
+/// if there is no token storage. Values which aren't a token storage are
+/// rejected, as well as requests of other sites (see `is_same_origin`), so
+/// they can't log the user in or out.
+///
+/// # Errors
+///
+/// Returns an error if the request isn't sent by the app itself, the token
+/// storage is invalid or the request parts or response options aren't
+/// available on the server.
+#[server(WriteAuthCookie, "/api")]
+pub async fn write_auth_cookie(token_storage_json: Option<String>) -> Result<(), ServerFnError> {
+    use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+
+    use crate::compression::decompress;
+
+    let parts = leptos::use_context::<http::request::Parts>()
+        .ok_or_else(|| ServerFnError::ServerError("missing request parts".to_string()))?;
+    if !is_same_origin(&parts) {
+        return Err(ServerFnError::ServerError("cross-site request".to_string()));
+    }
+    let response = leptos::use_context::<leptos_axum::ResponseOptions>()
+        .ok_or_else(|| ServerFnError::ServerError("missing response options".to_string()))?;
+    let cookie = match token_storage_json {
+        Some(token_storage_json) => {
+            let is_token_storage = decompress(token_storage_json.clone())
+                .ok()
+                .and_then(|json| serde_json::from_str::<TokenStorage>(&json).ok())
+                .is_some();
+            if !is_token_storage {
+                return Err(ServerFnError::ServerError(
+                    "invalid token storage".to_string(),
+                ));
+            }
+            format!(
+                "{AUTH_COOKIE_NAME}={}; Path=/; HttpOnly; Secure; SameSite=Lax",
+                URL_SAFE_NO_PAD.encode(token_storage_json)
+            )
+        }
+        None => {
+            format!("{AUTH_COOKIE_NAME}=; Path=/; HttpOnly; Secure; SameSite=Lax; Max-Age=0")
+        }
+    };
+    response.append_header(
+        http::header::SET_COOKIE,
+        http::HeaderValue::from_str(&cookie)?,
+    );
+
+    Ok(())
+}
@@ -0,0 +1,65 @@
+/*
+* The MIT License (MIT)
+*
+* Copyright (c) 2023 Daniél Kerkmann <daniel@kerkmann.dev>
+*
+* Permission is hereby granted, free of charge, to any person obtaining a copy
+* of this software and associated documentation files (the "Software"), to deal
+* in the Software without restriction, including without limitation the rights
+* to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+* copies of the Software, and to permit persons to whom the Software is
+* furnished to do so, subject to the following conditions:
+*
+* The above copyright notice and this permission notice shall be included in all
+* copies or substantial portions of the Software.
+*
+* THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+* IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+* FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+* AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+* LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+* OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+* SOFTWARE.
+*/
+
+use std::time::Duration;
+
+use leptos::{
+    create_rw_signal, on_cleanup, set_interval_with_handle, Signal, SignalGet, SignalSet,
+};
+
+use crate::{utils::now, Auth};
+
+/// The interval of the countdown of `Auth::expires_in_signal`.
+const COUNTDOWN_INTERVAL: Duration = Duration::from_secs(1);
+
+impl Auth {
+    /// Returns a signal of the time left until the user has to log in again,
+    /// updated every second, e.g. to render a "session expires in 4:59"
+    /// banner. That's the expiry of the refresh token or, without one, of the
+    /// access token. The signal is `None` if unauthenticated or if the
+    /// refresh token doesn't expire, and zero once expired. The countdown stops when the current reactive scope is
+    /// disposed.
+    #[must_use]
+    pub fn expires_in_signal(&self) -> Signal<Option<Duration>> {
+        let tick = create_rw_signal(now());
+        if leptos::leptos_dom::is_browser() {
+            let interval = set_interval_with_handle(move || tick.set(now()), COUNTDOWN_INTERVAL);
+            on_cleanup(move || {
+                if let Ok(interval) = interval {
+                    interval.clear();
+                }
+            });
+        }
+
+        let auth = self.clone();
+        Signal::derive(move || {
+            let tokens = auth.resource_state().and_then(Result::ok).flatten()?;
+            let expires_at = match (&tokens.refresh_token, tokens.refresh_expires_in) {
+                (Some(_), refresh_expires_in) => refresh_expires_in?,
+                (None, _) => tokens.expires_in,
+            };
+            Some((expires_at - tick.get()).to_std().unwrap_or(Duration::ZERO))
+        })
+    }
+}
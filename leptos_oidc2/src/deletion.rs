@@ -0,0 +1,100 @@
+/*
+* The MIT License (MIT)
+*
+* Copyright (c) 2023 Daniél Kerkmann <daniel@kerkmann.dev>
+*
+* Permission is hereby granted, free of charge, to any person obtaining a copy
+* of this software and associated documentation files (the "Software"), to deal
+* in the Software without restriction, including without limitation the rights
+* to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+* copies of the Software, and to permit persons to whom the Software is
+* furnished to do so, subject to the following conditions:
+*
+* The above copyright notice and this permission notice shall be included in all
+* copies or substantial portions of the Software.
+*
+* THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+* IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+* FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+* AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+* LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+* OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+* SOFTWARE.
+*/
+
+use leptos::window;
+
+use crate::{
+    client::HttpRequest,
+    error::{AuthConfigError, AuthError},
+    Auth, LogoutBehavior,
+};
+
+/// An enumeration representing the steps of `Auth::request_account_deletion`,
+/// e.g. to show the progress in a confirmation dialog.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AccountDeletionStep {
+    /// The backend deletes the account.
+    DeletingAccount,
+    /// The tokens are revoked at the provider.
+    RevokingTokens,
+    /// The local tokens are removed and the session at the provider is
+    /// ended.
+    LoggingOut,
+    /// The account was deleted, the browser is redirected to the provider.
+    Done,
+}
+
+impl Auth {
+    /// Deletes the account of the user and ends every session of it. The
+    /// steps run in this order, each one is reported to `progress`:
+    ///
+    /// 1. The `AuthParameters::account_deletion_endpoint` of the backend is
+    ///    called with a `DELETE` and the access token.
+    /// 2. The tokens are revoked at the revocation endpoint, a failure is
+    ///    only logged, because the account is gone already.
+    /// 3. The local tokens are removed and the browser is redirected to the
+    ///    end session endpoint of the provider, regardless of the
+    ///    `LogoutBehavior`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if no deletion endpoint is configured or the backend
+    /// rejected the deletion. The session is kept in that case.
+    pub async fn request_account_deletion(
+        &self,
+        progress: impl Fn(AccountDeletionStep),
+    ) -> Result<(), AuthError> {
+        let endpoint = self
+            .parameters
+            .account_deletion_endpoint
+            .clone()
+            .ok_or(AuthConfigError::Missing("account_deletion_endpoint"))?;
+
+        progress(AccountDeletionStep::DeletingAccount);
+        let response = self
+            .authenticated_fetch(HttpRequest::new("DELETE", endpoint))
+            .await?;
+        if !response.is_success() {
+            return Err(AuthError::AccountDeletion(response.status));
+        }
+
+        progress(AccountDeletionStep::RevokingTokens);
+        if let Err(error) = self.revoke_tokens().await {
+            leptos::logging::error!("Unable to revoke tokens of the deleted account: {error:#?}");
+        }
+
+        progress(AccountDeletionStep::LoggingOut);
+        // The end session URL needs the ID token, it's created before the
+        // local tokens are removed.
+        let mut provider = self.clone();
+        provider.parameters.logout_behavior = LogoutBehavior::Provider;
+        let logout_url = provider.logout_url();
+        self.local_logout();
+        window().location().set_href(&logout_url).ok();
+
+        progress(AccountDeletionStep::Done);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,374 @@
+/*
+* The MIT License (MIT)
+*
+* Copyright (c) 2023 Daniél Kerkmann <daniel@kerkmann.dev>
+*
+* Permission is hereby granted, free of charge, to any person obtaining a copy
+* of this software and associated documentation files (the "Software"), to deal
+* in the Software without restriction, including without limitation the rights
+* to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+* copies of the Software, and to permit persons to whom the Software is
+* furnished to do so, subject to the following conditions:
+*
+* The above copyright notice and this permission notice shall be included in all
+* copies or substantial portions of the Software.
+*
+* THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+* IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+* FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+* AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+* LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+* OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+* SOFTWARE.
+*/
+
+use std::{cell::RefCell, collections::HashMap};
+
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use futures::future::{FutureExt, LocalBoxFuture};
+use js_sys::{Array, Object, Promise, Reflect, Uint8Array};
+use leptos::{
+    spawn_local,
+    wasm_bindgen::{closure::Closure, JsCast, JsValue},
+    window,
+};
+use oidc_core::dpop::{dpop_nonce, proof_claims};
+use serde_json::{json, Value};
+use wasm_bindgen_futures::JsFuture;
+use web_sys::{
+    CryptoKey, Event, IdbDatabase, IdbObjectStore, IdbOpenDbRequest, IdbRequest,
+    IdbTransactionMode, SubtleCrypto,
+};
+
+use crate::{error::AuthError, Auth, AuthParameters, DpopProver, OidcError};
+
+/// The signing algorithm of the DPoP proofs, ECDSA with the P-256 curve.
+const DPOP_ALGORITHM: &str = "ES256";
+
+/// The name of the IndexedDB database of the DPoP key pairs. IndexedDB keeps
+/// the `CryptoKey` objects as they are, so the private key never leaves
+/// WebCrypto, unlike a key in the `Storage` backends.
+const KEY_DATABASE: &str = "leptos_oidc";
+
+/// The name of the object store of the key pairs.
+const KEY_STORE: &str = "dpop_keys";
+
+thread_local! {
+    /// The DPoP keys by their storage key, so the key is only loaded once per
+    /// page.
+    static KEYS: RefCell<HashMap<String, DpopKey>> = RefCell::default();
+}
+
+/// The key pair the DPoP proofs are signed with. The private key is
+/// generated as non-extractable, it can't be read out of WebCrypto.
+#[derive(Debug, Clone)]
+struct DpopKey {
+    private_key: CryptoKey,
+    public_jwk: Value,
+}
+
+impl Auth {
+    /// Creates a DPoP proof (RFC 9449) for a request to a resource server,
+    /// bound to the current access token. The request has to carry the
+    /// access token as `Authorization: DPoP <access_token>` and the proof as
+    /// `DPoP` header. `Auth::authenticated_fetch` does both with
+    /// `AuthParameters::dpop`. The last nonce of the server, if any, is
+    /// included.
+    ///
+    /// # Errors
+    ///
+    /// Returns `AuthError::Unauthenticated` without an access token and
+    /// `AuthError::Dpop` if WebCrypto isn't available or signing fails.
+    pub async fn dpop_proof(&self, method: &str, url: &str) -> Result<String, AuthError> {
+        let access_token = self
+            .current_tokens()
+            .ok_or(AuthError::Unauthenticated)?
+            .access_token;
+
+        Ok(create_proof(
+            &dpop_storage_key(&self.parameters),
+            method,
+            url,
+            Some(&access_token),
+            dpop_nonce(url).as_deref(),
+        )
+        .await?)
+    }
+}
+
+/// The `DpopProver` of `AuthParameters::dpop`, which signs the proofs of the
+/// token requests and `Auth::authenticated_fetch` with the key of the client.
+#[derive(Debug, Clone)]
+pub(crate) struct WebCryptoProver {
+    storage_key: String,
+}
+
+impl WebCryptoProver {
+    pub(crate) fn new(parameters: &AuthParameters) -> Self {
+        Self {
+            storage_key: dpop_storage_key(parameters),
+        }
+    }
+}
+
+impl DpopProver for WebCryptoProver {
+    fn proof<'a>(
+        &'a self,
+        method: &'a str,
+        url: &'a str,
+        access_token: Option<&'a str>,
+        nonce: Option<&'a str>,
+    ) -> LocalBoxFuture<'a, Result<String, OidcError>> {
+        create_proof(&self.storage_key, method, url, access_token, nonce).boxed_local()
+    }
+}
+
+/// Deletes the key pair of the client once the session ended, the tokens of
+/// the next session are bound to a new key. The key is removed from
+/// IndexedDB in the background.
+pub(crate) fn delete_key(parameters: &AuthParameters) {
+    if !leptos::leptos_dom::is_browser() {
+        return;
+    }
+    let storage_key = dpop_storage_key(parameters);
+    KEYS.with(|keys| keys.borrow_mut().remove(&storage_key));
+    spawn_local(async move {
+        if let Err(error) = delete_key_pair(&storage_key).await {
+            leptos::logging::error!("Unable to delete the DPoP key: {error:#?}");
+        }
+    });
+}
+
+/// Creates a DPoP proof for the given request, signed with the key stored
+/// under the storage key. The key is generated on first use and kept in
+/// IndexedDB, as non-extractable `CryptoKey`. The claims are the ones of
+/// `proof_claims`.
+async fn create_proof(
+    storage_key: &str,
+    method: &str,
+    url: &str,
+    access_token: Option<&str>,
+    nonce: Option<&str>,
+) -> Result<String, OidcError> {
+    let key = dpop_key(storage_key).await?;
+    let header = json!({
+        "typ": "dpop+jwt",
+        "alg": DPOP_ALGORITHM,
+        "jwk": key.public_jwk,
+    });
+    let claims = proof_claims(method, url, access_token, nonce)?;
+
+    let signing_input = format!(
+        "{}.{}",
+        URL_SAFE_NO_PAD.encode(header.to_string()),
+        URL_SAFE_NO_PAD.encode(claims.to_string()),
+    );
+    let signature = sign(&key.private_key, &signing_input).await?;
+
+    Ok(format!("{signing_input}.{signature}"))
+}
+
+/// Returns the DPoP key stored under the storage key, loading the stored one
+/// or generating a new one.
+async fn dpop_key(storage_key: &str) -> Result<DpopKey, OidcError> {
+    if let Some(key) = KEYS.with(|keys| keys.borrow().get(storage_key).cloned()) {
+        return Ok(key);
+    }
+
+    let key_pair = match load_key_pair(storage_key).await? {
+        Some(key_pair) => key_pair,
+        None => {
+            let key_pair = generate_key().await?;
+            store_key_pair(storage_key, &key_pair).await?;
+            key_pair
+        }
+    };
+    let key = DpopKey::from_key_pair(&key_pair).await?;
+    KEYS.with(|keys| {
+        keys.borrow_mut()
+            .insert(storage_key.to_string(), key.clone())
+    });
+
+    Ok(key)
+}
+
+/// Returns the key the DPoP key pair of the client is stored under.
+fn dpop_storage_key(parameters: &AuthParameters) -> String {
+    format!("{}_dpop", parameters.token_storage_key())
+}
+
+/// Generates a new key pair. The private key isn't extractable, it can only
+/// be used to sign within WebCrypto.
+async fn generate_key() -> Result<Object, OidcError> {
+    resolve(subtle_crypto()?.generate_key_with_object(
+        &key_algorithm()?,
+        false,
+        &Array::of2(&"sign".into(), &"verify".into()),
+    ))
+    .await?
+    .dyn_into::<Object>()
+    .map_err(dpop_error)
+}
+
+impl DpopKey {
+    /// Reads the keys out of the key pair and exports the public key, which
+    /// is extractable regardless of the private key.
+    async fn from_key_pair(key_pair: &Object) -> Result<Self, OidcError> {
+        let key = |name: &str| {
+            Reflect::get(key_pair, &name.into())
+                .map_err(dpop_error)?
+                .dyn_into::<CryptoKey>()
+                .map_err(dpop_error)
+        };
+        let private_key = key("privateKey")?;
+        let public_key = key("publicKey")?;
+        let jwk = resolve(subtle_crypto()?.export_key("jwk", &public_key)).await?;
+        let jwk = js_sys::JSON::stringify(&jwk)
+            .map(String::from)
+            .map_err(dpop_error)?;
+        let jwk = serde_json::from_str::<Value>(&jwk)
+            .map_err(|_| OidcError::Dpop("corrupted key".to_string()))?;
+
+        Ok(Self {
+            private_key,
+            public_jwk: json!({
+                "kty": jwk["kty"],
+                "crv": jwk["crv"],
+                "x": jwk["x"],
+                "y": jwk["y"],
+            }),
+        })
+    }
+}
+
+/// Returns the key pair stored under the key.
+async fn load_key_pair(key: &str) -> Result<Option<Object>, OidcError> {
+    let store = object_store(IdbTransactionMode::Readonly).await?;
+    let key_pair = request_result(&store.get(&JsValue::from(key)).map_err(dpop_error)?).await?;
+
+    Ok(key_pair.dyn_into::<Object>().ok())
+}
+
+/// Stores the key pair under the key.
+async fn store_key_pair(key: &str, key_pair: &Object) -> Result<(), OidcError> {
+    let store = object_store(IdbTransactionMode::Readwrite).await?;
+    request_result(
+        &store
+            .put_with_key(key_pair, &JsValue::from(key))
+            .map_err(dpop_error)?,
+    )
+    .await?;
+
+    Ok(())
+}
+
+/// Deletes the key pair stored under the key.
+async fn delete_key_pair(key: &str) -> Result<(), OidcError> {
+    let store = object_store(IdbTransactionMode::Readwrite).await?;
+    request_result(&store.delete(&JsValue::from(key)).map_err(dpop_error)?).await?;
+
+    Ok(())
+}
+
+/// Opens the database, creating the object store on first use, and
+/// returns the store within a new transaction.
+async fn object_store(mode: IdbTransactionMode) -> Result<IdbObjectStore, OidcError> {
+    let factory = window()
+        .indexed_db()
+        .map_err(dpop_error)?
+        .ok_or_else(|| OidcError::Dpop("IndexedDB isn't available".to_string()))?;
+    let request: IdbOpenDbRequest = factory.open_with_u32(KEY_DATABASE, 1).map_err(dpop_error)?;
+    // The store can only be created within the upgrade event itself.
+    let on_upgrade = Closure::<dyn FnMut(Event)>::new(move |event: Event| {
+        let database = event
+            .target()
+            .and_then(|target| target.dyn_into::<IdbRequest>().ok())
+            .and_then(|request| request.result().ok())
+            .and_then(|database| database.dyn_into::<IdbDatabase>().ok());
+        if let Some(database) = database {
+            if let Err(error) = database.create_object_store(KEY_STORE) {
+                leptos::logging::error!("Unable to create the DPoP key store: {error:?}");
+            }
+        }
+    });
+    request.set_onupgradeneeded(Some(on_upgrade.as_ref().unchecked_ref()));
+    let database = request_result(&request).await;
+    request.set_onupgradeneeded(None);
+    let database = database?.dyn_into::<IdbDatabase>().map_err(dpop_error)?;
+
+    database
+        .transaction_with_str_and_mode(KEY_STORE, mode)
+        .and_then(|transaction| transaction.object_store(KEY_STORE))
+        .map_err(dpop_error)
+}
+
+/// Waits for the request to succeed and returns its result.
+async fn request_result(request: &IdbRequest) -> Result<JsValue, OidcError> {
+    let promise = Promise::new(&mut |resolve, reject| {
+        request.set_onsuccess(Some(&resolve));
+        request.set_onerror(Some(&reject));
+    });
+    let result = JsFuture::from(promise).await;
+    request.set_onsuccess(None);
+    request.set_onerror(None);
+    result.map_err(|_| {
+        OidcError::Dpop(format!(
+            "IndexedDB request failed: {:?}",
+            request.error().ok().flatten()
+        ))
+    })?;
+
+    request.result().map_err(dpop_error)
+}
+
+/// Signs the input and returns the signature encoded as base64url. ECDSA
+/// signatures of WebCrypto already have the JWS format.
+async fn sign(private_key: &CryptoKey, input: &str) -> Result<String, OidcError> {
+    let algorithm = algorithm_object(&[("name", "ECDSA"), ("hash", "SHA-256")])?;
+    let data = Uint8Array::from(input.as_bytes());
+    let signature = resolve(subtle_crypto()?.sign_with_object_and_buffer_source(
+        &algorithm,
+        private_key,
+        &data,
+    ))
+    .await?;
+
+    Ok(URL_SAFE_NO_PAD.encode(Uint8Array::new(&signature).to_vec()))
+}
+
+/// Returns the algorithm of the DPoP key pair.
+fn key_algorithm() -> Result<Object, OidcError> {
+    algorithm_object(&[("name", "ECDSA"), ("namedCurve", "P-256")])
+}
+
+/// Builds a WebCrypto algorithm object of the given properties.
+fn algorithm_object(properties: &[(&str, &str)]) -> Result<Object, OidcError> {
+    let algorithm = Object::new();
+    for (key, value) in properties {
+        Reflect::set(&algorithm, &(*key).into(), &(*value).into()).map_err(dpop_error)?;
+    }
+
+    Ok(algorithm)
+}
+
+/// Returns the WebCrypto API of the browser.
+fn subtle_crypto() -> Result<SubtleCrypto, OidcError> {
+    if !leptos::leptos_dom::is_browser() {
+        return Err(OidcError::Dpop("WebCrypto isn't available".to_string()));
+    }
+    window()
+        .crypto()
+        .map(|crypto| crypto.subtle())
+        .map_err(dpop_error)
+}
+
+/// Awaits the promise of a WebCrypto call.
+async fn resolve(promise: Result<js_sys::Promise, JsValue>) -> Result<JsValue, OidcError> {
+    JsFuture::from(promise.map_err(dpop_error)?)
+        .await
+        .map_err(dpop_error)
+}
+
+fn dpop_error(error: JsValue) -> OidcError {
+    OidcError::Dpop(format!("{error:?}"))
+}
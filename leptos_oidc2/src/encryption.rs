@@ -0,0 +1,173 @@
+/*
+* The MIT License (MIT)
+*
+* Copyright (c) 2023 Daniél Kerkmann <daniel@kerkmann.dev>
+*
+* Permission is hereby granted, free of charge, to any person obtaining a copy
+* of this software and associated documentation files (the "Software"), to deal
+* in the Software without restriction, including without limitation the rights
+* to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+* copies of the Software, and to permit persons to whom the Software is
+* furnished to do so, subject to the following conditions:
+*
+* The above copyright notice and this permission notice shall be included in all
+* copies or substantial portions of the Software.
+*
+* THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+* IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+* FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+* AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+* LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+* OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+* SOFTWARE.
+*/
+
+use std::fmt::{self, Debug};
+
+use aes_gcm::{
+    aead::{Aead, KeyInit},
+    Aes256Gcm, Nonce,
+};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use sha2::{Digest, Sha256};
+
+use crate::{
+    error::{AuthError, StorageErrorKind},
+    storage::{Storage, StorageKind},
+};
+
+/// The prefix marking an encrypted value. Unencrypted values of older
+/// versions are still read and encrypted on the next write.
+const ENCRYPTED_PREFIX: &str = "aes-gcm:";
+
+/// The length of the AES-GCM nonce in bytes.
+const NONCE_LENGTH: usize = 12;
+
+/// A 256 bit key of the `EncryptedStorage`.
+#[derive(Clone, PartialEq, Eq)]
+pub struct EncryptionKey([u8; 32]);
+
+impl EncryptionKey {
+    /// Creates the key out of 32 random bytes.
+    #[must_use]
+    pub fn from_bytes(bytes: [u8; 32]) -> Self {
+        Self(bytes)
+    }
+
+    /// Derives the key from a per-app secret with SHA-256. A secret compiled
+    /// into the app ships with the WASM bundle, anybody can derive the key
+    /// from it.
+    #[must_use]
+    pub fn derive(secret: &str) -> Self {
+        Self(Sha256::digest(format!("leptos_oidc2:{secret}")).into())
+    }
+}
+
+/// The key is never printed.
+impl Debug for EncryptionKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("EncryptionKey(..)")
+    }
+}
+
+/// A storage backend encrypting the values of another backend with AES-GCM,
+/// e.g. the tokens in the local storage. This only obfuscates the stored
+/// tokens: the key lives in the app, a key derived from a compiled-in secret
+/// ships with the WASM bundle. Anybody with a dump of the storage and the
+/// bundle, like a stolen browser profile, can decrypt the tokens, and scripts
+/// running in the app can use the key anyway. It keeps the tokens from being
+/// read in plain text, e.g. by tools scanning the storage, but it's no
+/// protection at rest and doesn't replace a content security policy.
+pub struct EncryptedStorage {
+    inner: &'static dyn Storage,
+    cipher: Aes256Gcm,
+}
+
+impl EncryptedStorage {
+    /// Creates a storage encrypting the values of the given backend with the
+    /// given key.
+    #[must_use]
+    pub fn new(inner: &'static dyn Storage, key: &EncryptionKey) -> Self {
+        Self {
+            inner,
+            cipher: Aes256Gcm::new(&key.0.into()),
+        }
+    }
+
+    /// Encrypts the value with a random nonce, which is prepended to the
+    /// ciphertext.
+    fn encrypt(&self, value: &str) -> Result<String, AuthError> {
+        let mut nonce = [0u8; NONCE_LENGTH];
+        getrandom::getrandom(&mut nonce).map_err(|_| AuthError::Random)?;
+        let ciphertext = self
+            .cipher
+            .encrypt(Nonce::from_slice(&nonce), value.as_bytes())
+            .map_err(|_| AuthError::Storage(StorageErrorKind::Write))?;
+
+        let mut payload = nonce.to_vec();
+        payload.extend(ciphertext);
+        Ok(format!(
+            "{ENCRYPTED_PREFIX}{}",
+            URL_SAFE_NO_PAD.encode(payload)
+        ))
+    }
+
+    /// Decrypts the value, if it's encrypted. Unencrypted values are returned
+    /// as is.
+    fn decrypt(&self, value: String) -> Result<String, AuthError> {
+        let Some(encoded) = value.strip_prefix(ENCRYPTED_PREFIX) else {
+            return Ok(value);
+        };
+        let payload = URL_SAFE_NO_PAD
+            .decode(encoded)
+            .map_err(|_| AuthError::Storage(StorageErrorKind::Corrupted))?;
+        if payload.len() < NONCE_LENGTH {
+            return Err(AuthError::Storage(StorageErrorKind::Corrupted));
+        }
+        let (nonce, ciphertext) = payload.split_at(NONCE_LENGTH);
+        let plaintext = self
+            .cipher
+            .decrypt(Nonce::from_slice(nonce), ciphertext)
+            .map_err(|_| AuthError::Storage(StorageErrorKind::Corrupted))?;
+
+        String::from_utf8(plaintext).map_err(|_| AuthError::Storage(StorageErrorKind::Corrupted))
+    }
+}
+
+impl Debug for EncryptedStorage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("EncryptedStorage")
+            .field("inner", &self.inner)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Storage for EncryptedStorage {
+    fn get(&self, key: &str) -> Result<Option<String>, AuthError> {
+        self.inner
+            .get(key)?
+            .map(|value| self.decrypt(value))
+            .transpose()
+    }
+
+    fn set(&self, key: &str, value: &str) -> Result<(), AuthError> {
+        self.inner.set(key, &self.encrypt(value)?)
+    }
+
+    fn remove(&self, key: &str) -> Result<(), AuthError> {
+        self.inner.remove(key)
+    }
+}
+
+impl StorageKind {
+    /// Returns a custom storage kind, which encrypts the values of this kind
+    /// with the given key, see `EncryptedStorage`. The storage lives as long
+    /// as the app, call this once on startup.
+    #[must_use]
+    pub fn encrypted(self, key: &EncryptionKey) -> StorageKind {
+        StorageKind::Custom(Box::leak(Box::new(EncryptedStorage::new(
+            self.backend(),
+            key,
+        ))))
+    }
+}
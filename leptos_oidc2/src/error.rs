@@ -24,6 +24,8 @@
 
 use std::sync::Arc;
 
+use leptos_router::ParamsError;
+use oidc_core::OidcError;
 use thiserror::Error;
 
 use crate::response::ErrorResponse;
@@ -31,23 +33,340 @@ use crate::response::ErrorResponse;
 /// An enumeration representing various authentication-related errors.
 #[derive(Debug, Clone, Error)]
 pub enum AuthError {
-    /// An error caused by the authentication provider.
+    /// An error caused by the authentication provider, which has no dedicated
+    /// variant.
     #[error("provider error {0:?}")]
     Provider(ErrorResponse),
 
-    /// An error related to a network request.
-    #[error("request error: {0}")]
-    Request(#[from] Arc<reqwest::Error>),
+    /// The provider rejected the authorization code or the refresh token
+    /// (`invalid_grant`), e.g. because it expired or was revoked.
+    #[error("invalid grant {0:?}")]
+    InvalidGrant(ErrorResponse),
 
-    /// An error related to handling parameters.
-    #[error("params error: {0}")]
-    Params(#[from] leptos_router::ParamsError),
+    /// The provider requires the user to log in (`login_required`).
+    #[error("login required {0:?}")]
+    LoginRequired(ErrorResponse),
+
+    /// The provider requires the consent of the user (`consent_required`).
+    #[error("consent required {0:?}")]
+    ConsentRequired(ErrorResponse),
+
+    /// An error indicating that a request of the `HttpClient` didn't get a
+    /// response, e.g. offline, after a timeout or blocked by CORS.
+    #[error("network error: {0}")]
+    Network(String),
+
+    /// An error indicating that the server answered with the given error
+    /// status.
+    #[error("request failed with status {0}")]
+    Http(u16),
+
+    /// An error indicating that the callback parameters are missing or
+    /// invalid.
+    #[error("invalid callback: {0}")]
+    InvalidCallback(#[from] ParamsError),
 
     /// An error related to the serialization or deserialization of JSON data.
     #[error("failed to serialize/deserialilze json: {0}")]
     Serde(#[from] Arc<serde_json::Error>),
 
-    /// An error indicating the inability to initialize local storage.
-    #[error("unable to initialize local storage")]
-    Storage,
+    /// An error returned by a server function, e.g. reading the auth cookie.
+    #[error("server error: {0}")]
+    Server(#[from] leptos::ServerFnError),
+
+    /// An error indicating the inability to access the storage.
+    #[error("unable to access the storage ({0:?})")]
+    Storage(StorageErrorKind),
+
+    /// An error indicating an invalid configuration.
+    #[error("invalid configuration: {0}")]
+    ConfigInvalid(#[from] AuthConfigError),
+
+    /// An error indicating that the provider metadata is missing a required
+    /// field.
+    #[error("provider metadata is missing '{0}'")]
+    Discovery(&'static str),
+
+    /// An error related to decoding or validating a JWT.
+    #[error("token error: {0}")]
+    Token(#[from] Arc<jsonwebtoken::errors::Error>),
+
+    /// An error indicating that the signature of a JWT is invalid.
+    #[error("invalid token signature")]
+    SignatureInvalid,
+
+    /// An error indicating that a token is expired.
+    #[error("token expired")]
+    TokenExpired,
+
+    /// An error indicating that the provider rejected a rotated refresh token,
+    /// most likely because it was already used elsewhere, e.g. by another
+    /// tab. The provider revoked the grant, the session ended.
+    #[error("refresh token reused, the session was ended")]
+    RefreshTokenReused,
+
+    /// An error indicating that refreshing the tokens failed.
+    #[error("refresh failed: {cause}")]
+    RefreshFailed {
+        #[source]
+        cause: Box<AuthError>,
+    },
+
+    /// An error indicating that the JSON Web Key Set doesn't contain the key
+    /// the token was signed with.
+    #[error("no key found for kid {0:?}")]
+    UnknownKey(Option<String>),
+
+    /// An error indicating that a token is not a well-formed JWT.
+    #[error("malformed token")]
+    MalformedToken,
+
+    /// An error indicating that the header of a JWT declares an algorithm,
+    /// which isn't supported, so its signature can't be verified.
+    #[error("unsupported token algorithm '{0}'")]
+    UnsupportedAlgorithm(String),
+
+    /// An error indicating that the `nonce` claim of the ID token doesn't
+    /// match the nonce sent with the authentication request.
+    #[error("nonce mismatch")]
+    Nonce,
+
+    /// An error indicating that a hash claim of the ID token (`at_hash` or
+    /// `c_hash`) doesn't match the access token or the code returned with it
+    /// by the implicit or hybrid flow.
+    #[error("{0} mismatch")]
+    TokenHash(&'static str),
+
+    /// An error indicating that a front-channel logout request doesn't
+    /// belong to this client or session.
+    #[error("invalid logout request: {0}")]
+    LogoutRequest(&'static str),
+
+    /// An error indicating the inability to generate random data, e.g. for
+    /// the PKCE code verifier.
+    #[error("unable to generate random data")]
+    Random,
+
+    /// An error indicating that the token response of a login is missing the
+    /// ID token, e.g. because the `openid` scope wasn't requested.
+    #[error("token response is missing the id_token")]
+    MissingIdToken,
+
+    /// An error indicating that the operation requires an authenticated user.
+    #[error("not authenticated")]
+    Unauthenticated,
+
+    /// An error indicating that a silent authentication in an iframe failed,
+    /// because the provider didn't answer in time. Usually the browser blocks
+    /// the iframe or third-party cookies, see `Auth::silent_login`.
+    #[error("third-party cookies are blocked")]
+    ThirdPartyCookiesBlocked,
+
+    /// An error indicating the inability to access or modify the document.
+    #[error("unable to access the document")]
+    Document,
+
+    /// An error indicating that the backend rejected the account deletion
+    /// with the given HTTP status.
+    #[error("account deletion failed with status {0}")]
+    AccountDeletion(u16),
+
+    /// An error of the push API of the browser, see
+    /// `Auth::sync_push_subscription`.
+    #[error("web push failed: {0}")]
+    Push(String),
+
+    /// An error of creating a DPoP proof, e.g. because WebCrypto isn't
+    /// available, see `AuthParameters::dpop`.
+    #[error("DPoP failed: {0}")]
+    Dpop(String),
+
+    /// An error indicating that the provider issued a handoff code of
+    /// another token type than `HANDOFF_TOKEN_TYPE`, e.g. an access token,
+    /// which must not be handed off, see `Auth::create_handoff`.
+    #[error("unexpected handoff token type '{0}'")]
+    HandoffTokenType(String),
+}
+
+impl AuthError {
+    /// Converts an error response of the provider, well-known OAuth error
+    /// codes are mapped to dedicated variants.
+    #[must_use]
+    pub fn provider(response: ErrorResponse) -> Self {
+        match response.error.as_str() {
+            "invalid_grant" => AuthError::InvalidGrant(response),
+            "login_required" => AuthError::LoginRequired(response),
+            "consent_required" => AuthError::ConsentRequired(response),
+            _ => AuthError::Provider(response),
+        }
+    }
+
+    /// Converts an error of decoding or validating a JWT, an invalid signature
+    /// and an expired token are mapped to dedicated variants.
+    #[must_use]
+    pub fn token(error: jsonwebtoken::errors::Error) -> Self {
+        match error.kind() {
+            jsonwebtoken::errors::ErrorKind::InvalidSignature => AuthError::SignatureInvalid,
+            jsonwebtoken::errors::ErrorKind::ExpiredSignature => AuthError::TokenExpired,
+            _ => AuthError::Token(Arc::new(error)),
+        }
+    }
+
+    /// Returns the error response of the provider, if the error was caused
+    /// by the provider.
+    #[must_use]
+    pub fn provider_error(&self) -> Option<&ErrorResponse> {
+        match self {
+            AuthError::Provider(response)
+            | AuthError::InvalidGrant(response)
+            | AuthError::LoginRequired(response)
+            | AuthError::ConsentRequired(response) => Some(response),
+            AuthError::RefreshFailed { cause } => cause.provider_error(),
+            _ => None,
+        }
+    }
+
+    /// Returns `true` if the error is likely transient, like a network error,
+    /// a timeout or a server error of the provider, and worth a retry.
+    #[must_use]
+    pub fn is_transient(&self) -> bool {
+        match self {
+            // The request never got a response of the provider.
+            AuthError::Network(_) => true,
+            AuthError::Http(status) => *status >= 500,
+            AuthError::RefreshFailed { cause } => cause.is_transient(),
+            _ => false,
+        }
+    }
+}
+
+/// Converts the errors of the protocol logic of `oidc_core`.
+impl From<OidcError> for AuthError {
+    fn from(error: OidcError) -> Self {
+        match error {
+            OidcError::Provider(response) => AuthError::Provider(response),
+            OidcError::InvalidGrant(response) => AuthError::InvalidGrant(response),
+            OidcError::LoginRequired(response) => AuthError::LoginRequired(response),
+            OidcError::ConsentRequired(response) => AuthError::ConsentRequired(response),
+            OidcError::Network(message) => AuthError::Network(message),
+            OidcError::Http(status) => AuthError::Http(status),
+            OidcError::InvalidCallback(message) => {
+                AuthError::InvalidCallback(ParamsError::MissingParam(message))
+            }
+            OidcError::Serde(error) => AuthError::Serde(error),
+            OidcError::MissingParameter(parameter) => {
+                AuthError::ConfigInvalid(AuthConfigError::Missing(parameter))
+            }
+            OidcError::Discovery(field) => AuthError::Discovery(field),
+            OidcError::Token(error) => AuthError::Token(error),
+            OidcError::SignatureInvalid => AuthError::SignatureInvalid,
+            OidcError::TokenExpired => AuthError::TokenExpired,
+            OidcError::UnknownKey(kid) => AuthError::UnknownKey(kid),
+            OidcError::MalformedToken => AuthError::MalformedToken,
+            OidcError::UnsupportedAlgorithm(algorithm) => {
+                AuthError::UnsupportedAlgorithm(algorithm)
+            }
+            OidcError::Nonce => AuthError::Nonce,
+            OidcError::TokenHash(claim) => AuthError::TokenHash(claim),
+            OidcError::Random => AuthError::Random,
+            OidcError::MissingIdToken => AuthError::MissingIdToken,
+            OidcError::Dpop(message) => AuthError::Dpop(message),
+        }
+    }
+}
+
+/// An enumeration representing the storage operation, which failed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StorageErrorKind {
+    /// The storage isn't available, e.g. outside of the browser or in a
+    /// private window.
+    Unavailable,
+    /// Reading a value failed.
+    Read,
+    /// Writing a value failed, e.g. because the quota is exceeded.
+    Write,
+    /// Removing a value failed.
+    Remove,
+    /// A stored value is corrupted and can't be decoded.
+    Corrupted,
+}
+
+/// A trait for errors which might be resolved by refreshing the access token
+/// and retrying, e.g. a `401 Unauthorized` response of an API.
+pub trait AuthFailure {
+    /// Returns `true` if the error is caused by a missing, invalid or expired
+    /// access token.
+    fn is_auth_failure(&self) -> bool;
+}
+
+/// Errors of an ended session, like a revoked grant, aren't resolved by a
+/// refresh and aren't auth failures.
+impl AuthFailure for AuthError {
+    fn is_auth_failure(&self) -> bool {
+        match self {
+            AuthError::Provider(response) => response.error == "invalid_token",
+            AuthError::Unauthenticated | AuthError::TokenExpired => true,
+            AuthError::Http(status) => *status == 401,
+            _ => false,
+        }
+    }
+}
+
+#[cfg(feature = "reqwest")]
+impl AuthFailure for reqwest::Error {
+    fn is_auth_failure(&self) -> bool {
+        self.status() == Some(reqwest::StatusCode::UNAUTHORIZED)
+    }
+}
+
+impl<T: AuthFailure> AuthFailure for Arc<T> {
+    fn is_auth_failure(&self) -> bool {
+        self.as_ref().is_auth_failure()
+    }
+}
+
+/// An enumeration representing errors of an invalid configuration, returned
+/// by the `AuthParametersBuilder` and `AuthParameters::validate`.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum AuthConfigError {
+    /// A required parameter wasn't set.
+    #[error("missing required parameter '{0}'")]
+    Missing(&'static str),
+
+    /// A parameter isn't a valid absolute URL.
+    #[error("invalid url for '{field}': {value}")]
+    InvalidUrl { field: &'static str, value: String },
+
+    /// A URL uses plain `http` for a host other than `localhost`.
+    #[error("'{field}' must use https, http is only allowed for localhost: {value}")]
+    InsecureUrl { field: &'static str, value: String },
+
+    /// A redirect URI has another origin than the `redirect_uri`.
+    #[error("'{field}' must have the origin of the redirect_uri ({expected}): {value}")]
+    OriginMismatch {
+        field: &'static str,
+        value: String,
+        expected: String,
+    },
+
+    /// The scope doesn't request an ID token.
+    #[error("the scope must contain 'openid', otherwise the provider issues no ID token")]
+    MissingOpenidScope,
+
+    /// Two options contradict each other.
+    #[error("conflicting options: {0}")]
+    Conflict(&'static str),
+
+    /// Several problems, see `AuthParameters::validate`.
+    #[error("invalid configuration: {}", join_problems(.0))]
+    Invalid(Vec<AuthConfigError>),
+}
+
+/// Joins the problems of an invalid configuration into one message.
+fn join_problems(problems: &[AuthConfigError]) -> String {
+    problems
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join("; ")
 }
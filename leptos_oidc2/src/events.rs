@@ -0,0 +1,113 @@
+/*
+* The MIT License (MIT)
+*
+* Copyright (c) 2023 Daniél Kerkmann <daniel@kerkmann.dev>
+*
+* Permission is hereby granted, free of charge, to any person obtaining a copy
+* of this software and associated documentation files (the "Software"), to deal
+* in the Software without restriction, including without limitation the rights
+* to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+* copies of the Software, and to permit persons to whom the Software is
+* furnished to do so, subject to the following conditions:
+*
+* The above copyright notice and this permission notice shall be included in all
+* copies or substantial portions of the Software.
+*
+* THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+* IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+* FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+* AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+* LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+* OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+* SOFTWARE.
+*/
+
+use std::{rc::Rc, time::Duration};
+
+use leptos::{create_effect, SignalGet};
+
+use crate::{current_state, error::AuthError, storage::TokenStorage, Auth};
+
+/// An enumeration representing a transition of the session, see
+/// `Auth::subscribe`. Unlike the recorded `AuthEvent`s of the audit log, these
+/// are derived from the state and delivered to the subscribers right away.
+#[derive(Debug, Clone)]
+pub enum SessionEvent {
+    /// The user logged in, or a stored session was restored on startup.
+    Login,
+    /// The user logged out.
+    Logout,
+    /// The tokens were refreshed, the access token rotated.
+    TokenRefreshed,
+    /// Refreshing the tokens failed with the given error.
+    RefreshFailed(AuthError),
+    /// The session ended, because the refresh token expired.
+    SessionExpired,
+    /// The user wasn't active for the `IdleTimeout`.
+    Idle,
+}
+
+impl Auth {
+    /// Subscribes to the transitions of the session, e.g. to clear caches or
+    /// to reconnect a websocket whenever the access token rotates. The
+    /// subscription lives as long as the current reactive owner, e.g. the
+    /// component calling this.
+    pub fn subscribe(&self, callback: impl Fn(&SessionEvent) + 'static) {
+        let callback = Rc::new(callback);
+        let idle = self.idle;
+        create_effect({
+            let callback = Rc::clone(&callback);
+            move |_| {
+                if idle.get() {
+                    callback(&SessionEvent::Idle);
+                }
+            }
+        });
+
+        let (resource, server_state) = (self.resource, self.server_state);
+        let leeway = self.parameters.leeway;
+        create_effect(move |previous: Option<Option<TokenStorage>>| {
+            let previous = previous.flatten();
+            let current = match current_state(resource, server_state) {
+                // The state is (re)loading, the transition is emitted once
+                // it's loaded.
+                None => return previous,
+                Some(Err(error)) => {
+                    if matches!(
+                        error,
+                        AuthError::RefreshFailed { .. } | AuthError::RefreshTokenReused
+                    ) {
+                        callback(&SessionEvent::RefreshFailed(error));
+                    }
+                    return previous;
+                }
+                Some(Ok(current)) => current,
+            };
+
+            if let Some(event) = transition(previous.as_ref(), current.as_ref(), leeway) {
+                callback(&event);
+            }
+            current
+        });
+    }
+}
+
+/// Returns the event of the transition between the given tokens, if any.
+fn transition(
+    previous: Option<&TokenStorage>,
+    current: Option<&TokenStorage>,
+    leeway: Duration,
+) -> Option<SessionEvent> {
+    match (previous, current) {
+        (None, Some(_)) => Some(SessionEvent::Login),
+        (Some(previous), None) => Some(if previous.refresh_token_expired_with(leeway) {
+            SessionEvent::SessionExpired
+        } else {
+            SessionEvent::Logout
+        }),
+        (Some(previous), Some(current)) if previous.access_token != current.access_token => {
+            Some(SessionEvent::TokenRefreshed)
+        }
+        _ => None,
+    }
+}
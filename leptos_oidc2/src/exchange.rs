@@ -0,0 +1,68 @@
+/*
+* The MIT License (MIT)
+*
+* Copyright (c) 2023 Daniél Kerkmann <daniel@kerkmann.dev>
+*
+* Permission is hereby granted, free of charge, to any person obtaining a copy
+* of this software and associated documentation files (the "Software"), to deal
+* in the Software without restriction, including without limitation the rights
+* to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+* copies of the Software, and to permit persons to whom the Software is
+* furnished to do so, subject to the following conditions:
+*
+* The above copyright notice and this permission notice shall be included in all
+* copies or substantial portions of the Software.
+*
+* THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+* IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+* FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+* AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+* LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+* OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+* SOFTWARE.
+*/
+
+use chrono::Duration;
+
+use crate::{error::AuthError, scopes::Scopes, token_cache::TokenCacheKey, Auth};
+
+pub use oidc_core::exchange::{ExchangedToken, TokenTarget};
+
+impl Auth {
+    /// Exchanges the subject token for a token of the given downstream
+    /// service, see `OidcClient::exchange_token`. Pass the access token of
+    /// the user (`Auth::access_token`) as subject token to call a
+    /// microservice on their behalf, `Auth::access_token_for` does this
+    /// implicitly.
+    ///
+    /// The exchanged tokens are kept in the `TokenCache` until they expire,
+    /// as long as the same subject token is passed, apart from the tokens of
+    /// `Auth::access_token_for`. Tokens without expiry aren't cached.
+    ///
+    /// # Errors
+    ///
+    /// Returns the provider error of the token endpoint or a failed request,
+    /// and `AuthError::Unauthenticated` if the user logged out meanwhile.
+    pub async fn exchange_token(
+        &self,
+        target: &TokenTarget,
+        subject_token: &str,
+    ) -> Result<ExchangedToken, AuthError> {
+        let key = TokenCacheKey::for_subject(target.clone(), subject_token);
+        let generation = self.token_cache.generation();
+        let leeway =
+            Duration::from_std(self.parameters.leeway).unwrap_or_else(|_| Duration::zero());
+        if let Some(token) = self.token_cache.fresh(&key, Some(subject_token), leeway) {
+            return Ok(token);
+        }
+
+        let token = self
+            .oidc_client()
+            .exchange_token(target, subject_token, &Scopes::new())
+            .await?;
+        self.token_cache
+            .insert(generation, key, subject_token.to_string(), token.clone())?;
+
+        Ok(token)
+    }
+}
@@ -0,0 +1,113 @@
+/*
+* The MIT License (MIT)
+*
+* Copyright (c) 2023 Daniél Kerkmann <daniel@kerkmann.dev>
+*
+* Permission is hereby granted, free of charge, to any person obtaining a copy
+* of this software and associated documentation files (the "Software"), to deal
+* in the Software without restriction, including without limitation the rights
+* to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+* copies of the Software, and to permit persons to whom the Software is
+* furnished to do so, subject to the following conditions:
+*
+* The above copyright notice and this permission notice shall be included in all
+* copies or substantial portions of the Software.
+*
+* THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+* IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+* FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+* AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+* LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+* OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+* SOFTWARE.
+*/
+
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use crate::{
+    error::{AuthError, StorageErrorKind},
+    storage::Storage,
+};
+
+/// A storage wrapper, which fails reads, writes or removals on demand, e.g. to
+/// test how an app handles a full or unavailable storage. Every other call is
+/// passed to the wrapped storage.
+///
+/// ```rust,ignore
+/// static STORAGE: FaultyStorage = FaultyStorage::new(&InMemoryStorage);
+///
+/// let parameters = AuthParameters {
+///     storage: StorageKind::Custom(&STORAGE),
+///     ..parameters
+/// };
+/// STORAGE.fail_writes(true);
+/// ```
+#[derive(Debug)]
+pub struct FaultyStorage {
+    inner: &'static (dyn Storage + Sync),
+    fail_reads: AtomicBool,
+    fail_writes: AtomicBool,
+    fail_removes: AtomicBool,
+}
+
+impl FaultyStorage {
+    /// Wraps the given storage, without failing any calls yet. The storage
+    /// has to be `Sync`, so the wrapper can be kept in a `static`.
+    #[must_use]
+    pub const fn new(inner: &'static (dyn Storage + Sync)) -> Self {
+        Self {
+            inner,
+            fail_reads: AtomicBool::new(false),
+            fail_writes: AtomicBool::new(false),
+            fail_removes: AtomicBool::new(false),
+        }
+    }
+
+    /// Fails every read with `StorageErrorKind::Read` while enabled.
+    pub fn fail_reads(&self, fail: bool) {
+        self.fail_reads.store(fail, Ordering::Relaxed);
+    }
+
+    /// Fails every write with `StorageErrorKind::Write` while enabled.
+    pub fn fail_writes(&self, fail: bool) {
+        self.fail_writes.store(fail, Ordering::Relaxed);
+    }
+
+    /// Fails every removal with `StorageErrorKind::Remove` while enabled.
+    pub fn fail_removes(&self, fail: bool) {
+        self.fail_removes.store(fail, Ordering::Relaxed);
+    }
+
+    /// Passes every call to the wrapped storage again.
+    pub fn heal(&self) {
+        self.fail_reads(false);
+        self.fail_writes(false);
+        self.fail_removes(false);
+    }
+}
+
+/// Returns the given error if the fault is enabled.
+fn check(fault: &AtomicBool, kind: StorageErrorKind) -> Result<(), AuthError> {
+    if fault.load(Ordering::Relaxed) {
+        Err(AuthError::Storage(kind))
+    } else {
+        Ok(())
+    }
+}
+
+impl Storage for FaultyStorage {
+    fn get(&self, key: &str) -> Result<Option<String>, AuthError> {
+        check(&self.fail_reads, StorageErrorKind::Read)?;
+        self.inner.get(key)
+    }
+
+    fn set(&self, key: &str, value: &str) -> Result<(), AuthError> {
+        check(&self.fail_writes, StorageErrorKind::Write)?;
+        self.inner.set(key, value)
+    }
+
+    fn remove(&self, key: &str) -> Result<(), AuthError> {
+        check(&self.fail_removes, StorageErrorKind::Remove)?;
+        self.inner.remove(key)
+    }
+}
@@ -0,0 +1,78 @@
+/*
+* The MIT License (MIT)
+*
+* Copyright (c) 2023 Daniél Kerkmann <daniel@kerkmann.dev>
+*
+* Permission is hereby granted, free of charge, to any person obtaining a copy
+* of this software and associated documentation files (the "Software"), to deal
+* in the Software without restriction, including without limitation the rights
+* to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+* copies of the Software, and to permit persons to whom the Software is
+* furnished to do so, subject to the following conditions:
+*
+* The above copyright notice and this permission notice shall be included in all
+* copies or substantial portions of the Software.
+*
+* THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+* IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+* FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+* AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+* LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+* OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+* SOFTWARE.
+*/
+
+use leptos::SignalGetUntracked;
+
+use crate::{error::AuthError, Auth};
+
+impl Auth {
+    /// Handles a front-channel logout request of the provider (OpenID Connect
+    /// Front-Channel Logout), which is loaded in an iframe, e.g. when an
+    /// admin ends the session. If the request carries an `iss`, it has to
+    /// match the configured issuer, and a `sid` the session of the ID token,
+    /// then the local tokens are removed. With
+    /// `frontchannel_logout_session_required`, both are required, otherwise
+    /// any page could log the user out by embedding the route. Without a
+    /// session, there's nothing to do.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the request belongs to another issuer or session,
+    /// or lacks the required parameters.
+    pub fn front_channel_logout(
+        &self,
+        iss: Option<&str>,
+        sid: Option<&str>,
+    ) -> Result<(), AuthError> {
+        if self.parameters.frontchannel_logout_session_required && (iss.is_none() || sid.is_none())
+        {
+            return Err(AuthError::LogoutRequest("'iss' and 'sid' are required"));
+        }
+        if let Some(iss) = iss {
+            let Some(issuer) = self.parameters.issuer.as_deref() else {
+                return Err(AuthError::LogoutRequest(
+                    "'iss' requires a configured issuer",
+                ));
+            };
+            if issuer.trim_end_matches('/') != iss.trim_end_matches('/') {
+                return Err(AuthError::LogoutRequest("'iss' doesn't match the issuer"));
+            }
+        }
+        if sid.is_some() && iss.is_none() {
+            return Err(AuthError::LogoutRequest("'sid' requires 'iss'"));
+        }
+
+        let Some(claims) = self.id_token_claims.get_untracked() else {
+            return Ok(());
+        };
+        if let Some(sid) = sid {
+            if claims.sid.as_deref() != Some(sid) {
+                return Err(AuthError::LogoutRequest("'sid' doesn't match the session"));
+            }
+        }
+        self.local_logout();
+
+        Ok(())
+    }
+}
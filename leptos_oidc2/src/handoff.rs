@@ -0,0 +1,209 @@
+/*
+* The MIT License (MIT)
+*
+* Copyright (c) 2023 Daniél Kerkmann <daniel@kerkmann.dev>
+*
+* Permission is hereby granted, free of charge, to any person obtaining a copy
+* of this software and associated documentation files (the "Software"), to deal
+* in the Software without restriction, including without limitation the rights
+* to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+* copies of the Software, and to permit persons to whom the Software is
+* furnished to do so, subject to the following conditions:
+*
+* The above copyright notice and this permission notice shall be included in all
+* copies or substantial portions of the Software.
+*
+* THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+* IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+* FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+* AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+* LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+* OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+* SOFTWARE.
+*/
+
+use std::sync::Arc;
+
+use chrono::{Duration, NaiveDateTime};
+use leptos::{wasm_bindgen::JsValue, window, SignalGetUntracked, SignalSet};
+use leptos_router::ParamsMap;
+use oidc_core::exchange::{ACCESS_TOKEN_TYPE, TOKEN_EXCHANGE_GRANT_TYPE};
+
+use crate::{
+    audit::{record_result, AuthEventKind},
+    error::AuthError,
+    insert_fragment_params, persist_token_storage, post_client_form, refresh_token,
+    response::{SuccessTokenExchangeResponse, TokenExchangeResponse},
+    utils::{form_encode, now},
+    Auth, AuthParameters,
+};
+
+/// The token type of a refresh token in a token exchange.
+const REFRESH_TOKEN_TYPE: &str = "urn:ietf:params:oauth:token-type:refresh_token";
+
+/// The token type of a handoff code in the token exchange. It's no access
+/// token type, so resource servers don't accept a handoff code as bearer
+/// token, the provider has to be configured to issue and exchange it.
+pub const HANDOFF_TOKEN_TYPE: &str = "urn:leptos-oidc2:params:oauth:token-type:handoff";
+
+/// The fragment parameter carrying the handoff code in the handoff URL.
+pub const HANDOFF_PARAM: &str = "handoff";
+
+/// A structure representing a handoff code, which transfers the session to
+/// another device, e.g. by rendering the `url` as QR code.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HandoffCode {
+    /// The code, a short-lived token of the `HANDOFF_TOKEN_TYPE` with a
+    /// reduced scope. The other device exchanges it for its own refresh
+    /// token, the refresh token of the session is never handed off.
+    pub code: String,
+    /// The URL redeeming the code, the redirect URI with the `handoff`
+    /// parameter in the fragment. The fragment isn't sent to servers, so the
+    /// code doesn't end up in their logs or the `Referer` header.
+    pub url: String,
+    /// The expiry of the code, if the provider returned one.
+    pub expires_at: Option<NaiveDateTime>,
+}
+
+impl Auth {
+    /// Creates a handoff code for another device, e.g. a kiosk, by exchanging
+    /// the access token for a short-lived token of the `HANDOFF_TOKEN_TYPE`
+    /// with the given (reduced) scope. The other device exchanges that code
+    /// for its own refresh token, see `Auth::redeem_handoff`. This requires
+    /// the token exchange (RFC 8693) with that token type to be enabled for
+    /// the client at the provider.
+    ///
+    /// The code isn't single-use by itself: until it expires, it can be
+    /// redeemed as often as the provider accepts it. Configure the provider
+    /// to accept it only once, if it supports that.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the user isn't authenticated, the provider
+    /// rejected the token exchange or issued another token type
+    /// (`AuthError::HandoffTokenType`), which is never handed off.
+    pub async fn create_handoff(&self, scope: &str) -> Result<HandoffCode, AuthError> {
+        let access_token = self
+            .resource
+            .get_untracked()
+            .and_then(Result::ok)
+            .flatten()
+            .ok_or(AuthError::Unauthenticated)?
+            .access_token;
+
+        let response = exchange_handoff(
+            &self.parameters,
+            &access_token,
+            ACCESS_TOKEN_TYPE,
+            HANDOFF_TOKEN_TYPE,
+            Some(scope),
+        )
+        .await?;
+        // A provider ignoring the requested type might issue a plain access
+        // token, which resource servers would accept from whoever scans it.
+        if response.issued_token_type != HANDOFF_TOKEN_TYPE {
+            return Err(AuthError::HandoffTokenType(response.issued_token_type));
+        }
+        let fragment = form_encode(vec![(
+            HANDOFF_PARAM.to_string(),
+            response.access_token.clone(),
+        )]);
+
+        Ok(HandoffCode {
+            url: format!("{}#{fragment}", self.parameters.redirect_uri),
+            code: response.access_token,
+            expires_at: response
+                .expires_in
+                .map(|expires_in| now() + Duration::seconds(expires_in)),
+        })
+    }
+
+    /// Redeems a handoff code, created by `create_handoff` on another device,
+    /// by exchanging it for a refresh token of this device (token exchange)
+    /// and stores the resulting tokens.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the provider rejected the code, e.g. because it's
+    /// invalid, expired or, if the provider enforces it, already redeemed.
+    pub async fn redeem_handoff(&self, code: &str) -> Result<(), AuthError> {
+        let storage = self.parameters.storage.backend();
+        let response = match exchange_handoff(
+            &self.parameters,
+            code,
+            HANDOFF_TOKEN_TYPE,
+            REFRESH_TOKEN_TYPE,
+            None,
+        )
+        .await
+        {
+            Ok(exchanged) => refresh_token(&self.parameters, exchanged.access_token, None).await,
+            Err(error) => Err(error),
+        };
+        record_result(storage, &response, AuthEventKind::Login);
+
+        let token_storage = response?;
+        persist_token_storage(
+            storage,
+            &self.parameters.token_storage_key(),
+            &token_storage,
+        )?;
+        self.resource.set(Ok(Some(token_storage)));
+
+        Ok(())
+    }
+}
+
+/// Returns the handoff code of the current URL, see `HandoffCode::url`, and
+/// removes it from the URL, so it doesn't stay in the history.
+#[must_use]
+pub fn take_handoff_code() -> Option<String> {
+    if !leptos::leptos_dom::is_browser() {
+        return None;
+    }
+    let location = window().location();
+    let mut params = ParamsMap::new();
+    insert_fragment_params(&mut params, &location.hash().ok()?);
+    let code = params.get(HANDOFF_PARAM)?.clone();
+
+    let url = format!("{}{}", location.pathname().ok()?, location.search().ok()?);
+    if let Err(error) = window()
+        .history()
+        .and_then(|history| history.replace_state_with_url(&JsValue::NULL, "", Some(&url)))
+    {
+        leptos::logging::error!("Unable to remove the handoff code: {error:#?}");
+    }
+
+    Some(code)
+}
+
+/// Exchanges the subject token of the given type for a token of the
+/// requested type (token exchange, RFC 8693).
+async fn exchange_handoff(
+    parameters: &AuthParameters,
+    subject_token: &str,
+    subject_token_type: &str,
+    requested_token_type: &str,
+    scope: Option<&str>,
+) -> Result<SuccessTokenExchangeResponse, AuthError> {
+    let mut params = vec![
+        ("grant_type", TOKEN_EXCHANGE_GRANT_TYPE),
+        ("client_id", parameters.client_id.as_str()),
+        ("subject_token", subject_token),
+        ("subject_token_type", subject_token_type),
+        ("requested_token_type", requested_token_type),
+    ];
+    if let Some(scope) = scope {
+        params.push(("scope", scope));
+    }
+    let params = params
+        .into_iter()
+        .map(|(key, value)| (key.to_string(), value.to_string()))
+        .collect();
+    let response = post_client_form(parameters, &parameters.token_endpoint, params).await?;
+
+    match serde_json::from_str::<TokenExchangeResponse>(&response).map_err(Arc::new)? {
+        TokenExchangeResponse::Success(success) => Ok(success),
+        TokenExchangeResponse::Error(error) => Err(AuthError::provider(error)),
+    }
+}
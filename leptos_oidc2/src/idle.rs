@@ -0,0 +1,190 @@
+/*
+* The MIT License (MIT)
+*
+* Copyright (c) 2023 Daniél Kerkmann <daniel@kerkmann.dev>
+*
+* Permission is hereby granted, free of charge, to any person obtaining a copy
+* of this software and associated documentation files (the "Software"), to deal
+* in the Software without restriction, including without limitation the rights
+* to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+* copies of the Software, and to permit persons to whom the Software is
+* furnished to do so, subject to the following conditions:
+*
+* The above copyright notice and this permission notice shall be included in all
+* copies or substantial portions of the Software.
+*
+* THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+* IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+* FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+* AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+* LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+* OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+* SOFTWARE.
+*/
+
+use std::{cell::Cell, rc::Rc, sync::Arc, time::Duration};
+
+use chrono::NaiveDateTime;
+use leptos::{
+    set_interval_with_handle, window_event_listener_untyped, Signal, SignalGetUntracked, SignalSet,
+};
+use serde::Deserialize;
+
+use crate::{
+    error::AuthError,
+    storage::{LocalStorage, Storage},
+    utils::now,
+    Auth,
+};
+
+/// The DOM events counted as user activity by default.
+const DEFAULT_ACTIVITY_EVENTS: [&str; 5] = [
+    "pointerdown",
+    "keydown",
+    "scroll",
+    "touchstart",
+    "visibilitychange",
+];
+
+/// The longest time between two idle checks.
+const MAX_CHECK_INTERVAL: Duration = Duration::from_secs(30);
+
+/// The shortest time between two writes of the activity to the storage, see
+/// `share_activity`.
+const SHARE_INTERVAL: Duration = Duration::from_secs(1);
+
+/// An enumeration of what happens once the user is idle, see `IdleTimeout`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IdleAction {
+    /// Refreshes the tokens silently.
+    Refresh,
+    /// Only emits `SessionEvent::Idle`, e.g. to show a warning.
+    Warn,
+    /// Logs out, see `Auth::logout`.
+    #[default]
+    Logout,
+}
+
+/// Options of the idle timeout, see `AuthParameters::idle_timeout`. Once the
+/// user wasn't active for `timeout`, `SessionEvent::Idle` is emitted and the
+/// `action` is taken, once per idle period.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct IdleTimeout {
+    /// The time without activity, after which the user is idle.
+    pub timeout: Duration,
+    /// What happens once the user is idle, defaults to a logout.
+    #[serde(default)]
+    pub action: IdleAction,
+    /// The DOM events counted as user activity, defaults to pointer, key,
+    /// scroll, touch and visibility events.
+    #[serde(default = "default_activity_events")]
+    pub events: Vec<String>,
+}
+
+impl Default for IdleTimeout {
+    fn default() -> Self {
+        Self {
+            timeout: Duration::from_secs(15 * 60),
+            action: IdleAction::default(),
+            events: default_activity_events(),
+        }
+    }
+}
+
+/// Returns the default activity events.
+fn default_activity_events() -> Vec<String> {
+    DEFAULT_ACTIVITY_EVENTS.map(String::from).to_vec()
+}
+
+impl Auth {
+    /// Returns a signal, which is `true` while an authenticated user is idle,
+    /// see `AuthParameters::idle_timeout`.
+    #[must_use]
+    pub fn idle(&self) -> Signal<bool> {
+        self.idle.into()
+    }
+
+    /// Tracks the activity of the user and takes the idle action, see
+    /// `IdleTimeout`. The activity is shared with the other tabs through the
+    /// local storage, because the idle action, like a logout, ends the
+    /// session of every tab. The listeners live as long as the app.
+    pub(crate) fn watch_idle(&self, options: &IdleTimeout) {
+        if !leptos::leptos_dom::is_browser() {
+            return;
+        }
+
+        let timeout = chrono::Duration::from_std(options.timeout)
+            .unwrap_or_else(|_| chrono::Duration::zero());
+        let share_interval =
+            chrono::Duration::from_std(SHARE_INTERVAL).unwrap_or_else(|_| chrono::Duration::zero());
+        let key = format!("{}_last_activity", self.parameters.token_storage_key());
+        let last_activity = Rc::new(Cell::new(now()));
+        let last_shared = Rc::new(Cell::new(now()));
+        share_activity(&key, now());
+        for event in &options.events {
+            let idle = self.idle;
+            let key = key.clone();
+            let last_activity = Rc::clone(&last_activity);
+            let last_shared = Rc::clone(&last_shared);
+            // The listeners live as long as the app, the handles aren't needed.
+            let _ = window_event_listener_untyped(event, move |_| {
+                let time = now();
+                last_activity.set(time);
+                // Scroll and pointer events fire in bursts, the other tabs
+                // only need to know about the activity once per interval.
+                if time - last_shared.get() >= share_interval {
+                    last_shared.set(time);
+                    share_activity(&key, time);
+                }
+                if idle.get_untracked() {
+                    idle.set(false);
+                }
+            });
+        }
+
+        let auth = self.clone();
+        let action = options.action;
+        let check_interval =
+            (options.timeout / 10).clamp(Duration::from_secs(1), MAX_CHECK_INTERVAL);
+        let _ = set_interval_with_handle(
+            move || {
+                let last_activity = shared_activity(&key).map_or(last_activity.get(), |shared| {
+                    shared.max(last_activity.get())
+                });
+                let idle = now() - last_activity >= timeout;
+                // The user might be active in another tab.
+                if !idle && auth.idle.get_untracked() {
+                    auth.idle.set(false);
+                }
+                if !idle || auth.idle.get_untracked() || auth.current_tokens().is_none() {
+                    return;
+                }
+                auth.idle.set(true);
+                match action {
+                    IdleAction::Refresh => auth.refresh_token(),
+                    IdleAction::Warn => {}
+                    IdleAction::Logout => auth.logout(),
+                }
+            },
+            check_interval,
+        );
+    }
+}
+
+/// Writes the time of the last activity to the local storage, where the
+/// other tabs pick it up with `shared_activity`.
+fn share_activity(key: &str, time: NaiveDateTime) {
+    let result = serde_json::to_string(&time)
+        .map_err(|error| AuthError::from(Arc::new(error)))
+        .and_then(|time| LocalStorage.set(key, &time));
+    if let Err(error) = result {
+        leptos::logging::error!("Unable to share the activity: {error:#?}");
+    }
+}
+
+/// Returns the time of the last activity in any tab, see `share_activity`.
+fn shared_activity(key: &str) -> Option<NaiveDateTime> {
+    let time = LocalStorage.get(key).ok().flatten()?;
+    serde_json::from_str(&time).ok()
+}
@@ -0,0 +1,54 @@
+/*
+* The MIT License (MIT)
+*
+* Copyright (c) 2023 Daniél Kerkmann <daniel@kerkmann.dev>
+*
+* Permission is hereby granted, free of charge, to any person obtaining a copy
+* of this software and associated documentation files (the "Software"), to deal
+* in the Software without restriction, including without limitation the rights
+* to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+* copies of the Software, and to permit persons to whom the Software is
+* furnished to do so, subject to the following conditions:
+*
+* The above copyright notice and this permission notice shall be included in all
+* copies or substantial portions of the Software.
+*
+* THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+* IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+* FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+* AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+* LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+* OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+* SOFTWARE.
+*/
+
+use crate::{
+    error::AuthError,
+    finish_login,
+    instrument::instrumented,
+    response::ImplicitCallbackResponse,
+    storage::{read_code_verifier, read_nonce, remove_code_verifier, remove_nonce, TokenStorage},
+    AuthParameters, OidcClient,
+};
+
+/// Completes the login callback of the implicit or hybrid flow and persists
+/// the tokens, see `OidcClient::complete_implicit`.
+pub(crate) async fn fetch_implicit_token(
+    parameters: &AuthParameters,
+    response: ImplicitCallbackResponse,
+) -> Result<TokenStorage, AuthError> {
+    instrumented("fetch_implicit_token", async move {
+        let storage = parameters.storage.transient_backend();
+        let key = parameters.token_storage_key();
+        let code_verifier = read_code_verifier(storage, &key)?;
+        remove_code_verifier(storage, &key)?;
+        let nonce = read_nonce(storage, &key)?;
+        let token_storage = OidcClient::new(parameters)
+            .complete_implicit(response, code_verifier.as_deref(), nonce.as_deref())
+            .await?;
+        remove_nonce(storage, &key)?;
+
+        finish_login(parameters, token_storage)
+    })
+    .await
+}
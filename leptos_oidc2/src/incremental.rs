@@ -0,0 +1,94 @@
+/*
+* The MIT License (MIT)
+*
+* Copyright (c) 2023 Daniél Kerkmann <daniel@kerkmann.dev>
+*
+* Permission is hereby granted, free of charge, to any person obtaining a copy
+* of this software and associated documentation files (the "Software"), to deal
+* in the Software without restriction, including without limitation the rights
+* to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+* copies of the Software, and to permit persons to whom the Software is
+* furnished to do so, subject to the following conditions:
+*
+* The above copyright notice and this permission notice shall be included in all
+* copies or substantial portions of the Software.
+*
+* THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+* IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+* FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+* AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+* LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+* OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+* SOFTWARE.
+*/
+
+use crate::{
+    error::AuthError,
+    storage::{
+        read_scope_request, remove_scope_request, write_requested_scope, write_return_to,
+        write_scope_request,
+    },
+    Auth, Scopes,
+};
+
+impl Auth {
+    /// Generates the login URL like `Auth::login_url`, but requests the given
+    /// scopes in addition to the configured ones, e.g. to ask for the consent
+    /// to a feature once it's used (incremental authorization).
+    ///
+    /// # Errors
+    ///
+    /// Returns `AuthError::Storage` like `Auth::login_url`.
+    pub fn login_url_with_scopes(&self, scopes: &Scopes) -> Result<String, AuthError> {
+        self.authorization_url(&self.parameters.scope.union(scopes))
+    }
+
+    /// Returns the given scopes, which weren't granted yet. Empty if
+    /// unauthenticated.
+    #[must_use]
+    pub fn missing_required_scopes(&self, scopes: &Scopes) -> Scopes {
+        self.granted_scopes()
+            .map(|granted| scopes.missing_in(&granted))
+            .unwrap_or_else(Scopes::new)
+    }
+
+    /// Redirects the browser to the provider to grant the given scopes in
+    /// addition to the configured ones. After the callback, the app navigates
+    /// back to `return_to`.
+    ///
+    /// Returns `false` without redirecting if the scopes were already
+    /// requested for `return_to` and the provider didn't grant them, so a
+    /// denied consent doesn't end up in a redirect loop.
+    pub fn request_scopes(&self, scopes: &Scopes, return_to: &str) -> bool {
+        if !leptos::leptos_dom::is_browser() {
+            return false;
+        }
+        let storage = self.parameters.storage.transient_backend();
+        let key = self.parameters.token_storage_key();
+        if read_scope_request(storage, &key).ok().flatten().as_deref() == Some(return_to) {
+            self.finish_scope_request();
+            return false;
+        }
+
+        let requested = self.parameters.scope.union(scopes);
+        let stored = write_scope_request(storage, &key, return_to)
+            .and_then(|()| write_return_to(storage, &key, return_to))
+            .and_then(|()| write_requested_scope(storage, &key, &requested.to_string()));
+        if let Err(error) = stored {
+            leptos::logging::error!("Unable to request scopes: {error:#?}");
+            return false;
+        }
+        self.redirect_to_authorization(requested, false);
+        true
+    }
+
+    /// Forgets the pending incremental authorization, once the requested
+    /// scopes were granted or denied.
+    pub(crate) fn finish_scope_request(&self) {
+        let storage = self.parameters.storage.transient_backend();
+        let key = self.parameters.token_storage_key();
+        if let Err(error) = remove_scope_request(storage, &key) {
+            leptos::logging::error!("Unable to remove the scope request: {error:#?}");
+        }
+    }
+}
@@ -0,0 +1,88 @@
+/*
+* The MIT License (MIT)
+*
+* Copyright (c) 2023 Daniél Kerkmann <daniel@kerkmann.dev>
+*
+* Permission is hereby granted, free of charge, to any person obtaining a copy
+* of this software and associated documentation files (the "Software"), to deal
+* in the Software without restriction, including without limitation the rights
+* to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+* copies of the Software, and to permit persons to whom the Software is
+* furnished to do so, subject to the following conditions:
+*
+* The above copyright notice and this permission notice shall be included in all
+* copies or substantial portions of the Software.
+*
+* THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+* IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+* FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+* AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+* LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+* OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+* SOFTWARE.
+*/
+
+use std::future::Future;
+
+use crate::error::AuthError;
+
+/// Emits a `tracing` event with the target `leptos_oidc2`, if the `tracing`
+/// feature is enabled. The arguments aren't evaluated otherwise.
+macro_rules! trace_event {
+    ($level:ident, $($arg:tt)+) => {
+        #[cfg(feature = "tracing")]
+        tracing::$level!(target: "leptos_oidc2", $($arg)+);
+    };
+}
+pub(crate) use trace_event;
+
+/// Runs the future in a span of the given operation and records its
+/// duration and its error, if any.
+pub(crate) async fn instrumented<T>(
+    operation: &'static str,
+    future: impl Future<Output = Result<T, AuthError>>,
+) -> Result<T, AuthError> {
+    #[cfg(feature = "tracing")]
+    {
+        use tracing::Instrument;
+
+        // `Instant` isn't available in the browser.
+        let started = chrono::Utc::now();
+        let result = future
+            .instrument(tracing::debug_span!(target: "leptos_oidc2", "auth", operation))
+            .await;
+        let elapsed_ms = (chrono::Utc::now() - started).num_milliseconds();
+        match &result {
+            Ok(_) => tracing::debug!(target: "leptos_oidc2", operation, elapsed_ms, "succeeded"),
+            Err(error) => {
+                tracing::warn!(target: "leptos_oidc2", operation, elapsed_ms, %error, "failed");
+            }
+        }
+        result
+    }
+
+    #[cfg(not(feature = "tracing"))]
+    {
+        let _ = operation;
+        future.await
+    }
+}
+
+/// A token value in a `tracing` event, displayed as the first bytes of its
+/// SHA-256 hash instead of the token itself.
+#[cfg(feature = "tracing")]
+pub(crate) struct Redacted<'a>(pub &'a str);
+
+#[cfg(feature = "tracing")]
+impl std::fmt::Display for Redacted<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        use sha2::{Digest, Sha256};
+
+        let hash = Sha256::digest(self.0.as_bytes());
+        write!(f, "sha256:")?;
+        for byte in &hash[..4] {
+            write!(f, "{byte:02x}")?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,102 @@
+/*
+* The MIT License (MIT)
+*
+* Copyright (c) 2023 Daniél Kerkmann <daniel@kerkmann.dev>
+*
+* Permission is hereby granted, free of charge, to any person obtaining a copy
+* of this software and associated documentation files (the "Software"), to deal
+* in the Software without restriction, including without limitation the rights
+* to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+* copies of the Software, and to permit persons to whom the Software is
+* furnished to do so, subject to the following conditions:
+*
+* The above copyright notice and this permission notice shall be included in all
+* copies or substantial portions of the Software.
+*
+* THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+* IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+* FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+* AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+* LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+* OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+* SOFTWARE.
+*/
+
+use leptos::{Signal, SignalGetUntracked, SignalSet, SignalUpdate};
+
+use crate::{redirect, Auth};
+
+/// An enumeration of the redirects to the provider, which can be announced by
+/// the `RedirectingToLogin` component.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RedirectKind {
+    /// The redirect to the login page, see `Auth::login`.
+    Login,
+    /// The redirect to the logout page, see `Auth::logout`.
+    Logout,
+}
+
+/// A redirect to the provider, which waits for the `RedirectingToLogin`
+/// component to confirm it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PendingRedirect {
+    /// Whether the redirect logs in or out.
+    pub kind: RedirectKind,
+    /// The URL the browser is sent to.
+    pub url: String,
+    /// Whether the local tokens are removed before leaving, see
+    /// `LogoutBehavior::Both`.
+    local_logout: bool,
+}
+
+impl Auth {
+    /// Sends the browser to the provider. While a `RedirectingToLogin`
+    /// component is mounted, the redirect waits for it instead.
+    pub(crate) fn redirect_to_provider(&self, kind: RedirectKind, url: String, local_logout: bool) {
+        let pending = PendingRedirect {
+            kind,
+            url,
+            local_logout,
+        };
+        if self.interstitials.get_untracked() > 0 {
+            self.pending_redirect.set(Some(pending));
+        } else {
+            self.perform_redirect(pending);
+        }
+    }
+
+    /// Returns a signal of the redirect waiting for the `RedirectingToLogin`
+    /// component, if any.
+    #[must_use]
+    pub fn pending_redirect(&self) -> Signal<Option<PendingRedirect>> {
+        self.pending_redirect.into()
+    }
+
+    /// Performs the pending redirect right away.
+    pub fn confirm_redirect(&self) {
+        if let Some(pending) = self.pending_redirect.get_untracked() {
+            self.pending_redirect.set(None);
+            self.perform_redirect(pending);
+        }
+    }
+
+    /// Cancels the pending redirect, the user stays on the current page.
+    pub fn cancel_redirect(&self) {
+        self.pending_redirect.set(None);
+    }
+
+    /// Registers a mounted `RedirectingToLogin` component, so redirects wait
+    /// for it. Returns the function to unregister it.
+    pub(crate) fn register_interstitial(&self) -> impl FnOnce() {
+        self.interstitials.update(|count| *count += 1);
+        let interstitials = self.interstitials;
+        move || interstitials.update(|count| *count = count.saturating_sub(1))
+    }
+
+    fn perform_redirect(&self, pending: PendingRedirect) {
+        if pending.local_logout {
+            self.local_logout();
+        }
+        redirect(&pending.url);
+    }
+}
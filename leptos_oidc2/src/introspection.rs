@@ -0,0 +1,96 @@
+/*
+* The MIT License (MIT)
+*
+* Copyright (c) 2023 Daniél Kerkmann <daniel@kerkmann.dev>
+*
+* Permission is hereby granted, free of charge, to any person obtaining a copy
+* of this software and associated documentation files (the "Software"), to deal
+* in the Software without restriction, including without limitation the rights
+* to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+* copies of the Software, and to permit persons to whom the Software is
+* furnished to do so, subject to the following conditions:
+*
+* The above copyright notice and this permission notice shall be included in all
+* copies or substantial portions of the Software.
+*
+* THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+* IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+* FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+* AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+* LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+* OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+* SOFTWARE.
+*/
+
+use chrono::{DateTime, NaiveDateTime};
+use leptos::SignalGetUntracked;
+use serde::{Deserialize, Serialize};
+
+use crate::{client_form_request, error::AuthError, response::ErrorResponse, scopes::Scopes, Auth};
+
+/// The response of the introspection endpoint (RFC 7662). Only `active` is
+/// guaranteed, inactive tokens come without any other field.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct Introspection {
+    pub active: bool,
+    pub exp: Option<i64>,
+    pub scope: Option<Scopes>,
+    pub username: Option<String>,
+    pub sub: Option<String>,
+    pub client_id: Option<String>,
+}
+
+impl Introspection {
+    /// Returns the expiry of the token, taken from `exp`.
+    #[must_use]
+    pub fn expires_at(&self) -> Option<NaiveDateTime> {
+        DateTime::from_timestamp(self.exp?, 0).map(|date_time| date_time.naive_utc())
+    }
+}
+
+impl Auth {
+    /// Asks the provider whether the current access token is still active,
+    /// at the introspection endpoint (RFC 7662). Unlike decoding the token,
+    /// this also works for opaque (non-JWT) access tokens and notices
+    /// revoked tokens. The endpoint is taken from
+    /// `AuthParameters::introspection_endpoint` or the provider metadata.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the user isn't authenticated, no introspection
+    /// endpoint is known or the provider rejected the request.
+    pub async fn introspect(&self) -> Result<Introspection, AuthError> {
+        let tokens = self
+            .resource
+            .get_untracked()
+            .and_then(Result::ok)
+            .flatten()
+            .ok_or(AuthError::Unauthenticated)?;
+        let endpoint = self
+            .parameters
+            .introspection_endpoint
+            .clone()
+            .or_else(|| {
+                self.metadata
+                    .get_untracked()
+                    .and_then(|metadata| metadata.introspection_endpoint)
+            })
+            .ok_or(AuthError::Discovery("introspection_endpoint"))?;
+
+        let request = client_form_request(
+            &self.parameters,
+            endpoint,
+            vec![
+                ("client_id".to_string(), self.parameters.client_id.clone()),
+                ("token".to_string(), tokens.access_token),
+                ("token_type_hint".to_string(), "access_token".to_string()),
+            ],
+        );
+        let response = self.parameters.client().send(request).await?;
+        if !response.is_success() {
+            return Err(AuthError::provider(response.json::<ErrorResponse>()?));
+        }
+
+        Ok(response.json()?)
+    }
+}
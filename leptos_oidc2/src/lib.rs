@@ -24,46 +24,441 @@
 
 #![allow(clippy::module_name_repetitions)]
 
-use std::sync::Arc;
+use std::{collections::HashMap, sync::Arc, time::Duration};
 
-use chrono::Utc;
+use audit::{record_event, record_result, AuthEvent, AuthEventKind};
+use builder::AuthParametersBuilder;
+use chrono::{DateTime, NaiveDateTime};
+use claims::{
+    claim_values, decode_claims_unverified, token_algorithm, StandardClaims,
+    DEFAULT_PERMISSION_CLAIM, DEFAULT_ROLE_CLAIM,
+};
+use client::{
+    client_authentication, http_client, ClientAuthMethod, HttpClient, HttpRequest, HttpResponse,
+};
+use context::provide_auth;
+use cookie::read_auth_state;
+use discovery::ProviderMetadata;
+use futures::future::{join, FutureExt, LocalBoxFuture, Shared};
+use idle::IdleTimeout;
+use implicit::fetch_implicit_token;
+use instrument::{instrumented, trace_event};
+use interstitial::{PendingRedirect, RedirectKind};
 use jsonwebtoken::decode;
+use jwks::Jwks;
 use leptos::{
-    create_effect, create_local_resource, expect_context, provide_context, spawn_local, Resource,
-    SignalGet, SignalGetUntracked, SignalSet,
+    create_local_resource, create_memo, create_resource, create_rw_signal, ev, spawn_local, window,
+    window_event_listener, Memo, Resource, RwSignal, ServerFnError, Signal, SignalGet,
+    SignalGetUntracked, SignalSet, SignalWith,
+};
+use leptos_router::{
+    use_location, use_navigate, use_query_map, Location, NavigateOptions, ParamsError, ParamsMap,
+};
+use maintenance::MaintenanceWindow;
+use offline::is_offline;
+use oidc_core::{dpop::send_with_dpop, oidc::NONCE_BYTES};
+use pkce::generate_code_verifier;
+use response::{
+    CallbackParams, CallbackResponse, SuccessCallbackResponse, SuccessTokenResponse, TokenResponse,
 };
-use leptos_router::use_query;
-use response::{CallbackResponse, SuccessCallbackResponse, TokenResponse};
+use return_to::{current_route, take_return_to};
 use serde::{de::DeserializeOwned, Deserialize};
-use storage::{read_token_storage, remove_token_storage, write_to_token_storage, TokenStorage};
-use utils::ParamBuilder;
+use silent::SilentRenewal;
+use sliding::SlidingSession;
+use storage::{
+    migrate_legacy_token_storage, read_audit_log, read_code_verifier, read_logout_state,
+    read_nonce, read_requested_scope, read_token_storage, remove_code_verifier,
+    remove_logout_state, remove_nonce, remove_requested_scope, remove_token_storage,
+    write_code_verifier, write_logout_state, write_nonce, write_to_token_storage, InMemoryStorage,
+    Storage, StorageKind, TokenIdentity, TokenStorage, TOKEN_STORAGE_KEY_PREFIX,
+};
+use userinfo::{user_info_resource, UserInfoSource};
+use utils::{now, random_string, sleep};
 
+pub mod audit;
+pub mod builder;
+pub mod capabilities;
+pub mod claims;
+#[cfg(feature = "components")]
 pub mod components;
+mod compression;
+pub mod context;
+pub mod cookie;
+pub mod countdown;
+pub mod deletion;
+#[cfg(feature = "dpop")]
+pub mod dpop;
+#[cfg(feature = "encryption")]
+pub mod encryption;
 pub mod error;
+pub mod events;
+pub mod exchange;
+#[cfg(feature = "fault-injection")]
+pub mod fault;
+pub mod frontchannel;
+pub mod handoff;
+pub mod idle;
+pub mod implicit;
+pub mod incremental;
+mod instrument;
+pub mod interstitial;
+pub mod introspection;
+pub mod maintenance;
+pub mod metrics;
+pub mod offline;
+pub mod par;
+#[cfg(feature = "preview")]
+pub mod preview;
+#[cfg(feature = "web-push")]
+pub mod push;
+#[cfg(feature = "leptos-use")]
+pub mod reactive_storage;
+pub mod registry;
+pub mod requirement;
 pub mod response;
+pub mod return_to;
+pub mod revocation;
+pub mod service;
+pub mod session;
+pub mod silent;
+pub mod sliding;
+pub mod state;
 pub mod storage;
+pub mod token_cache;
+#[cfg(feature = "grants")]
+pub mod token_client;
+pub mod userinfo;
 pub mod utils;
+mod validation;
+
+pub use oidc_core::{client, discovery, jwks, pkce, profile, scopes};
 
+pub use capabilities::{capabilities, Capabilities};
+#[cfg(feature = "components")]
 pub use components::*;
-pub use error::AuthError;
+pub use context::{try_use_auth, use_auth, AuthContext};
+pub use error::{AuthConfigError, AuthError, StorageErrorKind};
+pub use events::SessionEvent;
+pub use exchange::{ExchangedToken, TokenTarget};
+pub use oidc_core::{
+    AuthorizationRequest, DpopProver, LogoutBehavior, OidcClient, OidcConfig, OidcError,
+    ResponseMode, ResponseType,
+};
+pub use profile::ProviderProfile;
+pub use registry::{use_auth_registry, AuthRegistry};
+pub use requirement::Requirement;
+pub use scopes::Scopes;
+pub use service::{provide_authed_service, use_service};
+pub use state::{AuthState, AuthStateSerde};
+pub use token_cache::{TokenCache, TokenCacheKey};
 
 pub type Algorithm = jsonwebtoken::Algorithm;
 pub type DecodingKey = jsonwebtoken::DecodingKey;
 pub type TokenData<T> = jsonwebtoken::TokenData<T>;
 pub type Validation = jsonwebtoken::Validation;
 
+/// The resource of the state read by the server, see `CookieStorage`.
+type ServerState = Resource<(), Result<AuthStateSerde, ServerFnError>>;
+
+/// The refresh in flight, which can be awaited by multiple callers.
+type PendingRefresh = Shared<LocalBoxFuture<'static, ()>>;
+
+/// The query parameters of the login and logout callbacks, which are removed
+/// from the URL once the callback was handled.
+const CALLBACK_PARAMS: [&str; 5] = ["code", "state", "session_state", "iss", "destroy_session"];
+
+/// The amount of random bytes used for the logout state.
+const LOGOUT_STATE_BYTES: usize = 16;
+
+/// How often a refresh is retried after a transient failure.
+const REFRESH_RETRIES: u32 = 3;
+
+/// The delay before the first retry of a refresh, doubled on every retry.
+const REFRESH_BACKOFF: Duration = Duration::from_millis(500);
+
 /// Represents authentication parameters required for initializing the `Auth`
 /// structure. These parameters include authentication and token endpoints,
 /// client ID, and other related data.
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Default, Clone, Deserialize)]
 pub struct AuthParameters {
+    pub issuer: Option<String>,
     pub auth_endpoint: String,
     pub token_endpoint: String,
     pub logout_endpoint: String,
+    pub jwks_uri: Option<String>,
+    /// The token revocation endpoint (RFC 7009), see `Auth::revoke_consent`.
+    /// Defaults to the one of the provider metadata.
+    pub revocation_endpoint: Option<String>,
+    /// The OP iframe of the session management, see `Auth::monitor_session`.
+    /// Defaults to the one of the provider metadata.
+    pub check_session_iframe: Option<String>,
+    /// The token introspection endpoint (RFC 7662), see `Auth::introspect`.
+    /// Defaults to the one of the provider metadata.
+    pub introspection_endpoint: Option<String>,
+    /// The endpoint of the backend deleting the account of the user, see
+    /// `Auth::request_account_deletion`.
+    pub account_deletion_endpoint: Option<String>,
+    /// The tolerated clock skew between the browser and the provider, applied
+    /// to the expiry checks of the tokens and to the token validation.
+    /// Defaults to zero.
+    #[serde(default)]
+    pub leeway: Duration,
+    /// The time before the expiry of the access token, in which
+    /// `Auth::access_token` already refreshes it in the background, while
+    /// still returning the current one. Clamped to half the lifetime of the
+    /// access token. Disabled (zero) by default.
+    #[serde(default)]
+    pub refresh_before_expiry: Duration,
+    /// The issuer every decoded token has to be issued by (`iss`). Defaults
+    /// to the `issuer`.
+    #[serde(default)]
+    pub expected_issuer: Option<String>,
+    /// The audience every decoded access token has to be issued for (`aud`),
+    /// usually the API. Not checked if unset.
+    #[serde(default)]
+    pub expected_audience: Option<String>,
+    /// Refreshes the tokens on user activity to extend the session, see
+    /// `SlidingSession`. Disabled by default.
+    #[serde(default)]
+    pub sliding_session: Option<SlidingSession>,
+    /// Detects idle users and refreshes, warns or logs out, see
+    /// `IdleTimeout`. Disabled by default.
+    #[serde(default)]
+    pub idle_timeout: Option<IdleTimeout>,
+    /// The userinfo endpoint, see `Auth::user_info`. Defaults to the one of
+    /// the provider metadata.
+    pub userinfo_endpoint: Option<String>,
     pub client_id: String,
+    /// The secret of a confidential client, sent with the token requests.
+    /// It ends up in the WASM bundle, so only set it for first-party apps of
+    /// providers which don't allow public clients.
+    #[serde(default)]
+    pub client_secret: Option<String>,
+    /// How the `client_secret` is sent, defaults to the `Authorization`
+    /// header.
+    #[serde(default)]
+    pub client_auth_method: ClientAuthMethod,
     pub redirect_uri: String,
     pub post_logout_redirect_uri: String,
-    pub scope: Option<String>,
+    /// The page loaded in the hidden iframe of `Auth::silent_login`, which
+    /// posts its URL to the app.
+    pub silent_redirect_uri: Option<String>,
+    /// How `Auth::silent_login` renews the session, see `SilentRenewal`.
+    #[serde(default)]
+    pub silent_renewal: SilentRenewal,
+    /// The requested scopes, defaults to `openid`.
+    #[serde(default)]
+    pub scope: Scopes,
+    /// The API the access token is requested for, required by e.g. Auth0 to
+    /// issue a JWT access token.
+    pub audience: Option<String>,
+    pub prompt: Option<String>,
+    pub max_age: Option<u64>,
+    pub login_hint: Option<String>,
+    pub ui_locales: Option<String>,
+    /// Additional parameters, which are appended to the login URL as is.
+    #[serde(default)]
+    pub extra_params: Vec<(String, String)>,
+    #[serde(default)]
+    pub storage: StorageKind,
+    /// The key of the token storage, defaults to `auth_<client_id>`, so apps
+    /// on the same origin don't overwrite each other's tokens.
+    #[serde(default)]
+    pub storage_key: Option<String>,
+    /// Renames non-standard callback parameters of the provider to the
+    /// standard names, e.g. `authorization_code` to `code`.
+    #[serde(default)]
+    pub callback_param_overrides: HashMap<String, String>,
+    /// Scheduled maintenance windows of the provider.
+    #[serde(default)]
+    pub maintenance_windows: Vec<MaintenanceWindow>,
+    /// A URL returning a JSON list of maintenance windows, which are added to
+    /// the scheduled ones.
+    pub maintenance_url: Option<String>,
+    /// The dot separated path of the roles claim in the access token,
+    /// defaults to `realm_access.roles` (Keycloak).
+    pub role_claim: Option<String>,
+    /// The dot separated path of the permissions claim in the access token,
+    /// defaults to `permissions` (Auth0).
+    pub permission_claim: Option<String>,
+    /// Sends unauthenticated users to the end session endpoint anyway, for
+    /// providers which accept a logout without `id_token_hint`. By default
+    /// they are redirected straight to the `post_logout_redirect_uri`.
+    #[serde(default)]
+    pub logout_without_id_token_hint: bool,
+    /// Requires the `iss` and `sid` parameters in front-channel logout
+    /// requests, see `Auth::front_channel_logout`. Defaults to whether the
+    /// provider metadata announces `frontchannel_logout_session_supported`.
+    #[serde(default)]
+    pub frontchannel_logout_session_required: bool,
+    #[serde(default)]
+    pub logout_behavior: LogoutBehavior,
+    /// Keeps the callback parameters, like the `code`, in the URL. By default
+    /// they are removed once the callback was handled.
+    #[serde(default)]
+    pub keep_callback_params: bool,
+    /// Adapts the requests to the quirks of the provider.
+    #[serde(default)]
+    pub profile: ProviderProfile,
+    /// Selects whether the callback parameters are read from the query string
+    /// or the URL fragment.
+    #[serde(default)]
+    pub response_mode: ResponseMode,
+    /// Selects the flow by the tokens the provider returns in the callback.
+    /// Response types other than `code` always return the callback
+    /// parameters in the URL fragment.
+    #[serde(default)]
+    pub response_type: ResponseType,
+    /// The pushed authorization request endpoint (RFC 9126), filled in by
+    /// `AuthParameters::from_issuer` if the provider supports it.
+    #[serde(default)]
+    pub pushed_authorization_request_endpoint: Option<String>,
+    /// Pushes the authorization request to the
+    /// `pushed_authorization_request_endpoint`, the login page is only passed
+    /// the returned `request_uri`, see `Auth::pushed_login_url`.
+    #[serde(default)]
+    pub pushed_authorization: bool,
+    /// Binds the tokens to a key of the browser with DPoP (RFC 9449), the
+    /// token requests and `Auth::authenticated_fetch` send DPoP proofs.
+    /// Requires the `dpop` feature.
+    #[serde(default)]
+    pub dpop: bool,
+    /// Forwards the auth events of the browser to the recorder of the server,
+    /// see `metrics::set_metrics`.
+    #[serde(default)]
+    pub report_metrics: bool,
+    /// The HTTP client used for every request to the provider and of
+    /// `Auth::authenticated_fetch`, e.g. a `reqwest::Client` with a proxy,
+    /// custom headers or timeouts, or the `GlooNetClient` of the `gloo-net`
+    /// feature. Defaults to the shared client, see `configure_http_client`.
+    #[serde(skip)]
+    pub http_client: Option<Arc<dyn HttpClient>>,
+}
+
+impl AuthParameters {
+    /// Returns a builder for the parameters, which validates them and sets
+    /// sane defaults for the optional ones.
+    pub fn builder() -> AuthParametersBuilder {
+        AuthParametersBuilder::default()
+    }
+
+    /// Fetches the discovery document (`/.well-known/openid-configuration`)
+    /// of the given issuer and fills in the provider endpoints. The client
+    /// related fields are left empty and need to be set by the caller, e.g.
+    /// with the struct update syntax:
+    ///
+    /// ```ignore
+    /// let parameters = AuthParameters {
+    ///     client_id: "CLIENT_ID".to_string(),
+    ///     redirect_uri: "http://localhost:3000/profile".to_string(),
+    ///     post_logout_redirect_uri: "http://localhost:3000/bye".to_string(),
+    ///     ..AuthParameters::from_issuer("https://ENDPOINT/auth/realms/REALM").await?
+    /// };
+    /// ```
+    pub async fn from_issuer(issuer_url: &str) -> Result<Self, AuthError> {
+        let metadata = ProviderMetadata::fetch(issuer_url).await?;
+
+        Ok(Self {
+            issuer: Some(issuer_url.to_string()),
+            auth_endpoint: metadata.authorization_endpoint,
+            token_endpoint: metadata.token_endpoint,
+            logout_endpoint: metadata
+                .end_session_endpoint
+                .ok_or(AuthError::Discovery("end_session_endpoint"))?,
+            jwks_uri: metadata.jwks_uri,
+            revocation_endpoint: metadata.revocation_endpoint,
+            check_session_iframe: metadata.check_session_iframe,
+            introspection_endpoint: metadata.introspection_endpoint,
+            userinfo_endpoint: metadata.userinfo_endpoint,
+            pushed_authorization_request_endpoint: metadata.pushed_authorization_request_endpoint,
+            frontchannel_logout_session_required: metadata
+                .frontchannel_logout_session_supported
+                .unwrap_or_default(),
+            ..Self::default()
+        })
+    }
+
+    /// Returns the key of the token storage, the configured `storage_key` or
+    /// `auth_<client_id>`. Needed to observe the tokens, e.g. with a
+    /// `LeptosUseStorage`.
+    #[must_use]
+    pub fn token_storage_key(&self) -> String {
+        self.storage_key
+            .clone()
+            .unwrap_or_else(|| format!("{TOKEN_STORAGE_KEY_PREFIX}_{}", self.client_id))
+    }
+
+    /// Returns where the provider returns the callback parameters, the
+    /// implicit and hybrid flow never use the query string.
+    pub(crate) fn callback_response_mode(&self) -> ResponseMode {
+        match self.response_type {
+            ResponseType::Code => self.response_mode,
+            _ => ResponseMode::Fragment,
+        }
+    }
+
+    /// Returns the injected HTTP client or the shared one.
+    pub(crate) fn client(&self) -> Arc<dyn HttpClient> {
+        self.http_client.clone().unwrap_or_else(http_client)
+    }
+
+    /// Returns the signer of the DPoP proofs with `dpop`, which keeps its key
+    /// in WebCrypto.
+    #[cfg(feature = "dpop")]
+    pub(crate) fn dpop_prover(&self) -> Option<Arc<dyn DpopProver>> {
+        self.dpop
+            .then(|| Arc::new(dpop::WebCryptoProver::new(self)) as Arc<dyn DpopProver>)
+    }
+
+    /// Returns no signer, DPoP requires the `dpop` feature.
+    #[cfg(not(feature = "dpop"))]
+    #[allow(clippy::unused_self)]
+    pub(crate) fn dpop_prover(&self) -> Option<Arc<dyn DpopProver>> {
+        None
+    }
+}
+
+/// The protocol related parameters, the storage, the session handling and
+/// the components only concern `Auth`.
+impl From<&AuthParameters> for OidcConfig {
+    fn from(parameters: &AuthParameters) -> Self {
+        Self {
+            issuer: parameters.issuer.clone(),
+            auth_endpoint: parameters.auth_endpoint.clone(),
+            token_endpoint: parameters.token_endpoint.clone(),
+            logout_endpoint: parameters.logout_endpoint.clone(),
+            jwks_uri: parameters.jwks_uri.clone(),
+            pushed_authorization_request_endpoint: parameters
+                .pushed_authorization_request_endpoint
+                .clone(),
+            expected_issuer: parameters.expected_issuer.clone(),
+            leeway: parameters.leeway,
+            client_id: parameters.client_id.clone(),
+            client_secret: parameters.client_secret.clone(),
+            client_auth_method: parameters.client_auth_method,
+            redirect_uri: parameters.redirect_uri.clone(),
+            post_logout_redirect_uri: parameters.post_logout_redirect_uri.clone(),
+            audience: parameters.audience.clone(),
+            prompt: parameters.prompt.clone(),
+            max_age: parameters.max_age,
+            login_hint: parameters.login_hint.clone(),
+            ui_locales: parameters.ui_locales.clone(),
+            extra_params: parameters.extra_params.clone(),
+            logout_without_id_token_hint: parameters.logout_without_id_token_hint,
+            logout_behavior: parameters.logout_behavior,
+            profile: parameters.profile,
+            response_mode: parameters.response_mode,
+            response_type: parameters.response_type,
+            http_client: Some(parameters.client()),
+            dpop: parameters.dpop_prover(),
+        }
+    }
+}
+
+impl From<AuthParameters> for OidcConfig {
+    fn from(parameters: AuthParameters) -> Self {
+        Self::from(&parameters)
+    }
 }
 
 /// Authentication handler responsible for handling user authentication and
@@ -71,7 +466,22 @@ pub struct AuthParameters {
 #[derive(Debug, Clone)]
 pub struct Auth {
     parameters: AuthParameters,
-    resource: Resource<(), Result<Option<TokenStorage>, AuthError>>,
+    active: RwSignal<bool>,
+    refreshing: RwSignal<Option<PendingRefresh>>,
+    metadata: RwSignal<Option<ProviderMetadata>>,
+    resource: Resource<bool, Result<Option<TokenStorage>, AuthError>>,
+    server_state: Option<ServerState>,
+    tokens: Memo<Option<TokenIdentity>>,
+    id_token_claims: Memo<Option<StandardClaims>>,
+    jwks: Resource<Option<String>, Result<Option<Jwks>, AuthError>>,
+    maintenance: Resource<bool, Vec<MaintenanceWindow>>,
+    user_info: Resource<UserInfoSource, Result<serde_json::Value, AuthError>>,
+    user_info_requested: RwSignal<bool>,
+    pending_redirect: RwSignal<Option<PendingRedirect>>,
+    interstitials: RwSignal<usize>,
+    idle: RwSignal<bool>,
+    degraded: RwSignal<bool>,
+    token_cache: TokenCache,
 }
 
 impl Auth {
@@ -80,90 +490,355 @@ impl Auth {
     /// configured for authentication.
     #[allow(clippy::must_use_candidate)]
     pub fn init(parameters: AuthParameters) -> Self {
-        let resource = create_local_resource(move || (), {
+        Self::new(parameters, true)
+    }
+
+    /// Initializes a new `Auth` instance like `init`, but neither reads the
+    /// token storage nor parses the callback parameters until the
+    /// authentication state is accessed for the first time or `activate` is
+    /// called. This is useful for public pages, which should not pay any
+    /// authentication startup cost.
+    #[allow(clippy::must_use_candidate)]
+    pub fn init_lazy(parameters: AuthParameters) -> Self {
+        Self::new(parameters, false)
+    }
+
+    /// Initializes a new `Auth` instance for one of several providers and
+    /// registers it under the given ID in the `AuthRegistry`, instead of
+    /// providing it as context, see `AuthProvider`. Its token storage is
+    /// namespaced by the ID, unless a `storage_key` is set. Every provider
+    /// needs its own `redirect_uri` and `post_logout_redirect_uri` path, an
+    /// instance only handles callbacks on its own paths.
+    #[allow(clippy::must_use_candidate)]
+    pub fn init_provider(id: &str, mut parameters: AuthParameters) -> Self {
+        parameters.storage_key.get_or_insert_with(|| {
+            format!("{TOKEN_STORAGE_KEY_PREFIX}_{id}_{}", parameters.client_id)
+        });
+        let auth = Self::create(parameters, true, true);
+        use_auth_registry().insert(id, auth.clone());
+
+        auth
+    }
+
+    /// Creates the `Auth` instance and provides it as context.
+    fn new(parameters: AuthParameters, active: bool) -> Self {
+        let auth = Self::create(parameters, active, false);
+        provide_auth(auth.clone());
+
+        auth
+    }
+
+    /// Creates the `Auth` instance. A scoped instance only handles the
+    /// callbacks on the paths of its redirect URIs.
+    fn create(parameters: AuthParameters, active: bool, scoped: bool) -> Self {
+        if let Err(problems) = parameters.validate() {
+            for problem in problems {
+                leptos::logging::error!("Invalid auth parameters: {problem}");
+            }
+        }
+        if parameters.report_metrics {
+            metrics::enable_reporting();
+        }
+        let active = create_rw_signal(active);
+        let metadata = create_rw_signal(None);
+        // With the cookie storage, the server reads the state out of the
+        // cookie. The state is serialized to the client for the hydration.
+        let server_state = matches!(parameters.storage, StorageKind::Cookie)
+            .then(|| create_resource(|| (), |()| read_auth_state()));
+        let query = use_query_map();
+        let location = use_location();
+        let navigate = use_navigate();
+        let resource = create_local_resource(move || active.get(), {
             let parameters = parameters.clone();
-            move |()| {
+            move |active| {
                 let parameters = parameters.clone();
+                let location = location.clone();
+                let navigate = navigate.clone();
                 async move {
-                    let auth_response = use_query::<CallbackResponse>();
-                    match auth_response.get_untracked() {
-                        Ok(CallbackResponse::SuccessLogin(response)) => {
-                            fetch_token(&parameters, response).await.map(Option::Some)
-                        }
-                        Ok(CallbackResponse::SuccessLogout(response)) => {
-                            if response.destroy_session {
-                                create_effect(move |_| {
-                                    if let Err(error) = remove_token_storage() {
-                                        leptos::logging::error!(
-                                            "Unable to delete token: {error:#?}"
-                                        );
-                                    }
-                                });
+                    if !active {
+                        return Ok(None);
+                    }
+
+                    let params = match parameters.callback_response_mode() {
+                        ResponseMode::Query => query.get_untracked(),
+                        ResponseMode::Fragment => {
+                            // The logout callback still arrives in the query
+                            // string.
+                            let mut params = query.get_untracked();
+                            if let Ok(hash) = window().location().hash() {
+                                insert_fragment_params(&mut params, &hash);
                             }
+                            params
+                        }
+                    };
+                    let callback = match CallbackResponse::from_map_with_overrides(
+                        params,
+                        &parameters.callback_param_overrides,
+                        parameters.response_type,
+                    ) {
+                        Ok(callback)
+                            if scoped
+                                && !callback_path_matches(&parameters, &callback, &location) =>
+                        {
+                            Err(ParamsError::MissingParam(
+                                "Callback of another provider".to_string(),
+                            ))
+                        }
+                        callback => callback,
+                    };
+                    trace_event!(
+                        debug,
+                        callback = match &callback {
+                            Ok(
+                                CallbackResponse::SuccessLogin(_)
+                                | CallbackResponse::SuccessImplicitLogin(_),
+                            ) => "login",
+                            Ok(CallbackResponse::SuccessLogout(_)) => "logout",
+                            Ok(CallbackResponse::Error(_)) => "error",
+                            Err(_) => "none",
+                        },
+                        "parsed callback parameters"
+                    );
+                    let login_callback = matches!(
+                        callback,
+                        Ok(CallbackResponse::SuccessLogin(_)
+                            | CallbackResponse::SuccessImplicitLogin(_))
+                    );
+                    let handled_callback = login_callback
+                        || matches!(callback, Ok(CallbackResponse::SuccessLogout(_)));
+                    let state = load_state(&parameters, callback, server_state, metadata).await;
+                    let return_to = (login_callback && state.is_ok())
+                        .then(|| {
+                            take_return_to(
+                                parameters.storage.transient_backend(),
+                                &parameters.token_storage_key(),
+                            )
+                        })
+                        .flatten();
+                    if let Some(return_to) = return_to {
+                        // The login returns to the route it was started on.
+                        navigate(
+                            &return_to,
+                            NavigateOptions {
+                                replace: true,
+                                ..NavigateOptions::default()
+                            },
+                        );
+                    } else if handled_callback && state.is_ok() && !parameters.keep_callback_params
+                    {
+                        remove_callback_params(&parameters, &location, navigate);
+                    }
 
-                            Ok(None)
+                    state
+                }
+            }
+        });
+
+        Self::assemble(parameters, active, metadata, resource, server_state)
+    }
+
+    /// Creates the `Auth` instance around the resource of the authentication
+    /// state, which either handles the callback and the storage (see
+    /// `create`) or yields a fixed state.
+    fn assemble(
+        parameters: AuthParameters,
+        active: RwSignal<bool>,
+        metadata: RwSignal<Option<ProviderMetadata>>,
+        resource: Resource<bool, Result<Option<TokenStorage>, AuthError>>,
+        server_state: Option<ServerState>,
+    ) -> Self {
+        // Views depending on the tokens only re-run if the token identity or
+        // expiry changes, see `TokenIdentity`.
+        let tokens = create_memo(move |_| {
+            current_state(resource, server_state)
+                .and_then(Result::ok)
+                .flatten()
+                .map(TokenIdentity)
+        });
+
+        // The ID token is decoded once per token, not on every claim access.
+        let id_token_claims = create_memo(move |_| {
+            tokens.with(|tokens| {
+                tokens
+                    .as_ref()
+                    .and_then(|tokens| decode_claims_unverified(&tokens.0.id_token).ok())
+            })
+        });
+
+        // The key set is fetched concurrently with the code exchange. Without
+        // an explicit `jwks_uri`, it's taken from the preloaded metadata.
+        let jwks = create_local_resource(
+            {
+                let jwks_uri = parameters.jwks_uri.clone();
+                move || {
+                    if !active.get() {
+                        return None;
+                    }
+                    jwks_uri
+                        .clone()
+                        .or_else(|| metadata.get().and_then(|metadata| metadata.jwks_uri))
+                }
+            },
+            {
+                let client = parameters.client();
+                move |jwks_uri: Option<String>| {
+                    let client = client.clone();
+                    async move {
+                        #[cfg(feature = "preview")]
+                        if preview::current_preview().is_some() {
+                            return Ok(None);
                         }
-                        Ok(CallbackResponse::Error(error)) => Err(AuthError::Provider(error)),
-                        Err(_) => {
-                            create_effect(move |_| {
-                                let auth = expect_context::<Auth>();
-                                match read_token_storage() {
-                                    Err(error) => {
-                                        remove_token_storage().ok();
-                                        auth.resource.set(Err(error));
-                                    }
-                                    Ok(Some(state)) => {
-                                        if state.refresh_expires_in.is_some()
-                                            && state.refresh_expires_in
-                                                < Some(Utc::now().naive_utc())
-                                        {
-                                            remove_token_storage().ok();
-                                            auth.resource.set(Ok(None));
-                                        } else {
-                                            auth.resource.set(Ok(Some(state)));
-                                        }
-                                    }
-                                    Ok(None) => {
-                                        auth.resource.set(Ok(None));
-                                    }
-                                }
-                            });
-
-                            Ok(None)
+
+                        match jwks_uri {
+                            Some(jwks_uri) => Ok(Some(
+                                Jwks::fetch_with_client(client.as_ref(), &jwks_uri).await?,
+                            )),
+                            None => Ok(None),
                         }
                     }
                 }
+            },
+        );
+
+        let maintenance = create_local_resource(move || active.get(), {
+            let parameters = parameters.clone();
+            move |active| {
+                let parameters = parameters.clone();
+                async move {
+                    let Some(url) = parameters.maintenance_url.as_deref().filter(|_| active) else {
+                        return Vec::new();
+                    };
+                    MaintenanceWindow::fetch_with_client(parameters.client().as_ref(), url)
+                        .await
+                        .unwrap_or_else(|error| {
+                            leptos::logging::error!(
+                                "Unable to fetch maintenance windows: {error:#?}"
+                            );
+                            Vec::new()
+                        })
+                }
             }
         });
 
+        let user_info_requested = create_rw_signal(false);
+        let user_info = user_info_resource(&parameters, metadata, tokens, user_info_requested);
+
+        sync_across_tabs(
+            parameters.storage,
+            parameters.token_storage_key(),
+            parameters.leeway,
+            active,
+            resource,
+        );
+
         let auth = Self {
             parameters,
+            active,
+            refreshing: create_rw_signal(None),
+            metadata,
             resource,
+            server_state,
+            tokens,
+            id_token_claims,
+            jwks,
+            maintenance,
+            user_info,
+            user_info_requested,
+            pending_redirect: create_rw_signal(None),
+            interstitials: create_rw_signal(0),
+            idle: create_rw_signal(false),
+            degraded: create_rw_signal(false),
+            token_cache: TokenCache::new(),
         };
+        auth.watch_connectivity();
+        auth.watch_token_cache();
+        if let Some(sliding_session) = &auth.parameters.sliding_session {
+            auth.slide_session(sliding_session);
+        }
+        if let Some(idle_timeout) = &auth.parameters.idle_timeout {
+            auth.watch_idle(idle_timeout);
+        }
 
-        provide_context(auth);
+        auth
+    }
 
-        expect_context::<Auth>()
+    /// Activates a lazily initialized `Auth` instance, see `init_lazy`. Calling
+    /// this on an already active instance has no effect.
+    pub fn activate(&self) {
+        if !self.active.get_untracked() {
+            self.active.set(true);
+        }
+    }
+
+    /// Returns the underlying resource, activating the instance beforehand.
+    fn resource(&self) -> Resource<bool, Result<Option<TokenStorage>, AuthError>> {
+        self.activate();
+        self.resource
+    }
+
+    /// Returns the current state of the resource, see `current_state`.
+    fn resource_state(&self) -> Option<Result<Option<TokenStorage>, AuthError>> {
+        current_state(self.resource(), self.server_state)
+    }
+
+    /// Returns a signal of the authentication state, which is easier to match
+    /// on than the combination of `loading`, `ok` and `err`.
+    #[must_use]
+    pub fn state(&self) -> Signal<AuthState> {
+        let auth = self.clone();
+        Signal::derive(move || match auth.resource_state().into() {
+            AuthState::Authenticated(tokens) if auth.degraded.get() => AuthState::Degraded(tokens),
+            state => state,
+        })
+    }
+
+    /// Returns a signal of the authentication state without the tokens, see
+    /// `AuthStateSerde`.
+    #[must_use]
+    pub fn state_serde(&self) -> Signal<AuthStateSerde> {
+        let auth = self.clone();
+        Signal::derive(move || (&auth.state().get()).into())
+    }
+
+    /// Returns the current tokens, tracking changes of their identity only.
+    fn tokens(&self) -> Option<TokenStorage> {
+        self.activate();
+        self.tokens.get().map(|tokens| tokens.0)
     }
 
     /// Generates and returns the URL for initiating the authentication process.
     /// This URL is used to redirect the user to the authentication provider's
-    /// login page.
+    /// login page. In the browser a PKCE code challenge (`S256`) and a nonce
+    /// are appended, the code verifier and the nonce are kept in session
+    /// storage. With `pushed_authorization` enabled, use
+    /// `Auth::pushed_login_url` instead.
+    ///
+    /// # Errors
+    ///
+    /// Returns `AuthError::Storage` if the code verifier or the nonce can't
+    /// be kept, a login without them would be rejected on callback or skip
+    /// their protection.
+    pub fn login_url(&self) -> Result<String, AuthError> {
+        self.authorization_url(&self.parameters.scope)
+    }
+
+    /// Generates the URL of the authorization request for the given scopes,
+    /// see `Auth::login_url`.
+    fn authorization_url(&self, scope: &Scopes) -> Result<String, AuthError> {
+        let (code_verifier, nonce) = pending_login_values(
+            self.parameters.storage.transient_backend(),
+            &self.parameters.token_storage_key(),
+        )?;
+
+        Ok(self
+            .oidc_client()
+            .authorization_url(scope, code_verifier.as_deref(), nonce.as_deref()))
+    }
+
+    /// Returns the framework-agnostic client of the parameters.
     #[must_use]
-    pub fn login_url(&self) -> String {
-        self.parameters
-            .auth_endpoint
-            .clone()
-            .push_param_query("response_type", "code")
-            .push_param_query("client_id", &self.parameters.client_id)
-            .push_param_query("redirect_uri", &self.parameters.redirect_uri)
-            .push_param_query(
-                "scope",
-                self.parameters
-                    .scope
-                    .clone()
-                    .unwrap_or("openid".to_string()),
-            )
+    pub fn oidc_client(&self) -> OidcClient {
+        OidcClient::new(&self.parameters)
     }
 
     /// Generates and returns the URL for initiating the logout process. This
@@ -171,91 +846,504 @@ impl Auth {
     /// page.
     #[must_use]
     pub fn logout_url(&self) -> String {
-        let url = self.parameters.logout_endpoint.clone().push_param_query(
-            "post_logout_redirect_uri",
-            self.parameters
-                .post_logout_redirect_uri
-                .clone()
-                .push_param_query("destroy_session", "true"),
-        );
-        if let Some(token) = self.resource.get().and_then(Result::ok).flatten() {
-            return url.push_param_query("id_token_hint", token.id_token);
-        }
+        self.logout_url_with_hints(None, None)
+    }
 
-        url
+    /// Generates and returns the URL for initiating the logout process, like
+    /// `logout_url`. Additionally the `logout_hint`, which identifies the
+    /// account to log out and is preferred over the `id_token_hint` by some
+    /// providers, and the `ui_locales` of the logout page can be passed.
+    ///
+    /// If the user isn't authenticated, there is no `id_token_hint` and some
+    /// providers show an error page. In that case the post logout redirect
+    /// URI is returned instead, unless `logout_without_id_token_hint` is set.
+    /// With `LogoutBehavior::LocalOnly` it's returned in any case.
+    #[must_use]
+    pub fn logout_url_with_hints(
+        &self,
+        logout_hint: Option<&str>,
+        ui_locales: Option<&str>,
+    ) -> String {
+        let token = self.resource_state().and_then(Result::ok).flatten();
+        // The callback is only accepted once with this state, see
+        // `consume_logout_state`.
+        // Without the state the logout still works, only its callback is
+        // ignored (see `consume_logout_state`).
+        let state = pending_login_value(
+            self.parameters.storage.transient_backend(),
+            &self.parameters.token_storage_key(),
+            read_logout_state,
+            write_logout_state,
+            || random_string(LOGOUT_STATE_BYTES),
+        )
+        .unwrap_or_else(|error| {
+            leptos::logging::error!("Unable to prepare logout: {error:#?}");
+            None
+        });
+
+        self.oidc_client().logout_url(
+            token
+                .as_ref()
+                .filter(|token| token.has_tokens())
+                .map(|token| token.id_token.as_str()),
+            state.as_deref(),
+            logout_hint,
+            ui_locales,
+        )
     }
 
     /// Checks if the authentication process is currently loading.
     #[must_use]
     pub fn loading(&self) -> bool {
-        self.resource.loading().get()
+        self.resource().loading().get()
+            && self
+                .server_state
+                .and_then(|server_state| server_state.get())
+                .is_none()
     }
 
     /// Checks if the user is authenticated.
     #[must_use]
     pub fn authenticated(&self) -> bool {
-        self.resource.get().and_then(Result::ok).flatten().is_some()
+        // The resource is read directly, so a surrounding `Suspense` or
+        // `Transition` keeps track of the loading state.
+        self.resource_state()
+            .and_then(Result::ok)
+            .flatten()
+            .is_some()
     }
 
     /// Returns the ID token, if available, from the authentication response.
     #[must_use]
     pub fn id_token(&self) -> Option<String> {
-        self.resource
-            .get()
-            .and_then(Result::ok)
-            .flatten()
+        self.tokens()
+            .filter(TokenStorage::has_tokens)
             .map(|response| response.id_token)
     }
 
     /// Returns the access token, if available, from the authentication response.
+    /// Within the `refresh_before_expiry` window, a refresh is started in
+    /// the background and the current token is returned until it finishes
+    /// (stale-while-revalidate), see `access_token_async` to wait for it.
     #[must_use]
     pub fn access_token(&self) -> Option<String> {
-        self.resource
-            .get()
+        let tokens = self.tokens()?;
+        if self.expires_soon(&tokens) {
+            self.refresh_token();
+        }
+
+        Some(tokens)
+            .filter(TokenStorage::has_tokens)
+            .map(|tokens| tokens.access_token)
+    }
+
+    /// Returns the access token like `access_token`, but waits for the
+    /// refresh, if the token expires within the `refresh_before_expiry`
+    /// window or already expired. Returns `None` if unauthenticated or the
+    /// refresh failed.
+    pub async fn access_token_async(&self) -> Option<String> {
+        self.activate();
+        let tokens = self.current_tokens()?;
+        if tokens.access_token_expired_with(self.parameters.leeway) || self.expires_soon(&tokens) {
+            self.await_refresh().await;
+            return self
+                .current_tokens()
+                .filter(|tokens| !tokens.access_token_expired_with(self.parameters.leeway))
+                .filter(TokenStorage::has_tokens)
+                .map(|tokens| tokens.access_token);
+        }
+
+        Some(tokens)
+            .filter(TokenStorage::has_tokens)
+            .map(|tokens| tokens.access_token)
+    }
+
+    /// Checks if the access token expires within the `refresh_before_expiry`
+    /// window, which is clamped to half the lifetime of the token.
+    fn expires_soon(&self, tokens: &TokenStorage) -> bool {
+        let window = self.parameters.refresh_before_expiry;
+        !window.is_zero() && tokens.access_token_expires_within(window)
+    }
+
+    /// Returns the values of the access token claim at the given dot
+    /// separated path, e.g. `realm_access.roles`. The access token isn't
+    /// validated, it was received directly from the token endpoint. Empty if
+    /// there is no access token or the claim doesn't exist.
+    #[must_use]
+    pub fn access_token_claim_values(&self, path: &str) -> Vec<String> {
+        self.access_token()
+            .and_then(|access_token| {
+                decode_claims_unverified::<serde_json::Value>(&access_token).ok()
+            })
+            .map(|claims| claim_values(&claims, path))
+            .unwrap_or_default()
+    }
+
+    /// Checks if the access token contains the given role, see
+    /// `AuthParameters::role_claim`.
+    #[must_use]
+    pub fn has_role(&self, role: &str) -> bool {
+        let path = self.parameters.role_claim.as_deref();
+        self.access_token_claim_values(path.unwrap_or(DEFAULT_ROLE_CLAIM))
+            .iter()
+            .any(|value| value == role)
+    }
+
+    /// Checks if the access token contains the given permission, see
+    /// `AuthParameters::permission_claim`.
+    #[must_use]
+    pub fn has_permission(&self, permission: &str) -> bool {
+        let path = self.parameters.permission_claim.as_deref();
+        self.access_token_claim_values(path.unwrap_or(DEFAULT_PERMISSION_CLAIM))
+            .iter()
+            .any(|value| value == permission)
+    }
+
+    /// Checks if the access token contains the given scope, either in the
+    /// space separated `scope` claim or in the `scp` claim.
+    #[must_use]
+    pub fn has_scope(&self, scope: &str) -> bool {
+        ["scope", "scp"].into_iter().any(|path| {
+            self.access_token_claim_values(path)
+                .iter()
+                .any(|value| value == scope)
+        })
+    }
+
+    /// Returns the scopes granted by the provider, taken from the `scope` of
+    /// the token response. If the provider didn't list them, the requested
+    /// scopes were granted. Returns `None` if unauthenticated.
+    #[must_use]
+    pub fn granted_scopes(&self) -> Option<Scopes> {
+        let tokens = self.resource_state().and_then(Result::ok).flatten()?;
+        Some(
+            tokens
+                .scope
+                .map_or_else(|| self.parameters.scope.clone(), Scopes::from),
+        )
+    }
+
+    /// Returns the requested scopes, which the provider silently dropped,
+    /// e.g. to hide the features depending on them. Empty if unauthenticated.
+    #[must_use]
+    pub fn missing_scopes(&self) -> Scopes {
+        self.granted_scopes()
+            .map(|granted| self.parameters.scope.missing_in(&granted))
+            .unwrap_or_else(Scopes::new)
+    }
+
+    /// Returns the time of the authentication, taken from the `auth_time`
+    /// claim of the ID token or, if missing, from the `iat` claim.
+    #[must_use]
+    pub fn authenticated_at(&self) -> Option<NaiveDateTime> {
+        #[derive(Deserialize)]
+        struct AuthTimeClaims {
+            auth_time: Option<i64>,
+            iat: Option<i64>,
+        }
+
+        let claims = decode_claims_unverified::<AuthTimeClaims>(&self.id_token()?).ok()?;
+        let timestamp = claims.auth_time.or(claims.iat)?;
+        DateTime::from_timestamp(timestamp, 0).map(|date_time| date_time.naive_utc())
+    }
+
+    /// Returns the subject (`sub` claim) of the ID token, the unique
+    /// identifier of the user at the provider.
+    #[must_use]
+    pub fn subject(&self) -> Option<String> {
+        self.standard_claims()?.sub
+    }
+
+    /// Returns the `email` claim of the ID token.
+    #[must_use]
+    pub fn email(&self) -> Option<String> {
+        self.standard_claims()?.email
+    }
+
+    /// Returns the `name` claim of the ID token.
+    #[must_use]
+    pub fn name(&self) -> Option<String> {
+        self.standard_claims()?.name
+    }
+
+    /// Returns the `preferred_username` claim of the ID token.
+    #[must_use]
+    pub fn preferred_username(&self) -> Option<String> {
+        self.standard_claims()?.preferred_username
+    }
+
+    /// Returns the expiry (`exp` claim) of the ID token.
+    #[must_use]
+    pub fn expires_at(&self) -> Option<NaiveDateTime> {
+        let timestamp = self.standard_claims()?.exp?;
+        DateTime::from_timestamp(timestamp, 0).map(|date_time| date_time.naive_utc())
+    }
+
+    /// Returns the cached standard claims of the ID token.
+    fn standard_claims(&self) -> Option<StandardClaims> {
+        self.activate();
+        self.id_token_claims.get()
+    }
+
+    /// Checks if the given requirement is met by the current authentication
+    /// state.
+    #[must_use]
+    pub fn check_access(&self, requirement: &Requirement) -> bool {
+        requirement.is_met(self)
+    }
+
+    /// Returns a memo of the decoded ID token claims, which is re-evaluated
+    /// whenever the ID token changes. The claims aren't validated, the ID
+    /// token was received directly from the token endpoint. `None` if there
+    /// is no ID token or the claims can't be decoded into `T`.
+    #[must_use]
+    pub fn claims_signal<T>(&self) -> Memo<Option<T>>
+    where
+        T: DeserializeOwned + PartialEq + 'static,
+    {
+        let auth = self.clone();
+        create_memo(move |_| {
+            auth.id_token()
+                .and_then(|id_token| decode_claims_unverified(&id_token).ok())
+        })
+    }
+
+    /// Returns the ID token, if available, decoded and validated against the
+    /// provider's JSON Web Key Set. The audience has to match the client ID
+    /// and the issuer, if configured. Returns `None` as long as the key set
+    /// is loading.
+    #[must_use]
+    pub fn verified_id_token<T: DeserializeOwned>(
+        &self,
+    ) -> Option<Result<TokenData<T>, AuthError>> {
+        let mut validation = self.validation();
+        validation.set_audience(&[&self.parameters.client_id]);
+        self.verify(&self.id_token()?, &validation)
+    }
+
+    /// Returns the access token, if available, decoded and validated against
+    /// the provider's JSON Web Key Set. The audience is only validated if
+    /// `expected_audience` is set, because it depends on the API the access
+    /// token was issued for. Returns `None` as long as the key set is
+    /// loading.
+    #[must_use]
+    pub fn verified_access_token<T: DeserializeOwned>(
+        &self,
+    ) -> Option<Result<TokenData<T>, AuthError>> {
+        let validation = self.access_token_validation(Algorithm::RS256);
+        self.verify(&self.access_token()?, &validation)
+    }
+
+    /// Returns the default validation of access tokens with the given
+    /// algorithm, e.g. for `decoded_access_token`. It checks the leeway,
+    /// the `expected_issuer` and the `expected_audience`.
+    #[must_use]
+    pub fn access_token_validation(&self, algorithm: Algorithm) -> Validation {
+        let mut validation = Validation::new(algorithm);
+        validation.leeway = self.parameters.leeway.as_secs();
+        validation.validate_aud = false;
+        self.expect_claims(&mut validation);
+        validation
+    }
+
+    /// Returns the default validation for tokens of the provider.
+    fn validation(&self) -> Validation {
+        let mut validation = Validation::new(Algorithm::RS256);
+        validation.leeway = self.parameters.leeway.as_secs();
+        if let Some(issuer) = self.expected_issuer() {
+            validation.set_issuer(&[issuer]);
+        }
+        validation
+    }
+
+    /// Returns the issuer of the decoded tokens, see `expected_issuer`.
+    fn expected_issuer(&self) -> Option<&String> {
+        self.parameters
+            .expected_issuer
+            .as_ref()
+            .or(self.parameters.issuer.as_ref())
+    }
+
+    /// Adds the expected issuer and audience to the validation of an access
+    /// token, unless the validation checks them already. This prevents a
+    /// token of another issuer or API from being accepted.
+    fn expect_claims(&self, validation: &mut Validation) {
+        if validation.iss.is_none() {
+            if let Some(issuer) = self.expected_issuer() {
+                validation.set_issuer(&[issuer]);
+            }
+        }
+        if validation.aud.is_none() {
+            if let Some(audience) = &self.parameters.expected_audience {
+                validation.set_audience(&[audience]);
+                validation.validate_aud = true;
+            }
+        }
+    }
+
+    /// Validates the token against the JSON Web Key Set.
+    fn verify<T: DeserializeOwned>(
+        &self,
+        token: &str,
+        validation: &Validation,
+    ) -> Option<Result<TokenData<T>, AuthError>> {
+        match self.jwks.get()? {
+            Ok(Some(jwks)) => Some(jwks.decode(token, validation).map_err(AuthError::from)),
+            Ok(None) => Some(Err(AuthError::Discovery("jwks_uri"))),
+            Err(error) => Some(Err(error)),
+        }
+    }
+
+    /// Returns the access token, if available and not expired yet. An expired
+    /// access token triggers a refresh, the refreshed token is returned by
+    /// the next call once the refresh has finished.
+    #[must_use]
+    pub fn valid_access_token(&self) -> Option<String> {
+        let state = self.resource_state().and_then(Result::ok).flatten()?;
+        if state.access_token_expired_with(self.parameters.leeway) {
+            self.refresh_token();
+            return None;
+        }
+
+        Some(state.access_token)
+    }
+
+    /// Redirects the browser to the login page of the provider, see
+    /// `Auth::login_url`. After the login, the app returns to the current
+    /// route, see `Auth::login_with_return_to`.
+    pub fn login(&self) {
+        self.login_with_return_to(current_route().as_deref());
+    }
+
+    /// Redirects the browser to the logout page of the provider, see
+    /// `Auth::logout_url`. With `LogoutBehavior::Both`, the local tokens are
+    /// removed before leaving. A mounted `RedirectingToLogin` component
+    /// announces the redirect first.
+    pub fn logout(&self) {
+        self.logout_with_hints(None, None);
+    }
+
+    /// Logs out like `Auth::logout`, passing the `logout_hint` and the
+    /// `ui_locales` on to the provider, see `Auth::logout_url_with_hints`.
+    pub fn logout_with_hints(&self, logout_hint: Option<&str>, ui_locales: Option<&str>) {
+        // The URL is taken before the local logout, which would drop the
+        // `id_token_hint`.
+        let url = self.logout_url_with_hints(logout_hint, ui_locales);
+        let local_logout = self.parameters.logout_behavior == LogoutBehavior::Both;
+        self.redirect_to_provider(RedirectKind::Logout, url, local_logout);
+    }
+
+    /// Removes the local tokens and resets the state, without redirecting
+    /// through the provider. The session at the provider is kept.
+    pub fn local_logout(&self) {
+        let storage = self.parameters.storage.backend();
+        if let Err(error) = remove_token_storage(storage, &self.parameters.token_storage_key()) {
+            leptos::logging::error!("Unable to delete token: {error:#?}");
+        }
+        record_event(storage, AuthEventKind::Logout);
+        #[cfg(feature = "dpop")]
+        if self.parameters.dpop {
+            dpop::delete_key(&self.parameters);
+        }
+
+        self.token_cache.clear();
+        self.resource.set(Ok(None));
+    }
+
+    /// Marks the access token as expired, without dropping the session. This
+    /// can be used if the backend signals a revocation out-of-band, the next
+    /// call of `valid_access_token` refreshes the token.
+    pub fn invalidate_access_token(&self) {
+        let Some(mut state) = self
+            .resource()
+            .get_untracked()
             .and_then(Result::ok)
             .flatten()
-            .map(|response| response.access_token)
+        else {
+            return;
+        };
+        state.expires_in = now();
+
+        if let Err(error) = persist_token_storage(
+            self.parameters.storage.backend(),
+            &self.parameters.token_storage_key(),
+            &state,
+        ) {
+            leptos::logging::error!("Unable to invalidate access token: {error:#?}");
+        }
+
+        self.resource.set(Ok(Some(state)));
     }
 
     /// Returns the decoded access token, if available, from the authentication response.
+    /// A token with an algorithm, which isn't supported, fails with
+    /// `AuthError::UnsupportedAlgorithm`. The `expected_issuer` and the
+    /// `expected_audience` are checked, unless the validation sets its own,
+    /// see `access_token_validation` for the default validation.
     #[must_use]
     pub fn decoded_access_token<T: DeserializeOwned>(
         &self,
         decoding_key: &DecodingKey,
         validation: &Validation,
-    ) -> Option<Result<TokenData<T>, jsonwebtoken::errors::Error>> {
-        self.resource
-            .get()
+    ) -> Option<Result<TokenData<T>, AuthError>> {
+        let mut validation = validation.clone();
+        self.expect_claims(&mut validation);
+
+        self.resource_state()
             .and_then(Result::ok)
             .flatten()
-            .map(|response| decode::<T>(&response.access_token, decoding_key, validation))
+            .map(|response| {
+                token_algorithm(&response.access_token)?;
+                decode::<T>(&response.access_token, decoding_key, &validation)
+                    .map_err(AuthError::token)
+            })
     }
 
     /// Returns the decoded access token, if available, from the authentication response, this is not validating the access token.
+    /// A token with an algorithm, which isn't supported, fails with
+    /// `AuthError::UnsupportedAlgorithm` as well, its claims can still be
+    /// read with `decode_claims_unverified`.
     #[must_use]
     pub fn decoded_access_token_unverified<T: DeserializeOwned>(
         &self,
         algorithm: Algorithm,
-    ) -> Option<Result<TokenData<T>, jsonwebtoken::errors::Error>> {
+    ) -> Option<Result<TokenData<T>, AuthError>> {
         let key = DecodingKey::from_secret(&[]);
-        let mut validation = Validation::new(algorithm);
+        let mut validation = self.access_token_validation(algorithm);
         validation.insecure_disable_signature_validation();
 
-        self.resource
-            .get()
+        self.resource_state()
             .and_then(Result::ok)
             .flatten()
-            .map(|response| decode::<T>(&response.access_token, &key, &validation))
+            .map(|response| {
+                token_algorithm(&response.access_token)?;
+                decode::<T>(&response.access_token, &key, &validation).map_err(AuthError::token)
+            })
     }
 
     /// Returns the authentication state, which may contain token storage information.
     pub fn ok(&self) -> Option<Option<TokenStorage>> {
-        self.resource.get().and_then(Result::ok)
+        self.resource_state().and_then(Result::ok)
     }
 
     /// Returns any authentication error that occurred during the process.
     pub fn err(&self) -> Option<AuthError> {
-        self.resource.get().and_then(Result::err)
+        self.resource_state().and_then(Result::err)
+    }
+
+    /// Returns the recently recorded authentication events (logins,
+    /// refreshes, failures and logouts), oldest first. The log is bounded by
+    /// `audit::AUDIT_LOG_CAPACITY`.
+    pub fn recent_events(&self) -> Result<Vec<AuthEvent>, AuthError> {
+        read_audit_log(self.parameters.storage.backend())
+    }
+
+    /// Returns the provider metadata of the discovery document, if it has
+    /// been preloaded during the callback handling. This requires the
+    /// `issuer` parameter, see `AuthParameters::from_issuer`.
+    #[must_use]
+    pub fn provider_metadata(&self) -> Option<ProviderMetadata> {
+        self.metadata.get()
     }
 
     /// This can be used to set the `redirect_uri` dynamically. It's helpful if
@@ -264,92 +1352,738 @@ impl Auth {
         self.parameters.redirect_uri = uri;
     }
 
-    /// Refresh the current access token with the current refresh token
+    /// Refresh the current access token with the current refresh token.
+    /// During a maintenance window of the provider, the refresh of a still
+    /// valid access token is deferred. Offline, a failed refresh is queued
+    /// until the browser is online again. After a transient failure the
+    /// tokens are kept, see `Auth::degraded`.
     pub fn refresh_token(&self) {
-        let token = self
-            .resource
-            .get()
+        let valid = self
+            .current_tokens()
+            .is_some_and(|tokens| !tokens.access_token_expired_with(self.parameters.leeway));
+        let fetched = self.maintenance.get_untracked().unwrap_or_default();
+        if valid && self.maintenance_window_at(&fetched, now()).is_some() {
+            return;
+        }
+        // A queued refresh is retried once the browser is online again.
+        if self.degraded.get_untracked() && is_offline() {
+            return;
+        }
+        if let Some(refresh) = self.pending_refresh() {
+            spawn_local(refresh);
+        }
+    }
+
+    /// Checks if a refresh of the tokens is currently in flight.
+    #[must_use]
+    pub fn refreshing(&self) -> bool {
+        self.refreshing.with(Option::is_some)
+    }
+
+    /// Sends the request with the current access token as `Bearer` token. If
+    /// the access token is expired, it's refreshed first. If the response is
+    /// `401 Unauthorized`, the access token is refreshed and the request is
+    /// retried once. The request is sent with the `HttpClient` of the
+    /// parameters.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the request couldn't be sent.
+    pub async fn authenticated_fetch(
+        &self,
+        request: HttpRequest,
+    ) -> Result<HttpResponse, AuthError> {
+        if self
+            .current_tokens()
+            .is_some_and(|tokens| tokens.access_token_expired_with(self.parameters.leeway))
+        {
+            self.await_refresh().await;
+        }
+
+        let response = self.send_authorized(request.clone()).await?;
+        if response.status != 401 {
+            return Ok(response);
+        }
+
+        self.await_refresh().await;
+        self.send_authorized(request).await
+    }
+
+    /// Sends the request with the current access token, if any. With DPoP,
+    /// the token is sent with a DPoP proof of the request.
+    async fn send_authorized(&self, request: HttpRequest) -> Result<HttpResponse, AuthError> {
+        let client = self.parameters.client();
+        let Some(tokens) = self.current_tokens() else {
+            return Ok(client.send(request).await?);
+        };
+
+        if let Some(dpop) = self.parameters.dpop_prover() {
+            let request = request.header("Authorization", format!("DPoP {}", tokens.access_token));
+
+            return Ok(send_with_dpop(
+                client.as_ref(),
+                dpop.as_ref(),
+                request,
+                Some(&tokens.access_token),
+            )
+            .await?);
+        }
+
+        let request = request.header("Authorization", format!("Bearer {}", tokens.access_token));
+        Ok(client.send(request).await?)
+    }
+
+    /// Renews the access token after it was rejected, e.g. by an API. The
+    /// tokens are refreshed, without a refresh token or if the refresh fails,
+    /// the user is logged in silently in the iframe, if a `silent_redirect_uri`
+    /// is set. Unlike `Auth::silent_login`, it never redirects to the
+    /// interactive login.
+    /// Returns `true` if a new access token was acquired.
+    pub(crate) async fn renew_access_token(&self) -> bool {
+        let previous = self.current_tokens().map(|tokens| tokens.access_token);
+        let renewed = |auth: &Auth| {
+            auth.current_tokens()
+                .is_some_and(|tokens| Some(&tokens.access_token) != previous.as_ref())
+        };
+        self.await_refresh().await;
+        if renewed(self) {
+            return true;
+        }
+        if self.parameters.silent_redirect_uri.is_none()
+            || self.parameters.silent_renewal == SilentRenewal::RefreshToken
+        {
+            return false;
+        }
+
+        self.silent_renew().await.is_ok() && renewed(self)
+    }
+
+    /// Refreshes the tokens and waits until the refresh has finished.
+    async fn await_refresh(&self) {
+        if let Some(refresh) = self.pending_refresh() {
+            refresh.await;
+        }
+    }
+
+    /// Returns the tokens without tracking them, for the use in async code.
+    fn current_tokens(&self) -> Option<TokenStorage> {
+        self.resource()
+            .get_untracked()
             .and_then(Result::ok)
             .flatten()
-            .map(|storage| storage.refresh_token);
-        let parameters = self.parameters.clone();
-        spawn_local(async move {
-            if let Some(token) = token {
-                let response = refresh_token(&parameters, token).await.map(Option::Some);
-                if response.is_err() {
-                    remove_token_storage().ok();
+    }
+
+    /// Returns the refresh in flight or starts a new one. Only a single
+    /// refresh is in flight at a time, some providers treat parallel requests
+    /// with the same refresh token as a reuse of the token and revoke the
+    /// session. Returns `None` if there is no refresh token.
+    fn pending_refresh(&self) -> Option<PendingRefresh> {
+        if let Some(refresh) = self.refreshing.get_untracked() {
+            return Some(refresh);
+        }
+
+        let token = self.current_tokens()?.refresh_token?;
+        let refresh = refresh_tokens(
+            self.parameters.clone(),
+            self.resource,
+            self.refreshing,
+            self.degraded,
+            token,
+        )
+        .boxed_local()
+        .shared();
+        self.refreshing.set(Some(refresh.clone()));
+
+        Some(refresh)
+    }
+}
+
+/// Refreshes the tokens and updates the resource, unless the session changed
+/// in the meantime. Clears the refresh in flight once it has finished.
+async fn refresh_tokens(
+    parameters: AuthParameters,
+    resource: Resource<bool, Result<Option<TokenStorage>, AuthError>>,
+    refreshing: RwSignal<Option<PendingRefresh>>,
+    degraded: RwSignal<bool>,
+    token: String,
+) {
+    let storage = parameters.storage.backend();
+    let previous = resource.get_untracked().and_then(Result::ok).flatten();
+    let response = refresh_token_with_backoff(&parameters, &token, previous.as_ref())
+        .await
+        .map_err(|cause| AuthError::RefreshFailed {
+            cause: Box::new(cause),
+        });
+    refreshing.set(None);
+    record_result(storage, &response, AuthEventKind::Refresh);
+    // Offline or after a transient failure, like a timeout or a server error
+    // of the provider, the tokens are kept instead of ending the session over
+    // a flaky connection. The refresh is retried once the browser is online
+    // again, or by the next refresh.
+    if response
+        .as_ref()
+        .is_err_and(|error| is_offline() || error.is_transient())
+    {
+        degraded.set(true);
+        return;
+    }
+    if response.is_ok() && degraded.get_untracked() {
+        degraded.set(false);
+    }
+
+    // The session might have changed in the meantime, e.g. by a logout. A
+    // stale response must not resurrect it.
+    let current_token = resource
+        .get_untracked()
+        .and_then(Result::ok)
+        .flatten()
+        .and_then(|storage| storage.refresh_token);
+    if current_token.as_ref() != Some(&token) {
+        return;
+    }
+    // Another tab might have refreshed the tokens concurrently. Its tokens
+    // win, the response of a rotated refresh token is likely an error anyway.
+    let key = parameters.token_storage_key();
+    let generation = previous
+        .as_ref()
+        .map_or(0, |previous| previous.refresh_generation);
+    if let Ok(Some(stored)) = read_token_storage(storage, &key) {
+        if stored.refresh_generation > generation || stored.refresh_token.as_ref() != Some(&token) {
+            resource.set(Ok(Some(stored)));
+            return;
+        }
+    }
+
+    // Nobody else rotated the refresh token, but the provider rejected it. A
+    // provider rotating refresh tokens revokes the whole grant, once a rotated
+    // token is reused, e.g. by a stale tab. An expired or revoked grant is
+    // reported as is, only a reuse reported by the provider ends the session
+    // with `RefreshTokenReused`.
+    let response = response.map_err(|error| match error {
+        AuthError::RefreshFailed { cause }
+            if matches!(&*cause, AuthError::InvalidGrant(response) if response.indicates_reuse()) =>
+        {
+            AuthError::RefreshTokenReused
+        }
+        error => error,
+    });
+    let response = response.and_then(|token_storage| {
+        persist_token_storage(storage, &key, &token_storage).map(|()| Some(token_storage))
+    });
+    if response.is_err() {
+        if let Err(error) = remove_token_storage(storage, &key) {
+            leptos::logging::error!("Unable to delete token: {error:#?}");
+        }
+    }
+    resource.set(response);
+}
+
+/// Sends the browser to the given URL, e.g. the login page of the provider.
+fn redirect(url: &str) {
+    if !leptos::leptos_dom::is_browser() {
+        return;
+    }
+    if let Err(error) = window().location().set_href(url) {
+        leptos::logging::error!("Unable to redirect to {url}: {error:#?}");
+    }
+}
+
+/// Removes the parameters of a handled callback from the URL, replacing the
+/// current history entry. Otherwise a reload would submit the stale code
+/// again.
+fn remove_callback_params(
+    parameters: &AuthParameters,
+    location: &Location,
+    navigate: impl Fn(&str, NavigateOptions),
+) {
+    let mut query = location.query.get_untracked();
+    let overrides = parameters.callback_param_overrides.keys();
+    for name in CALLBACK_PARAMS
+        .into_iter()
+        .chain(overrides.map(String::as_str))
+    {
+        query.remove(name);
+    }
+
+    // With the fragment response mode, the fragment contains the callback
+    // parameters only.
+    let hash = match parameters.callback_response_mode() {
+        ResponseMode::Query => location.hash.get_untracked(),
+        ResponseMode::Fragment => String::new(),
+    };
+    let url = format!(
+        "{}{}{hash}",
+        location.pathname.get_untracked(),
+        query.to_query_string(),
+    );
+    navigate(
+        &url,
+        NavigateOptions {
+            replace: true,
+            scroll: false,
+            ..NavigateOptions::default()
+        },
+    );
+}
+
+/// Checks if the callback arrived on the path of the matching redirect URI,
+/// the `post_logout_redirect_uri` for logouts, the `redirect_uri` otherwise.
+fn callback_path_matches(
+    parameters: &AuthParameters,
+    callback: &CallbackResponse,
+    location: &Location,
+) -> bool {
+    let uri = match callback {
+        CallbackResponse::SuccessLogout(_) => &parameters.post_logout_redirect_uri,
+        _ => &parameters.redirect_uri,
+    };
+    url::Url::parse(uri).is_ok_and(|uri| uri.path() == location.pathname.get_untracked())
+}
+
+/// Inserts the parameters of a URL fragment, with or without the leading
+/// `#`, into the map. The fragment contains the callback parameters with the
+/// fragment response mode.
+pub(crate) fn insert_fragment_params(params: &mut ParamsMap, fragment: &str) {
+    // The fragment has the same format as a query string, the base URL is
+    // only needed to parse it.
+    if let Ok(url) = url::Url::parse(&format!(
+        "http://localhost/?{}",
+        fragment.trim_start_matches('#')
+    )) {
+        for (key, value) in url.query_pairs() {
+            params.insert(key.into_owned(), value.into_owned());
+        }
+    }
+}
+
+/// Keeps the state in sync with other tabs, which log in, log out or refresh
+/// the tokens. The `storage` event is only dispatched for changes of the
+/// local storage, which were made by other tabs.
+fn sync_across_tabs(
+    storage: StorageKind,
+    key: String,
+    leeway: Duration,
+    active: RwSignal<bool>,
+    resource: Resource<bool, Result<Option<TokenStorage>, AuthError>>,
+) {
+    if !leptos::leptos_dom::is_browser() || !matches!(storage, StorageKind::Local) {
+        return;
+    }
+
+    // The listener lives as long as the app, the handle isn't needed.
+    let _ = window_event_listener(ev::storage, move |event| {
+        if event.key().as_ref() != Some(&key) || !active.get_untracked() {
+            return;
+        }
+        let state = restore_token_storage(storage.backend(), &key, leeway);
+        let current = resource.get_untracked().and_then(Result::ok).flatten();
+        if state.as_ref().ok().and_then(Option::as_ref) != current.as_ref() {
+            resource.set(state);
+        }
+    });
+}
+
+/// Returns a value of the pending login, like the PKCE code verifier or the
+/// nonce, or of the pending logout. If there is none yet, a new one is
+/// generated and written to the given storage, so that every generated URL
+/// carries the same values, see `StorageKind::transient_backend`.
+/// Returns `None` outside of the browser, because there is no session storage
+/// to keep it in.
+///
+/// # Errors
+///
+/// Returns an error if the value can't be read, generated or written. A
+/// value which isn't kept can't be verified on callback.
+fn pending_login_value(
+    storage: &dyn Storage,
+    key: &str,
+    read: fn(&dyn Storage, &str) -> Result<Option<String>, AuthError>,
+    write: fn(&dyn Storage, &str, &str) -> Result<(), AuthError>,
+    generate: impl FnOnce() -> Result<String, OidcError>,
+) -> Result<Option<String>, AuthError> {
+    if !leptos::leptos_dom::is_browser() {
+        return Ok(None);
+    }
+
+    let value = match read(storage, key)? {
+        Some(value) => value,
+        None => {
+            let value = generate()?;
+            write(storage, key, &value)?;
+            value
+        }
+    };
+
+    Ok(Some(value))
+}
+
+/// Returns the code verifier and the nonce of the pending login, see
+/// `pending_login_value`.
+fn pending_login_values(
+    storage: &dyn Storage,
+    key: &str,
+) -> Result<(Option<String>, Option<String>), AuthError> {
+    let code_verifier = pending_login_value(
+        storage,
+        key,
+        read_code_verifier,
+        write_code_verifier,
+        generate_code_verifier,
+    )?;
+    let nonce = pending_login_value(storage, key, read_nonce, write_nonce, || {
+        random_string(NONCE_BYTES)
+    })?;
+
+    Ok((code_verifier, nonce))
+}
+
+/// Returns the current authentication state. As long as the resource is
+/// loading, which it is during the whole server side rendering, the state read
+/// by the server is used, if the cookie storage is used.
+fn current_state(
+    resource: Resource<bool, Result<Option<TokenStorage>, AuthError>>,
+    server_state: Option<ServerState>,
+) -> Option<Result<Option<TokenStorage>, AuthError>> {
+    resource.get().or_else(|| {
+        server_state
+            .and_then(|server_state| server_state.get())
+            .map(|state| {
+                state
+                    .map(AuthStateSerde::into_token_storage)
+                    .map_err(AuthError::from)
+            })
+    })
+}
+
+/// Loads the initial authentication state. This is the only transition of
+/// the state during the initialization, there are no effects writing to it
+/// afterwards:
+/// - a login callback exchanges the code for the tokens,
+/// - a logout callback removes the tokens,
+/// - an error callback results in the provider error,
+/// - otherwise the tokens of a previous session are restored.
+async fn load_state(
+    parameters: &AuthParameters,
+    callback: Result<CallbackResponse, ParamsError>,
+    server_state: Option<ServerState>,
+    metadata: RwSignal<Option<ProviderMetadata>>,
+) -> Result<Option<TokenStorage>, AuthError> {
+    #[cfg(feature = "preview")]
+    if let Some(preview) = preview::current_preview() {
+        return preview::load_fixed_state(preview.state).await;
+    }
+
+    let storage = parameters.storage.backend();
+    let key = parameters.token_storage_key();
+    // A replayed logout callback, e.g. a reload of the callback URL, must not
+    // end a session established afterwards. It's treated like no callback.
+    let callback = match callback {
+        Ok(CallbackResponse::SuccessLogout(response))
+            if !consume_logout_state(
+                parameters.storage.transient_backend(),
+                &key,
+                response.state.as_deref(),
+            ) =>
+        {
+            Err(ParamsError::MissingParam(
+                "Missing or consumed parameter 'state'".to_string(),
+            ))
+        }
+        callback => callback,
+    };
+    match callback {
+        Ok(CallbackResponse::SuccessLogin(response)) => {
+            // The provider metadata is fetched concurrently, so it doesn't add
+            // up to the login latency.
+            let (result, preloaded) = join(
+                fetch_token(parameters, response),
+                preload_metadata(parameters),
+            )
+            .await;
+            if preloaded.is_some() {
+                metadata.set(preloaded);
+            }
+            record_result(storage, &result, AuthEventKind::Login);
+            result.map(Option::Some)
+        }
+        Ok(CallbackResponse::SuccessImplicitLogin(response)) => {
+            let (result, preloaded) = join(
+                fetch_implicit_token(parameters, response),
+                preload_metadata(parameters),
+            )
+            .await;
+            if preloaded.is_some() {
+                metadata.set(preloaded);
+            }
+            record_result(storage, &result, AuthEventKind::Login);
+            result.map(Option::Some)
+        }
+        Ok(CallbackResponse::SuccessLogout(response)) => {
+            if response.destroy_session {
+                if let Err(error) = remove_token_storage(storage, &key) {
+                    leptos::logging::error!("Unable to delete token: {error:#?}");
+                }
+                record_event(storage, AuthEventKind::Logout);
+                #[cfg(feature = "dpop")]
+                if parameters.dpop {
+                    dpop::delete_key(parameters);
                 }
-                expect_context::<Auth>().resource.set(response);
             }
-        });
+
+            Ok(None)
+        }
+        Ok(CallbackResponse::Error(error)) => {
+            let error = AuthError::provider(error);
+            record_event(storage, (&error).into());
+            Err(error)
+        }
+        Err(_) if matches!(parameters.storage, StorageKind::Cookie) => {
+            // The `HttpOnly` cookie can't be read by scripts, the state is
+            // taken from the server instead.
+            let state = match server_state.and_then(|state| state.get_untracked()) {
+                Some(state) => state,
+                None => read_auth_state().await,
+            }?
+            .into_token_storage();
+            if let Some(state) = &state {
+                let state_json = serde_json::to_string(state).map_err(Arc::new)?;
+                InMemoryStorage.set(&key, state_json.as_str())?;
+            }
+            Ok(state)
+        }
+        Err(_) => {
+            // Only the default key replaced the legacy key, a configured
+            // `storage_key` has never been stored there.
+            if parameters.storage_key.is_none() {
+                if let Err(error) = migrate_legacy_token_storage(storage, &key) {
+                    leptos::logging::error!("Unable to migrate the token storage: {error:#?}");
+                }
+            }
+            restore_token_storage(storage, &key, parameters.leeway)
+        }
     }
 }
 
+/// Checks the state of a logout callback against the pending logout state and
+/// consumes it, so every logout callback is only accepted once.
+fn consume_logout_state(storage: &dyn Storage, key: &str, state: Option<&str>) -> bool {
+    let pending = read_logout_state(storage, key)
+        .map_err(|error| leptos::logging::error!("Unable to read the logout state: {error:#?}"))
+        .ok()
+        .flatten();
+    match (state, pending) {
+        (Some(state), Some(pending)) if state == pending => {
+            if let Err(error) = remove_logout_state(storage, key) {
+                leptos::logging::error!("Unable to delete the logout state: {error:#?}");
+            }
+            true
+        }
+        _ => false,
+    }
+}
+
+/// Restores the token storage of a previous session. Unreadable sessions and
+/// sessions with an expired refresh token are removed.
+fn restore_token_storage(
+    storage: &dyn Storage,
+    key: &str,
+    leeway: Duration,
+) -> Result<Option<TokenStorage>, AuthError> {
+    match read_token_storage(storage, key) {
+        Err(error) => {
+            if let Err(error) = remove_token_storage(storage, key) {
+                leptos::logging::error!("Unable to delete token: {error:#?}");
+            }
+            Err(error)
+        }
+        Ok(Some(state)) if state.refresh_token_expired_with(leeway) => {
+            if let Err(error) = remove_token_storage(storage, key) {
+                leptos::logging::error!("Unable to delete token: {error:#?}");
+            }
+            Ok(None)
+        }
+        state => state,
+    }
+}
+
+/// Writes the token storage to the storage backend.
+fn persist_token_storage(
+    storage: &dyn Storage,
+    key: &str,
+    token_storage: &TokenStorage,
+) -> Result<(), AuthError> {
+    let token_storage_json = serde_json::to_string(token_storage).map_err(Arc::new)?;
+    write_to_token_storage(storage, key, token_storage_json.as_str())
+}
+
+/// Fetches the provider metadata of the configured issuer. Failures are only
+/// logged, because the metadata isn't required to complete the login.
+async fn preload_metadata(parameters: &AuthParameters) -> Option<ProviderMetadata> {
+    let issuer = parameters.issuer.as_deref()?;
+    ProviderMetadata::fetch_with_client(parameters.client().as_ref(), issuer)
+        .await
+        .map_err(|error| leptos::logging::error!("Unable to preload metadata: {error:#?}"))
+        .ok()
+}
+
 /// Asynchronous function for fetching an authentication token.
 /// This function is used to exchange an authorization code for an access token.
 async fn fetch_token(
     parameters: &AuthParameters,
     auth_response: SuccessCallbackResponse,
 ) -> Result<TokenStorage, AuthError> {
-    let mut body = "&grant_type=authorization_code"
-        .to_string()
-        .push_param_body("client_id", &parameters.client_id)
-        .push_param_body("redirect_uri", &parameters.redirect_uri)
-        .push_param_body("code", &auth_response.code);
-    if let Some(state) = &auth_response.session_state {
-        body = body.push_param_body("state", state);
-    }
-    let response = reqwest::Client::new()
-        .post(parameters.token_endpoint.clone())
-        .header("Content-Type", "application/x-www-form-urlencoded")
-        .body(body)
-        .send()
-        .await
-        .map_err(Arc::new)?
-        .json::<TokenResponse>()
-        .await
-        .map_err(Arc::new)?;
+    instrumented("fetch_token", async move {
+        let storage = parameters.storage.transient_backend();
+        let key = parameters.token_storage_key();
+        let code_verifier = read_code_verifier(storage, &key)?;
+        // The verifier belongs to exactly one authorization code, the next
+        // login has to use a fresh one.
+        remove_code_verifier(storage, &key)?;
+        let nonce = read_nonce(storage, &key)?;
+        let token_storage = OidcClient::new(parameters)
+            .exchange_code(
+                &auth_response.code,
+                auth_response.session_state.as_deref(),
+                code_verifier.as_deref(),
+                nonce.as_deref(),
+            )
+            .await?;
+        remove_nonce(storage, &key)?;
+
+        finish_login(parameters, token_storage)
+    })
+    .await
+}
 
-    let token_storage = match response {
-        TokenResponse::Success(success) => Ok(success.into()),
-        TokenResponse::Error(error) => Err(AuthError::Provider(error)),
-    }?;
+/// Completes a login with the received tokens: fills in the requested scopes
+/// and persists the tokens.
+fn finish_login(
+    parameters: &AuthParameters,
+    mut token_storage: TokenStorage,
+) -> Result<TokenStorage, AuthError> {
+    // A token response without `scope` grants the requested scopes, which
+    // differ from the configured ones after an incremental authorization.
+    let storage = parameters.storage.transient_backend();
+    let key = parameters.token_storage_key();
+    let requested_scope = read_requested_scope(storage, &key)?;
+    remove_requested_scope(storage, &key)?;
+    if token_storage.scope.is_none() {
+        token_storage.scope = requested_scope;
+    }
 
-    let token_storage_json = serde_json::to_string(&token_storage).map_err(Arc::new)?;
-    write_to_token_storage(token_storage_json.as_str())?;
+    persist_token_storage(
+        parameters.storage.backend(),
+        &parameters.token_storage_key(),
+        &token_storage,
+    )?;
+
+    trace_event!(
+        debug,
+        access_token = %crate::instrument::Redacted(&token_storage.access_token),
+        expires_in = %token_storage.expires_in,
+        "received tokens"
+    );
 
     Ok(token_storage)
 }
 
+/// Refreshes the tokens like `refresh_token`, but retries transient failures
+/// with an exponential backoff. The retries rely on the timers of the
+/// browser, outside of it the refresh isn't retried.
+async fn refresh_token_with_backoff(
+    parameters: &AuthParameters,
+    token: &str,
+    previous: Option<&TokenStorage>,
+) -> Result<TokenStorage, AuthError> {
+    instrumented("refresh_token", async move {
+        let mut retries = if leptos::leptos_dom::is_browser() {
+            REFRESH_RETRIES
+        } else {
+            0
+        };
+        let mut backoff = REFRESH_BACKOFF;
+        loop {
+            match refresh_token(parameters, token.to_string(), previous).await {
+                Err(error) if error.is_transient() && retries > 0 => {
+                    leptos::logging::error!("Unable to refresh token, retrying: {error:#?}");
+                    trace_event!(info, retries, %error, "retrying refresh");
+                    retries -= 1;
+                    sleep(backoff).await;
+                    backoff *= 2;
+                }
+                response => return response,
+            }
+        }
+    })
+    .await
+}
+
 /// Asynchronous function for refetching an authentication token.
 /// This function is used to exchange a new access token and refresh token.
+/// The caller is responsible for persisting the new tokens.
 async fn refresh_token(
     parameters: &AuthParameters,
     refresh_token: String,
+    previous: Option<&TokenStorage>,
 ) -> Result<TokenStorage, AuthError> {
-    let response = reqwest::Client::new()
-        .post(parameters.token_endpoint.clone())
-        .header("Content-Type", "application/x-www-form-urlencoded")
-        .body(
-            "&grant_type=refresh_token"
-                .to_string()
-                .push_param_body("client_id", &parameters.client_id)
-                .push_param_body("refresh_token", refresh_token),
-        )
-        .send()
-        .await
-        .map_err(Arc::new)?
-        .json::<TokenResponse>()
-        .await
-        .map_err(Arc::new)?;
+    #[cfg(feature = "preview")]
+    if let Some(preview) = preview::current_preview() {
+        return preview.refresh_response;
+    }
 
-    let token_storage = match response {
-        TokenResponse::Success(success) => Ok(success.into()),
-        TokenResponse::Error(error) => Err(AuthError::Provider(error)),
-    }?;
+    Ok(OidcClient::new(parameters)
+        .refresh(&refresh_token, previous)
+        .await?)
+}
 
-    let token_storage_json = serde_json::to_string(&token_storage).map_err(Arc::new)?;
-    write_to_token_storage(token_storage_json.as_str())?;
+/// Sends the form encoded body with the client authentication to the given
+/// endpoint of the provider, see `OidcClient::post_form`.
+pub(crate) async fn post_client_form(
+    parameters: &AuthParameters,
+    endpoint: &str,
+    params: Vec<(String, String)>,
+) -> Result<String, AuthError> {
+    Ok(OidcClient::new(parameters)
+        .post_form(endpoint, params)
+        .await?)
+}
 
-    Ok(token_storage)
+/// Builds a form request to an endpoint of the provider, like the
+/// introspection endpoint, with the client authentication of a confidential
+/// client (see `client_authentication`).
+pub(crate) fn client_form_request(
+    parameters: &AuthParameters,
+    endpoint: impl Into<String>,
+    params: Vec<(String, String)>,
+) -> HttpRequest {
+    let (body, authorization) = client_authentication(
+        &parameters.client_id,
+        parameters
+            .client_secret
+            .as_deref()
+            .map(|client_secret| (client_secret, parameters.client_auth_method)),
+        params,
+    );
+    let request = HttpRequest::post_form(endpoint, body);
+    match authorization {
+        Some(authorization) => request.header("Authorization", authorization),
+        None => request,
+    }
+}
+
+/// Sends the given request to the token endpoint and returns the successful
+/// token response, an error response of the provider is returned as error.
+pub(crate) async fn request_token(
+    client: &dyn HttpClient,
+    request: HttpRequest,
+) -> Result<SuccessTokenResponse, AuthError> {
+    let response = client.send(request).await?;
+    if response.status >= 500 {
+        return Err(AuthError::Http(response.status));
+    }
+
+    match response.json::<TokenResponse>()? {
+        TokenResponse::Success(success) => Ok(success),
+        TokenResponse::Error(error) => Err(AuthError::provider(error)),
+    }
 }
@@ -0,0 +1,150 @@
+/*
+* The MIT License (MIT)
+*
+* Copyright (c) 2023 Daniél Kerkmann <daniel@kerkmann.dev>
+*
+* Permission is hereby granted, free of charge, to any person obtaining a copy
+* of this software and associated documentation files (the "Software"), to deal
+* in the Software without restriction, including without limitation the rights
+* to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+* copies of the Software, and to permit persons to whom the Software is
+* furnished to do so, subject to the following conditions:
+*
+* The above copyright notice and this permission notice shall be included in all
+* copies or substantial portions of the Software.
+*
+* THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+* IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+* FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+* AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+* LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+* OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+* SOFTWARE.
+*/
+
+use std::{cell::Cell, rc::Rc, time::Duration};
+
+use chrono::NaiveDateTime;
+use leptos::{
+    create_effect, create_rw_signal, on_cleanup, set_timeout_with_handle, Signal, SignalGet,
+    SignalSet, TimeoutHandle,
+};
+use serde::Deserialize;
+
+use crate::{
+    client::{HttpClient, HttpRequest},
+    error::AuthError,
+    utils::now,
+    Auth,
+};
+
+/// The longest delay until `Auth::maintenance_mode` is re-evaluated, also if
+/// no window starts or ends earlier. Timers of the browser overflow after
+/// about 24 days.
+const MAX_MAINTENANCE_CHECK: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// A scheduled maintenance window of the provider, in UTC. During a window,
+/// refreshes of still valid access tokens are deferred and the login is
+/// reported as unavailable, see `Auth::maintenance_mode`.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct MaintenanceWindow {
+    pub start: NaiveDateTime,
+    pub end: NaiveDateTime,
+    /// An optional message of the provider, e.g. the reason.
+    #[serde(default)]
+    pub message: Option<String>,
+}
+
+impl MaintenanceWindow {
+    /// Checks if the window contains the given time.
+    #[must_use]
+    pub fn contains(&self, time: NaiveDateTime) -> bool {
+        self.start <= time && time < self.end
+    }
+
+    /// Fetches a JSON list of maintenance windows from the given URL.
+    pub async fn fetch_with_client(
+        client: &dyn HttpClient,
+        url: &str,
+    ) -> Result<Vec<Self>, AuthError> {
+        Ok(client
+            .send(HttpRequest::get(url))
+            .await?
+            .error_for_status()?
+            .json()?)
+    }
+}
+
+impl Auth {
+    /// Returns a signal of the current maintenance window of the provider,
+    /// `None` outside of maintenance windows. It's re-evaluated whenever a
+    /// window starts or ends and whenever the fetched windows change, until
+    /// the current reactive scope is disposed.
+    #[must_use]
+    pub fn maintenance_mode(&self) -> Signal<Option<MaintenanceWindow>> {
+        let tick = create_rw_signal(now());
+        if leptos::leptos_dom::is_browser() {
+            let timeout = Rc::new(Cell::new(None::<TimeoutHandle>));
+            let auth = self.clone();
+            create_effect({
+                let timeout = Rc::clone(&timeout);
+                move |_| {
+                    let time = tick.get();
+                    let fetched = auth.maintenance.get().unwrap_or_default();
+                    let delay = auth
+                        .next_maintenance_change(&fetched, time)
+                        .and_then(|next| (next - time).to_std().ok())
+                        .map_or(MAX_MAINTENANCE_CHECK, |delay| {
+                            delay.min(MAX_MAINTENANCE_CHECK)
+                        });
+                    let handle = set_timeout_with_handle(move || tick.set(now()), delay);
+                    if let Some(previous) = timeout.replace(handle.ok()) {
+                        previous.clear();
+                    }
+                }
+            });
+            on_cleanup(move || {
+                if let Some(handle) = timeout.take() {
+                    handle.clear();
+                }
+            });
+        }
+
+        let auth = self.clone();
+        Signal::derive(move || {
+            let fetched = auth.maintenance.get().unwrap_or_default();
+            auth.maintenance_window_at(&fetched, tick.get())
+        })
+    }
+
+    /// Returns the configured or fetched maintenance window containing the
+    /// given time.
+    pub(crate) fn maintenance_window_at(
+        &self,
+        fetched: &[MaintenanceWindow],
+        time: NaiveDateTime,
+    ) -> Option<MaintenanceWindow> {
+        self.parameters
+            .maintenance_windows
+            .iter()
+            .chain(fetched)
+            .find(|window| window.contains(time))
+            .cloned()
+    }
+
+    /// Returns the next start or end of a configured or fetched maintenance
+    /// window after the given time.
+    fn next_maintenance_change(
+        &self,
+        fetched: &[MaintenanceWindow],
+        time: NaiveDateTime,
+    ) -> Option<NaiveDateTime> {
+        self.parameters
+            .maintenance_windows
+            .iter()
+            .chain(fetched)
+            .flat_map(|window| [window.start, window.end])
+            .filter(|change| *change > time)
+            .min()
+    }
+}
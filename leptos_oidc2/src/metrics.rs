@@ -0,0 +1,316 @@
+/*
+* The MIT License (MIT)
+*
+* Copyright (c) 2023 Daniél Kerkmann <daniel@kerkmann.dev>
+*
+* Permission is hereby granted, free of charge, to any person obtaining a copy
+* of this software and associated documentation files (the "Software"), to deal
+* in the Software without restriction, including without limitation the rights
+* to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+* copies of the Software, and to permit persons to whom the Software is
+* furnished to do so, subject to the following conditions:
+*
+* The above copyright notice and this permission notice shall be included in all
+* copies or substantial portions of the Software.
+*
+* THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+* IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+* FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+* AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+* LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+* OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+* SOFTWARE.
+*/
+
+use std::{
+    collections::BTreeMap,
+    fmt::Write,
+    sync::{
+        atomic::{AtomicBool, AtomicU32, AtomicU64, Ordering},
+        Mutex, OnceLock,
+    },
+};
+
+use leptos::{server, ServerFnError};
+
+use crate::audit::AuthEventKind;
+
+/// The recorder receiving the auth events of this process, see `set_metrics`.
+static METRICS: OnceLock<&'static dyn Metrics> = OnceLock::new();
+
+/// Whether events are forwarded to the server, see
+/// `AuthParameters::report_metrics`. On the server, it enables
+/// `report_auth_event`.
+static REPORTING: AtomicBool = AtomicBool::new(false);
+
+/// The maximum amount of browser events accepted per client and minute, see
+/// `set_report_limit`.
+static REPORT_LIMIT: AtomicU32 = AtomicU32::new(60);
+
+/// The start of the current minute and the amount of browser events accepted
+/// within it, by the IP address of the client. Clients without a known
+/// address share one window.
+#[cfg(feature = "ssr")]
+static REPORT_WINDOWS: Mutex<BTreeMap<Option<std::net::IpAddr>, (std::time::Instant, u32)>> =
+    Mutex::new(BTreeMap::new());
+
+/// The error codes the failures are counted by, see `failure_label`. Every
+/// other code is counted as `other`, so the amount of counters is bounded.
+const FAILURE_LABELS: [&str; 18] = [
+    "invalid_request",
+    "invalid_client",
+    "invalid_grant",
+    "unauthorized_client",
+    "unsupported_grant_type",
+    "invalid_scope",
+    "access_denied",
+    "unsupported_response_type",
+    "server_error",
+    "temporarily_unavailable",
+    "login_required",
+    "consent_required",
+    "interaction_required",
+    "account_selection_required",
+    "invalid_token",
+    "invalid_dpop_proof",
+    "use_dpop_nonce",
+    "third_party_cookies_blocked",
+];
+
+/// A recorder of authentication events, e.g. to export them to a monitoring
+/// system.
+pub trait Metrics: Send + Sync {
+    /// Records a single event.
+    fn record(&self, kind: &AuthEventKind);
+}
+
+/// Registers the recorder of this process. This needs to be called once on
+/// startup, returns `false` if a recorder was already registered.
+pub fn set_metrics(metrics: &'static dyn Metrics) -> bool {
+    METRICS.set(metrics).is_ok()
+}
+
+/// Sets the maximum amount of browser events the server accepts per client
+/// and minute via `report_auth_event`, the default is 60. The endpoint can be
+/// called by anyone, events beyond the limit are dropped.
+pub fn set_report_limit(per_minute: u32) {
+    REPORT_LIMIT.store(per_minute, Ordering::Relaxed);
+}
+
+/// Enables forwarding the events of the browser to the server. On the
+/// server, which renders `Auth::init` as well, it enables accepting them.
+pub(crate) fn enable_reporting() {
+    REPORTING.store(true, Ordering::Relaxed);
+}
+
+/// Passes an event to the registered recorder. Without a recorder, e.g. in
+/// the browser, the event is forwarded to the server if reporting is enabled.
+pub(crate) fn observe(kind: &AuthEventKind) {
+    if let Some(metrics) = METRICS.get() {
+        metrics.record(kind);
+    } else if REPORTING.load(Ordering::Relaxed) {
+        let kind = kind.clone();
+        leptos::spawn_local(async move {
+            if let Err(error) = report_auth_event(kind).await {
+                leptos::logging::error!("Unable to report auth event: {error:#?}");
+            }
+        });
+    }
+}
+
+/// Records an event of the browser with the recorder of the server. It only
+/// accepts events if the server initialized `Auth` with
+/// `AuthParameters::report_metrics`, otherwise the endpoint is disabled. The
+/// endpoint is public, so the events are rate limited per client IP address,
+/// see `set_report_limit`.
+///
+/// # Errors
+///
+/// Returns an error if the request to the server fails, the reporting is
+/// disabled or the rate limit of the client is exceeded.
+#[server(ReportAuthEvent, "/api")]
+pub async fn report_auth_event(kind: AuthEventKind) -> Result<(), ServerFnError> {
+    if !REPORTING.load(Ordering::Relaxed) {
+        return Err(ServerFnError::ServerError(
+            "auth event reporting is disabled".to_string(),
+        ));
+    }
+    if !accept_report(client_address()) {
+        return Err(ServerFnError::ServerError(
+            "too many auth events".to_string(),
+        ));
+    }
+    if let Some(metrics) = METRICS.get() {
+        metrics.record(&kind);
+    }
+    Ok(())
+}
+
+/// Returns the IP address of the client of the current request. It's only
+/// known if the app is served with
+/// `into_make_service_with_connect_info::<SocketAddr>()`, behind a reverse
+/// proxy it's the address of the proxy.
+#[cfg(feature = "ssr")]
+fn client_address() -> Option<std::net::IpAddr> {
+    use axum::extract::ConnectInfo;
+
+    let parts = leptos::use_context::<http::request::Parts>()?;
+    parts
+        .extensions
+        .get::<ConnectInfo<std::net::SocketAddr>>()
+        .map(|ConnectInfo(address)| address.ip())
+}
+
+/// Counts a browser event against the rate limit of the client in the
+/// current minute and checks if it's accepted.
+#[cfg(feature = "ssr")]
+fn accept_report(client: Option<std::net::IpAddr>) -> bool {
+    let Ok(mut windows) = REPORT_WINDOWS.lock() else {
+        return false;
+    };
+    let now = std::time::Instant::now();
+    // Only the clients of the current minute are kept, so the windows don't
+    // grow with every address which ever reported.
+    windows.retain(|_, (start, _)| now.duration_since(*start).as_secs() < 60);
+    let (_, count) = windows.entry(client).or_insert((now, 0));
+    if *count >= REPORT_LIMIT.load(Ordering::Relaxed) {
+        return false;
+    }
+    *count += 1;
+    true
+}
+
+/// Returns the label a failure with the given error code is counted by: the
+/// code itself if it's a known OAuth error code, `other` otherwise.
+fn failure_label(code: &str) -> &'static str {
+    FAILURE_LABELS
+        .into_iter()
+        .find(|label| *label == code)
+        .unwrap_or("other")
+}
+
+/// Counters of the authentication events, rendered in the Prometheus text
+/// format. It's meant to be a `static`:
+///
+/// ```rust,ignore
+/// static METRICS: AuthMetrics = AuthMetrics::new();
+///
+/// leptos_oidc2::metrics::set_metrics(&METRICS);
+/// ```
+#[derive(Debug, Default)]
+pub struct AuthMetrics {
+    logins: AtomicU64,
+    refreshes: AtomicU64,
+    logouts: AtomicU64,
+    failures: Mutex<BTreeMap<&'static str, u64>>,
+}
+
+impl AuthMetrics {
+    /// Creates new counters, all starting at zero.
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            logins: AtomicU64::new(0),
+            refreshes: AtomicU64::new(0),
+            logouts: AtomicU64::new(0),
+            failures: Mutex::new(BTreeMap::new()),
+        }
+    }
+
+    /// Renders the counters in the Prometheus text exposition format.
+    #[must_use]
+    pub fn render(&self) -> String {
+        let mut output = String::new();
+        write_counter(
+            &mut output,
+            "leptos_oidc_logins_total",
+            "Successful logins, i.e. code exchanges.",
+            &[(None, self.logins.load(Ordering::Relaxed))],
+        );
+        write_counter(
+            &mut output,
+            "leptos_oidc_refreshes_total",
+            "Successful token refreshes.",
+            &[(None, self.refreshes.load(Ordering::Relaxed))],
+        );
+        write_counter(
+            &mut output,
+            "leptos_oidc_logouts_total",
+            "Logouts.",
+            &[(None, self.logouts.load(Ordering::Relaxed))],
+        );
+
+        let failures = self
+            .failures
+            .lock()
+            .map(|failures| {
+                failures
+                    .iter()
+                    .map(|(code, count)| (Some(*code), *count))
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_default();
+        write_counter(
+            &mut output,
+            "leptos_oidc_failures_total",
+            "Failed authentication requests by OAuth error code, or other.",
+            &failures,
+        );
+
+        output
+    }
+
+    /// Returns the rendered counters as response of a `/metrics` endpoint,
+    /// e.g. for an axum route:
+    ///
+    /// ```rust,ignore
+    /// .route("/metrics", get(|| async { METRICS.response() }))
+    /// ```
+    #[cfg(feature = "ssr")]
+    #[must_use]
+    pub fn response(&self) -> http::Response<String> {
+        let mut response = http::Response::new(self.render());
+        response.headers_mut().insert(
+            http::header::CONTENT_TYPE,
+            http::HeaderValue::from_static("text/plain; version=0.0.4"),
+        );
+        response
+    }
+}
+
+impl Metrics for AuthMetrics {
+    fn record(&self, kind: &AuthEventKind) {
+        let counter = match kind {
+            AuthEventKind::Login => &self.logins,
+            AuthEventKind::Refresh => &self.refreshes,
+            AuthEventKind::Logout => &self.logouts,
+            AuthEventKind::Failure { code } => {
+                if let Ok(mut failures) = self.failures.lock() {
+                    *failures.entry(failure_label(code)).or_default() += 1;
+                }
+                return;
+            }
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+/// Writes a counter with its samples, labeled by the error code.
+fn write_counter(output: &mut String, name: &str, help: &str, samples: &[(Option<&str>, u64)]) {
+    let _ = writeln!(output, "# HELP {name} {help}");
+    let _ = writeln!(output, "# TYPE {name} counter");
+    for (code, value) in samples {
+        let _ = match code {
+            Some(code) => writeln!(output, "{name}{{code=\"{}\"}} {value}", escape_label(code)),
+            None => writeln!(output, "{name} {value}"),
+        };
+    }
+}
+
+/// Escapes a label value, see the Prometheus text format.
+fn escape_label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
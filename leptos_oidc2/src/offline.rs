@@ -0,0 +1,61 @@
+/*
+* The MIT License (MIT)
+*
+* Copyright (c) 2023 Daniél Kerkmann <daniel@kerkmann.dev>
+*
+* Permission is hereby granted, free of charge, to any person obtaining a copy
+* of this software and associated documentation files (the "Software"), to deal
+* in the Software without restriction, including without limitation the rights
+* to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+* copies of the Software, and to permit persons to whom the Software is
+* furnished to do so, subject to the following conditions:
+*
+* The above copyright notice and this permission notice shall be included in all
+* copies or substantial portions of the Software.
+*
+* THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+* IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+* FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+* AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+* LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+* OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+* SOFTWARE.
+*/
+
+use leptos::{ev, window, window_event_listener, Signal, SignalGetUntracked, SignalSet};
+
+use crate::Auth;
+
+/// Checks if the browser is offline. Outside of the browser, it's always
+/// online.
+pub(crate) fn is_offline() -> bool {
+    leptos::leptos_dom::is_browser() && !window().navigator().on_line()
+}
+
+impl Auth {
+    /// Returns a signal, which is `true` while a refresh failed offline or
+    /// with a transient error, until a refresh succeeds. Offline, the refresh
+    /// is queued until the browser is online again. The tokens are kept in
+    /// the meantime, see `AuthState::Degraded`.
+    #[must_use]
+    pub fn degraded(&self) -> Signal<bool> {
+        self.degraded.into()
+    }
+
+    /// Retries a queued refresh once the browser is online again. The
+    /// listener lives as long as the app.
+    pub(crate) fn watch_connectivity(&self) {
+        if !leptos::leptos_dom::is_browser() {
+            return;
+        }
+
+        let auth = self.clone();
+        // The listener lives as long as the app, the handle isn't needed.
+        let _ = window_event_listener(ev::online, move |_| {
+            if auth.degraded.get_untracked() {
+                auth.degraded.set(false);
+                auth.refresh_token();
+            }
+        });
+    }
+}
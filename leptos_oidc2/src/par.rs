@@ -0,0 +1,114 @@
+/*
+* The MIT License (MIT)
+*
+* Copyright (c) 2023 Daniél Kerkmann <daniel@kerkmann.dev>
+*
+* Permission is hereby granted, free of charge, to any person obtaining a copy
+* of this software and associated documentation files (the "Software"), to deal
+* in the Software without restriction, including without limitation the rights
+* to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+* copies of the Software, and to permit persons to whom the Software is
+* furnished to do so, subject to the following conditions:
+*
+* The above copyright notice and this permission notice shall be included in all
+* copies or substantial portions of the Software.
+*
+* THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+* IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+* FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+* AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+* LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+* OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+* SOFTWARE.
+*/
+
+use leptos::spawn_local;
+
+use crate::{
+    audit::record_event, error::AuthError, interstitial::RedirectKind, pending_login_values,
+    redirect, Auth, Scopes,
+};
+
+impl Auth {
+    /// Returns the login URL like `Auth::login_url`. With
+    /// `pushed_authorization` enabled, the authorization request is
+    /// pushed to the provider first and the URL only carries the returned
+    /// `request_uri`, which keeps the request parameters, like the
+    /// `login_hint`, out of the browser history.
+    ///
+    /// # Errors
+    ///
+    /// Returns the provider error of the pushed authorization request
+    /// endpoint or a failed request.
+    pub async fn pushed_login_url(&self) -> Result<String, AuthError> {
+        self.resolve_authorization_url(&self.parameters.scope).await
+    }
+
+    /// Returns the URL of the authorization request for the given scopes,
+    /// pushing the request first if `pushed_authorization` is enabled.
+    pub(crate) async fn resolve_authorization_url(
+        &self,
+        scope: &Scopes,
+    ) -> Result<String, AuthError> {
+        if !self.requires_pushed_login() {
+            return self.authorization_url(scope);
+        }
+
+        let (code_verifier, nonce) = pending_login_values(
+            self.parameters.storage.transient_backend(),
+            &self.parameters.token_storage_key(),
+        )?;
+        Ok(self
+            .oidc_client()
+            .push_authorization_request(scope, code_verifier.as_deref(), nonce.as_deref())
+            .await?)
+    }
+
+    /// Sends the browser to the authorization request for the given scopes.
+    /// With `pushed_authorization` enabled, the request is pushed in
+    /// the background first. If `announce` is set, a mounted
+    /// `RedirectingToLogin` component announces the redirect.
+    pub(crate) fn redirect_to_authorization(&self, scope: Scopes, announce: bool) {
+        let redirect_to = {
+            let auth = self.clone();
+            move |url: String| {
+                if announce {
+                    auth.redirect_to_provider(RedirectKind::Login, url, false);
+                } else {
+                    redirect(&url);
+                }
+            }
+        };
+        if !self.requires_pushed_login() {
+            match self.authorization_url(&scope) {
+                Ok(url) => redirect_to(url),
+                Err(error) => {
+                    leptos::logging::error!("Unable to prepare login: {error:#?}");
+                    record_event(self.parameters.storage.backend(), (&error).into());
+                }
+            }
+            return;
+        }
+
+        let auth = self.clone();
+        spawn_local(async move {
+            match auth.resolve_authorization_url(&scope).await {
+                Ok(url) => redirect_to(url),
+                Err(error) => {
+                    leptos::logging::error!("Unable to push the authorization request: {error:#?}");
+                    record_event(auth.parameters.storage.backend(), (&error).into());
+                }
+            }
+        });
+    }
+
+    /// Checks if the login requires a pushed authorization request, so the
+    /// plain `Auth::login_url` can't be used.
+    pub(crate) fn requires_pushed_login(&self) -> bool {
+        self.parameters.pushed_authorization
+            && self
+                .parameters
+                .pushed_authorization_request_endpoint
+                .is_some()
+    }
+}
@@ -0,0 +1,129 @@
+/*
+* The MIT License (MIT)
+*
+* Copyright (c) 2023 Daniél Kerkmann <daniel@kerkmann.dev>
+*
+* Permission is hereby granted, free of charge, to any person obtaining a copy
+* of this software and associated documentation files (the "Software"), to deal
+* in the Software without restriction, including without limitation the rights
+* to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+* copies of the Software, and to permit persons to whom the Software is
+* furnished to do so, subject to the following conditions:
+*
+* The above copyright notice and this permission notice shall be included in all
+* copies or substantial portions of the Software.
+*
+* THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+* IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+* FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+* AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+* LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+* OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+* SOFTWARE.
+*/
+
+use std::cell::RefCell;
+
+use chrono::NaiveDateTime;
+use leptos::{create_local_resource, create_rw_signal, on_cleanup};
+use oidc_core::utils::set_fixed_time;
+
+use crate::{
+    context::provide_auth,
+    error::AuthError,
+    state::AuthState,
+    storage::{StorageKind, TokenStorage},
+    utils::now,
+    Auth, AuthParameters,
+};
+
+thread_local! {
+    /// The active preview, see `set_preview`.
+    static PREVIEW: RefCell<Option<Preview>> = const { RefCell::new(None) };
+}
+
+/// Canned data replacing the provider and the clock, to render components
+/// like `Authenticated` in isolation, e.g. in component previews, without a
+/// provider. No request is sent while a preview is active.
+#[derive(Debug, Clone)]
+pub struct Preview {
+    /// The authentication state, instead of reading the storage or
+    /// exchanging the code of a callback. `AuthState::Loading` never
+    /// finishes loading.
+    pub state: AuthState,
+    /// The fixed time, e.g. to check the expiry of the tokens.
+    pub now: NaiveDateTime,
+    /// The response of a token refresh.
+    pub refresh_response: Result<TokenStorage, AuthError>,
+}
+
+/// Activates the given preview for the current thread. This needs to be
+/// called before `Auth::init`.
+pub fn set_preview(preview: Preview) {
+    set_fixed_time(Some(preview.now));
+    PREVIEW.with(|current| *current.borrow_mut() = Some(preview));
+}
+
+/// Deactivates the preview of the current thread.
+pub fn clear_preview() {
+    set_fixed_time(None);
+    PREVIEW.with(|current| *current.borrow_mut() = None);
+}
+
+/// Returns the active preview, if any.
+pub(crate) fn current_preview() -> Option<Preview> {
+    PREVIEW.with(|current| current.borrow().clone())
+}
+
+/// Resolves the fixed state like the storage would, `AuthState::Loading`
+/// never resolves.
+pub(crate) async fn load_fixed_state(state: AuthState) -> Result<Option<TokenStorage>, AuthError> {
+    match state {
+        AuthState::Loading => futures::future::pending().await,
+        AuthState::Unauthenticated => Ok(None),
+        AuthState::Authenticated(tokens) | AuthState::Degraded(tokens) => Ok(Some(tokens)),
+        AuthState::Error(error) => Err(error),
+    }
+}
+
+impl Auth {
+    /// Initializes an `Auth` instance with a fixed authentication state and
+    /// provides it as context, to unit test components using e.g.
+    /// `Authenticated` or `use_auth` without a provider. Unlike `init`, it
+    /// needs no `Router`, neither parses a callback nor reads the token
+    /// storage and keeps the tokens in memory.
+    ///
+    /// If no preview is active, one is activated with the given state, the
+    /// current time and failing refreshes, so that no request is sent, and
+    /// deactivated once the reactive owner is cleaned up, e.g. the test's
+    /// runtime is disposed. Set a `Preview` beforehand to control the clock or
+    /// the refresh response, it's kept until `clear_preview`.
+    ///
+    /// ```rust,ignore
+    /// let auth = Auth::init_with_state(AuthParameters::default(), AuthState::Unauthenticated);
+    /// ```
+    #[allow(clippy::must_use_candidate)]
+    pub fn init_with_state(mut parameters: AuthParameters, state: AuthState) -> Self {
+        if current_preview().is_none() {
+            set_preview(Preview {
+                state: state.clone(),
+                now: now(),
+                refresh_response: Err(AuthError::Unauthenticated),
+            });
+            on_cleanup(clear_preview);
+        }
+        parameters.storage = StorageKind::InMemory;
+
+        let resource = create_local_resource(|| true, move |_| load_fixed_state(state.clone()));
+        let auth = Self::assemble(
+            parameters,
+            create_rw_signal(true),
+            create_rw_signal(None),
+            resource,
+            None,
+        );
+        provide_auth(auth.clone());
+
+        auth
+    }
+}
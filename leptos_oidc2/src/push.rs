@@ -0,0 +1,165 @@
+/*
+* The MIT License (MIT)
+*
+* Copyright (c) 2023 Daniél Kerkmann <daniel@kerkmann.dev>
+*
+* Permission is hereby granted, free of charge, to any person obtaining a copy
+* of this software and associated documentation files (the "Software"), to deal
+* in the Software without restriction, including without limitation the rights
+* to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+* copies of the Software, and to permit persons to whom the Software is
+* furnished to do so, subject to the following conditions:
+*
+* The above copyright notice and this permission notice shall be included in all
+* copies or substantial portions of the Software.
+*
+* THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+* IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+* FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+* AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+* LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+* OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+* SOFTWARE.
+*/
+
+use std::rc::Rc;
+
+use leptos::{create_effect, spawn_local, wasm_bindgen::JsCast, wasm_bindgen::JsValue, window};
+use wasm_bindgen_futures::JsFuture;
+use web_sys::{
+    PushManager, PushSubscription, PushSubscriptionOptionsInit, ServiceWorkerRegistration,
+};
+
+use crate::{client::HttpRequest, error::AuthError, Auth};
+
+/// Options of `Auth::sync_push_subscription`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PushOptions {
+    /// The public VAPID key of the push server, base64url encoded.
+    pub application_server_key: String,
+    /// The API endpoint, which receives the subscription as JSON with a
+    /// `POST` and associates it with the authenticated user.
+    pub endpoint: String,
+}
+
+impl Auth {
+    /// Keeps the WebPush subscription of the browser in sync with the
+    /// authenticated user. After a login, the subscription is registered with
+    /// `PushOptions::endpoint` via `authenticated_fetch`, so the API
+    /// associates it with the user. After an account switch, the old
+    /// subscription is replaced by a new one, after a logout it's
+    /// unsubscribed. The push service answers with `410 Gone` for removed
+    /// subscriptions, which is when the API should drop them.
+    ///
+    /// This needs an active service worker, the sync lives as long as the
+    /// current reactive owner.
+    pub fn sync_push_subscription(&self, options: PushOptions) {
+        let auth = self.clone();
+        let options = Rc::new(options);
+        create_effect(move |previous: Option<Option<String>>| {
+            let previous = previous.flatten();
+            let subject = auth.subject();
+            if previous == subject {
+                return subject;
+            }
+
+            let (auth, options) = (auth.clone(), Rc::clone(&options));
+            let switched = previous.is_some();
+            let authenticated = subject.is_some();
+            spawn_local(async move {
+                let result = match (switched, authenticated) {
+                    (true, true) => match unsubscribe().await {
+                        Ok(()) => register(&auth, &options).await,
+                        Err(error) => Err(error),
+                    },
+                    (false, true) => register(&auth, &options).await,
+                    (true, false) => unsubscribe().await,
+                    (false, false) => Ok(()),
+                };
+                if let Err(error) = result {
+                    leptos::logging::error!("Unable to sync push subscription: {error:#?}");
+                }
+            });
+
+            subject
+        });
+    }
+}
+
+/// Subscribes to push messages, reusing an existing subscription, and
+/// registers the subscription with the API.
+async fn register(auth: &Auth, options: &PushOptions) -> Result<(), AuthError> {
+    let push_manager = push_manager().await?;
+    let subscription = match subscription(&push_manager).await? {
+        Some(subscription) => subscription,
+        None => {
+            let mut subscribe_options = PushSubscriptionOptionsInit::new();
+            subscribe_options
+                .user_visible_only(true)
+                .application_server_key(Some(&JsValue::from_str(&options.application_server_key)));
+            let promise = push_manager
+                .subscribe_with_options(&subscribe_options)
+                .map_err(push_error)?;
+            JsFuture::from(promise)
+                .await
+                .and_then(JsCast::dyn_into::<PushSubscription>)
+                .map_err(push_error)?
+        }
+    };
+    let body = js_sys::JSON::stringify(&subscription)
+        .map_err(push_error)?
+        .as_string()
+        .unwrap_or_default();
+
+    let response = auth
+        .authenticated_fetch(
+            HttpRequest::new("POST", &options.endpoint)
+                .header("Content-Type", "application/json")
+                .body(body),
+        )
+        .await?;
+    if !response.is_success() {
+        return Err(AuthError::Push(format!(
+            "registration rejected with {}",
+            response.status
+        )));
+    }
+
+    Ok(())
+}
+
+/// Removes the current subscription, if any.
+async fn unsubscribe() -> Result<(), AuthError> {
+    if let Some(subscription) = subscription(&push_manager().await?).await? {
+        let promise = subscription.unsubscribe().map_err(push_error)?;
+        JsFuture::from(promise).await.map_err(push_error)?;
+    }
+
+    Ok(())
+}
+
+/// Returns the push manager of the active service worker.
+async fn push_manager() -> Result<PushManager, AuthError> {
+    let ready = window()
+        .navigator()
+        .service_worker()
+        .ready()
+        .map_err(push_error)?;
+    JsFuture::from(ready)
+        .await
+        .and_then(JsCast::dyn_into::<ServiceWorkerRegistration>)
+        .and_then(|registration| registration.push_manager())
+        .map_err(push_error)
+}
+
+/// Returns the current subscription, if any.
+async fn subscription(push_manager: &PushManager) -> Result<Option<PushSubscription>, AuthError> {
+    let promise = push_manager.get_subscription().map_err(push_error)?;
+    let subscription = JsFuture::from(promise).await.map_err(push_error)?;
+    Ok(subscription.dyn_into::<PushSubscription>().ok())
+}
+
+/// Converts an error of the browser.
+fn push_error(error: JsValue) -> AuthError {
+    AuthError::Push(format!("{error:?}"))
+}
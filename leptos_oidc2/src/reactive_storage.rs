@@ -0,0 +1,136 @@
+/*
+* The MIT License (MIT)
+*
+* Copyright (c) 2023 Daniél Kerkmann <daniel@kerkmann.dev>
+*
+* Permission is hereby granted, free of charge, to any person obtaining a copy
+* of this software and associated documentation files (the "Software"), to deal
+* in the Software without restriction, including without limitation the rights
+* to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+* copies of the Software, and to permit persons to whom the Software is
+* furnished to do so, subject to the following conditions:
+*
+* The above copyright notice and this permission notice shall be included in all
+* copies or substantial portions of the Software.
+*
+* THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+* IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+* FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+* AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+* LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+* OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+* SOFTWARE.
+*/
+
+use std::{collections::HashMap, fmt, rc::Rc};
+
+use leptos::{Signal, SignalGet, SignalGetUntracked, SignalSet, WriteSignal};
+use leptos_use::storage::{use_storage, StorageType, StringCodec};
+
+use crate::{
+    error::{AuthError, StorageErrorKind},
+    storage::{Storage, StorageKind, AUDIT_LOG_KEY},
+};
+
+/// A reactive `leptos-use` storage signal of a single key.
+struct Entry {
+    value: Signal<String>,
+    set_value: WriteSignal<String>,
+    remove: Rc<dyn Fn()>,
+}
+
+/// A storage backend using the reactive storage signals of `leptos-use`
+/// (`use_storage`). Every write goes through `leptos-use`, so apps observing
+/// the same keys with `use_local_storage` are notified about token changes,
+/// in this and in other tabs.
+///
+/// The signals are created eagerly, so the storage needs to be created
+/// inside a component, e.g. right before `Auth::init`, with the key of the
+/// token storage, see `AuthParameters::token_storage_key`.
+pub struct LeptosUseStorage {
+    token_storage_key: String,
+    entries: HashMap<String, Entry>,
+}
+
+impl LeptosUseStorage {
+    /// Creates a storage backed by the local storage of the browser.
+    #[must_use]
+    pub fn local(token_storage_key: &str) -> Self {
+        Self::new(&StorageType::Local, token_storage_key)
+    }
+
+    /// Creates a storage backed by the session storage of the browser.
+    #[must_use]
+    pub fn session(token_storage_key: &str) -> Self {
+        Self::new(&StorageType::Session, token_storage_key)
+    }
+
+    /// Creates a storage backed by the given `leptos-use` storage type.
+    #[must_use]
+    pub fn new(storage_type: &StorageType, token_storage_key: &str) -> Self {
+        let entries = [token_storage_key, AUDIT_LOG_KEY]
+            .into_iter()
+            .map(|key| {
+                let (value, set_value, remove) =
+                    use_storage::<String, StringCodec>(storage_type.clone(), key);
+                let entry = Entry {
+                    value,
+                    set_value,
+                    remove: Rc::new(remove),
+                };
+                (key.to_string(), entry)
+            })
+            .collect();
+
+        Self {
+            token_storage_key: token_storage_key.to_string(),
+            entries,
+        }
+    }
+
+    /// Returns a signal, which is `true` as long as tokens are stored.
+    #[must_use]
+    pub fn token_present(&self) -> Signal<bool> {
+        let value = self.entry(&self.token_storage_key).map(|entry| entry.value);
+        Signal::derive(move || value.is_some_and(|value| !value.get().is_empty()))
+    }
+
+    /// Leaks the storage, which lives as long as the app anyway, and returns
+    /// it as a custom `StorageKind` for the `AuthParameters`.
+    #[must_use]
+    pub fn into_storage_kind(self) -> StorageKind {
+        StorageKind::Custom(Box::leak(Box::new(self)))
+    }
+
+    /// Returns the signals of the given key.
+    fn entry(&self, key: &str) -> Result<&Entry, AuthError> {
+        self.entries
+            .get(key)
+            .ok_or(AuthError::Storage(StorageErrorKind::Unavailable))
+    }
+}
+
+impl fmt::Debug for LeptosUseStorage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("LeptosUseStorage")
+            .field("keys", &self.entries.keys().collect::<Vec<_>>())
+            .finish()
+    }
+}
+
+impl Storage for LeptosUseStorage {
+    fn get(&self, key: &str) -> Result<Option<String>, AuthError> {
+        let value = self.entry(key)?.value.get_untracked();
+        Ok(Some(value).filter(|value| !value.is_empty()))
+    }
+
+    fn set(&self, key: &str, value: &str) -> Result<(), AuthError> {
+        self.entry(key)?.set_value.set(value.to_string());
+        Ok(())
+    }
+
+    fn remove(&self, key: &str) -> Result<(), AuthError> {
+        (self.entry(key)?.remove)();
+        Ok(())
+    }
+}
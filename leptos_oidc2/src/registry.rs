@@ -0,0 +1,82 @@
+/*
+* The MIT License (MIT)
+*
+* Copyright (c) 2023 Daniél Kerkmann <daniel@kerkmann.dev>
+*
+* Permission is hereby granted, free of charge, to any person obtaining a copy
+* of this software and associated documentation files (the "Software"), to deal
+* in the Software without restriction, including without limitation the rights
+* to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+* copies of the Software, and to permit persons to whom the Software is
+* furnished to do so, subject to the following conditions:
+*
+* The above copyright notice and this permission notice shall be included in all
+* copies or substantial portions of the Software.
+*
+* THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+* IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+* FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+* AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+* LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+* OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+* SOFTWARE.
+*/
+
+use std::{collections::HashMap, fmt};
+
+use leptos::{provide_context, store_value, use_context, StoredValue};
+
+use crate::Auth;
+
+/// A registry of several `Auth` instances keyed by a provider ID, e.g. a
+/// corporate SSO and a customer login in the same app. The instances are
+/// registered by `Auth::init_provider`.
+#[derive(Clone, Copy)]
+pub struct AuthRegistry {
+    instances: StoredValue<HashMap<String, Auth>>,
+}
+
+impl AuthRegistry {
+    /// Returns the instance of the given provider ID, if registered.
+    #[must_use]
+    pub fn get(&self, id: &str) -> Option<Auth> {
+        self.instances
+            .with_value(|instances| instances.get(id).cloned())
+    }
+
+    /// Returns the IDs of the registered providers.
+    #[must_use]
+    pub fn ids(&self) -> Vec<String> {
+        self.instances
+            .with_value(|instances| instances.keys().cloned().collect())
+    }
+
+    /// Registers the instance under the given provider ID, replacing a
+    /// previous one.
+    pub(crate) fn insert(&self, id: &str, auth: Auth) {
+        self.instances.update_value(|instances| {
+            instances.insert(id.to_string(), auth);
+        });
+    }
+}
+
+impl fmt::Debug for AuthRegistry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AuthRegistry")
+            .field("ids", &self.ids())
+            .finish()
+    }
+}
+
+/// Returns the `AuthRegistry` of the current context, providing a new one if
+/// there is none yet.
+#[must_use]
+pub fn use_auth_registry() -> AuthRegistry {
+    use_context::<AuthRegistry>().unwrap_or_else(|| {
+        let registry = AuthRegistry {
+            instances: store_value(HashMap::new()),
+        };
+        provide_context(registry);
+        registry
+    })
+}
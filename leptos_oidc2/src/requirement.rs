@@ -0,0 +1,129 @@
+/*
+* The MIT License (MIT)
+*
+* Copyright (c) 2023 Daniél Kerkmann <daniel@kerkmann.dev>
+*
+* Permission is hereby granted, free of charge, to any person obtaining a copy
+* of this software and associated documentation files (the "Software"), to deal
+* in the Software without restriction, including without limitation the rights
+* to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+* copies of the Software, and to permit persons to whom the Software is
+* furnished to do so, subject to the following conditions:
+*
+* The above copyright notice and this permission notice shall be included in all
+* copies or substantial portions of the Software.
+*
+* THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+* IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+* FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+* AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+* LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+* OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+* SOFTWARE.
+*/
+
+use chrono::Duration;
+
+use crate::{utils::now, Auth};
+
+/// A composable authorization requirement, checked by `Auth::check_access`
+/// and the `AuthGuard` component, e.g.
+///
+/// ```ignore
+/// Requirement::role("admin")
+///     .or(Requirement::scope("ops"))
+///     .and(Requirement::fresh(Duration::minutes(5)))
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Requirement {
+    /// The user is authenticated.
+    Authenticated,
+    /// The access token contains the role, see `Auth::has_role`.
+    Role(String),
+    /// The access token contains the permission, see `Auth::has_permission`.
+    Permission(String),
+    /// The access token contains the scope, see `Auth::has_scope`.
+    Scope(String),
+    /// The user authenticated at most the given duration ago.
+    Fresh(Duration),
+    /// All of the requirements are met.
+    All(Vec<Requirement>),
+    /// Any of the requirements is met.
+    Any(Vec<Requirement>),
+}
+
+impl Requirement {
+    /// Requires the user to be authenticated.
+    #[must_use]
+    pub fn authenticated() -> Self {
+        Self::Authenticated
+    }
+
+    /// Requires the given role.
+    #[must_use]
+    pub fn role(role: impl Into<String>) -> Self {
+        Self::Role(role.into())
+    }
+
+    /// Requires the given permission.
+    #[must_use]
+    pub fn permission(permission: impl Into<String>) -> Self {
+        Self::Permission(permission.into())
+    }
+
+    /// Requires the given scope.
+    #[must_use]
+    pub fn scope(scope: impl Into<String>) -> Self {
+        Self::Scope(scope.into())
+    }
+
+    /// Requires the user to have authenticated at most `max_age` ago.
+    #[must_use]
+    pub fn fresh(max_age: Duration) -> Self {
+        Self::Fresh(max_age)
+    }
+
+    /// Requires this and the other requirement to be met.
+    #[must_use]
+    pub fn and(self, other: Self) -> Self {
+        match self {
+            Self::All(mut requirements) => {
+                requirements.push(other);
+                Self::All(requirements)
+            }
+            requirement => Self::All(vec![requirement, other]),
+        }
+    }
+
+    /// Requires this or the other requirement to be met.
+    #[must_use]
+    pub fn or(self, other: Self) -> Self {
+        match self {
+            Self::Any(mut requirements) => {
+                requirements.push(other);
+                Self::Any(requirements)
+            }
+            requirement => Self::Any(vec![requirement, other]),
+        }
+    }
+
+    /// Checks if the requirement is met by the current authentication state.
+    #[must_use]
+    pub fn is_met(&self, auth: &Auth) -> bool {
+        match self {
+            Self::Authenticated => auth.authenticated(),
+            Self::Role(role) => auth.has_role(role),
+            Self::Permission(permission) => auth.has_permission(permission),
+            Self::Scope(scope) => auth.has_scope(scope),
+            Self::Fresh(max_age) => auth
+                .authenticated_at()
+                .is_some_and(|authenticated_at| now() - authenticated_at <= *max_age),
+            Self::All(requirements) => requirements
+                .iter()
+                .all(|requirement| requirement.is_met(auth)),
+            Self::Any(requirements) => requirements
+                .iter()
+                .any(|requirement| requirement.is_met(auth)),
+        }
+    }
+}
@@ -22,127 +22,70 @@
 * SOFTWARE.
 */
 
-use leptos_router::{Params, ParamsError, ParamsMap};
-use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 
-/// An enumeration representing different callback responses during the
-/// authentication process.
-#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
-pub enum CallbackResponse {
-    SuccessLogin(SuccessCallbackResponse),
-    SuccessLogout(SuccessLogoutResponse),
-    Error(ErrorResponse),
-}
-
-/// A structure representing a successful login callback response.
-#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
-pub struct SuccessCallbackResponse {
-    pub session_state: Option<String>,
-    pub code: String,
-}
-
-/// A structure representing a successful logout callback response.
-#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
-pub struct SuccessLogoutResponse {
-    pub destroy_session: bool,
-}
+use leptos_router::{ParamsError, ParamsMap};
+use oidc_core::OidcError;
 
-/// An enumeration representing the response to token requests, including
-/// success and error responses.
-#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
-#[serde(untagged)]
-pub enum TokenResponse {
-    Success(SuccessTokenResponse),
-    Error(ErrorResponse),
-}
+pub use oidc_core::response::*;
 
-/// A structure representing a successful token response.
-#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
-pub struct SuccessTokenResponse {
-    pub access_token: String,
-    pub expires_in: i64,
-    pub refresh_expires_in: Option<i64>,
-    pub refresh_token: String,
-    pub token_type: Option<String>,
-    pub id_token: String,
-    #[serde(rename = "not-before-policy")]
-    pub not_before_policy: Option<i64>,
-    pub session_state: Option<String>,
-    pub scope: Option<String>,
-}
+use crate::ResponseType;
 
-/// A structure representing an error response during the authentication
-/// process.
-#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
-pub struct ErrorResponse {
-    pub error: String,
-    pub error_description: String,
-}
-
-/// A trait for converting parameters from a map to a structure for
-/// `SuccessCallbackResponse`.
-impl Params for SuccessCallbackResponse {
+/// A trait for converting the query parameters of the router to a
+/// `CallbackResponse`, see `CallbackResponse::from_lookup`.
+pub trait CallbackParams: Sized {
+    /// Converts the parameters as callback of the authorization code flow,
+    /// see `CallbackParams::from_map_for`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `ParamsError::MissingParam` if the parameters are neither a
+    /// login, a logout nor an error callback.
     fn from_map(map: &ParamsMap) -> Result<Self, ParamsError> {
-        if let (session_state, Some(code)) = (map.get("session_state"), map.get("code")) {
-            return Ok(SuccessCallbackResponse {
-                session_state: session_state.cloned(),
-                code: code.clone(),
-            });
-        }
-        Err(ParamsError::MissingParam(
-            "Missing parameter 'code'".to_string(),
-        ))
+        Self::from_map_for(map, ResponseType::Code)
     }
-}
 
-/// A trait for converting parameters from a map to a structure for
-/// `SuccessLogoutResponse`.
-impl Params for SuccessLogoutResponse {
-    fn from_map(map: &ParamsMap) -> Result<Self, ParamsError> {
-        if let Some(destroy_session) = map.get("destroy_session") {
-            return Ok(SuccessLogoutResponse {
-                destroy_session: destroy_session.parse().unwrap_or_default(),
-            });
-        }
-        Err(ParamsError::MissingParam(
-            "Missing parameter 'destroy_session'".to_string(),
-        ))
-    }
-}
+    /// Converts the parameters of a callback of the given response type.
+    /// Tokens in the callback are only read if the response type returns
+    /// them (implicit or hybrid flow).
+    ///
+    /// # Errors
+    ///
+    /// Returns `ParamsError::MissingParam` if the parameters are neither a
+    /// login, a logout nor an error callback.
+    fn from_map_for(map: &ParamsMap, response_type: ResponseType) -> Result<Self, ParamsError>;
 
-/// A trait for converting parameters from a map to a structure for
-/// `ErrorResponse`.
-impl Params for ErrorResponse {
-    fn from_map(map: &ParamsMap) -> Result<Self, ParamsError> {
-        if let (Some(error), Some(error_description)) =
-            (map.get("error"), map.get("error_description"))
-        {
-            return Ok(ErrorResponse {
-                error: error.clone(),
-                error_description: error_description.clone(),
-            });
+    /// Converts the parameters like `from_map_for`, but renames non-standard
+    /// parameter names of the provider beforehand. The overrides map the
+    /// name used by the provider to the standard name, e.g.
+    /// `authorization_code` to `code`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `ParamsError::MissingParam` if the parameters are neither a
+    /// login, a logout nor an error callback.
+    fn from_map_with_overrides(
+        mut map: ParamsMap,
+        overrides: &HashMap<String, String>,
+        response_type: ResponseType,
+    ) -> Result<Self, ParamsError> {
+        for (name, standard_name) in overrides {
+            if let Some(value) = map.remove(name) {
+                map.insert(standard_name.clone(), value);
+            }
         }
-        Err(ParamsError::MissingParam(
-            "Missing parameter 'error' and 'error_description'".to_string(),
-        ))
+
+        Self::from_map_for(&map, response_type)
     }
 }
 
-/// A trait for converting parameters from a map to a structure for
-/// `CallbackResponse`.
-impl Params for CallbackResponse {
-    fn from_map(map: &ParamsMap) -> Result<Self, ParamsError> {
-        if let Ok(response) = SuccessCallbackResponse::from_map(map) {
-            return Ok(CallbackResponse::SuccessLogin(response));
-        } else if let Ok(reponse) = SuccessLogoutResponse::from_map(map) {
-            return Ok(CallbackResponse::SuccessLogout(reponse));
-        } else if let Ok(reponse) = ErrorResponse::from_map(map) {
-            return Ok(CallbackResponse::Error(reponse));
-        }
-
-        Err(ParamsError::MissingParam(
-            "Missing parameter 'session_state' and 'code' or 'error' and 'error_description'"
-                .to_string(),
-        ))
+impl CallbackParams for CallbackResponse {
+    fn from_map_for(map: &ParamsMap, response_type: ResponseType) -> Result<Self, ParamsError> {
+        CallbackResponse::from_lookup(|key| map.get(key).cloned(), response_type).map_err(|error| {
+            match error {
+                OidcError::InvalidCallback(message) => ParamsError::MissingParam(message),
+                error => ParamsError::MissingParam(error.to_string()),
+            }
+        })
     }
 }
@@ -0,0 +1,94 @@
+/*
+* The MIT License (MIT)
+*
+* Copyright (c) 2023 Daniél Kerkmann <daniel@kerkmann.dev>
+*
+* Permission is hereby granted, free of charge, to any person obtaining a copy
+* of this software and associated documentation files (the "Software"), to deal
+* in the Software without restriction, including without limitation the rights
+* to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+* copies of the Software, and to permit persons to whom the Software is
+* furnished to do so, subject to the following conditions:
+*
+* The above copyright notice and this permission notice shall be included in all
+* copies or substantial portions of the Software.
+*
+* THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+* IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+* FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+* AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+* LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+* OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+* SOFTWARE.
+*/
+
+use leptos::window;
+
+use crate::{
+    storage::{read_return_to, remove_return_to, write_return_to, Storage},
+    Auth,
+};
+
+impl Auth {
+    /// Redirects the browser to the login page of the provider like
+    /// `Auth::login`. After the code exchange, the app navigates to
+    /// `return_to` instead of staying on the `redirect_uri`. Only paths of the
+    /// app itself (starting with a single `/`) are accepted, `None` stays on
+    /// the `redirect_uri`. A mounted `RedirectingToLogin` component announces
+    /// the redirect first.
+    pub fn login_with_return_to(&self, return_to: Option<&str>) {
+        remember_return_to(
+            self.parameters.storage.transient_backend(),
+            &self.parameters.token_storage_key(),
+            return_to,
+        );
+        self.redirect_to_authorization(self.parameters.scope.clone(), true);
+    }
+}
+
+/// Stores the route to return to after the next login, or forgets a previous
+/// one. Routes outside of the app are dropped, so the callback can't be
+/// abused as an open redirect.
+pub(crate) fn remember_return_to(storage: &dyn Storage, key: &str, return_to: Option<&str>) {
+    let result = match return_to.filter(|path| is_app_path(path)) {
+        Some(path) => write_return_to(storage, key, path),
+        None => remove_return_to(storage, key),
+    };
+    if let Err(error) = result {
+        leptos::logging::error!("Unable to store the return route: {error:#?}");
+    }
+}
+
+/// Returns and forgets the route to return to after a login.
+pub(crate) fn take_return_to(storage: &dyn Storage, key: &str) -> Option<String> {
+    let return_to = read_return_to(storage, key)
+        .map_err(|error| leptos::logging::error!("Unable to read the return route: {error:#?}"))
+        .ok()
+        .flatten();
+    if let Err(error) = remove_return_to(storage, key) {
+        leptos::logging::error!("Unable to remove the return route: {error:#?}");
+    }
+
+    return_to.filter(|path| is_app_path(path))
+}
+
+/// Returns the path, query and fragment of the current page, `None` outside
+/// of the browser.
+pub(crate) fn current_route() -> Option<String> {
+    if !leptos::leptos_dom::is_browser() {
+        return None;
+    }
+    let location = window().location();
+    Some(format!(
+        "{}{}{}",
+        location.pathname().ok()?,
+        location.search().ok()?,
+        location.hash().ok()?,
+    ))
+}
+
+/// Checks that the path points into the app, e.g. `/profile`, and not to
+/// another origin like `//evil.example`.
+fn is_app_path(path: &str) -> bool {
+    path.starts_with('/') && !path.starts_with("//") && !path.starts_with("/\\")
+}
@@ -0,0 +1,88 @@
+/*
+* The MIT License (MIT)
+*
+* Copyright (c) 2023 Daniél Kerkmann <daniel@kerkmann.dev>
+*
+* Permission is hereby granted, free of charge, to any person obtaining a copy
+* of this software and associated documentation files (the "Software"), to deal
+* in the Software without restriction, including without limitation the rights
+* to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+* copies of the Software, and to permit persons to whom the Software is
+* furnished to do so, subject to the following conditions:
+*
+* The above copyright notice and this permission notice shall be included in all
+* copies or substantial portions of the Software.
+*
+* THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+* IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+* FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+* AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+* LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+* OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+* SOFTWARE.
+*/
+
+use leptos::SignalGetUntracked;
+
+use crate::{client_form_request, error::AuthError, response::ErrorResponse, Auth};
+
+impl Auth {
+    /// Revokes the consent of the user for this client ("disconnect this
+    /// app") and logs out locally. The refresh token, or the access token
+    /// without one, is revoked at the revocation endpoint (RFC 7009), which
+    /// revokes the whole grant at most providers. The endpoint is taken from
+    /// `AuthParameters::revocation_endpoint` or the provider metadata.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the user isn't authenticated, no revocation
+    /// endpoint is known or the provider rejected the revocation. The local
+    /// tokens are kept in that case.
+    pub async fn revoke_consent(&self) -> Result<(), AuthError> {
+        self.revoke_tokens().await?;
+        self.local_logout();
+
+        Ok(())
+    }
+
+    /// Revokes the refresh token, or the access token without one, at the
+    /// revocation endpoint. The local tokens are kept.
+    pub(crate) async fn revoke_tokens(&self) -> Result<(), AuthError> {
+        let tokens = self
+            .resource
+            .get_untracked()
+            .and_then(Result::ok)
+            .flatten()
+            .ok_or(AuthError::Unauthenticated)?;
+        let endpoint = self
+            .parameters
+            .revocation_endpoint
+            .clone()
+            .or_else(|| {
+                self.metadata
+                    .get_untracked()
+                    .and_then(|metadata| metadata.revocation_endpoint)
+            })
+            .ok_or(AuthError::Discovery("revocation_endpoint"))?;
+        let (token, token_type_hint) = match tokens.refresh_token {
+            Some(refresh_token) => (refresh_token, "refresh_token"),
+            None => (tokens.access_token, "access_token"),
+        };
+
+        let request = client_form_request(
+            &self.parameters,
+            endpoint,
+            vec![
+                ("client_id".to_string(), self.parameters.client_id.clone()),
+                ("token".to_string(), token),
+                ("token_type_hint".to_string(), token_type_hint.to_string()),
+            ],
+        );
+        let response = self.parameters.client().send(request).await?;
+        if !response.is_success() {
+            return Err(AuthError::provider(response.json::<ErrorResponse>()?));
+        }
+
+        Ok(())
+    }
+}
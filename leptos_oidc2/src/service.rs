@@ -0,0 +1,78 @@
+/*
+* The MIT License (MIT)
+*
+* Copyright (c) 2023 Daniél Kerkmann <daniel@kerkmann.dev>
+*
+* Permission is hereby granted, free of charge, to any person obtaining a copy
+* of this software and associated documentation files (the "Software"), to deal
+* in the Software without restriction, including without limitation the rights
+* to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+* copies of the Software, and to permit persons to whom the Software is
+* furnished to do so, subject to the following conditions:
+*
+* The above copyright notice and this permission notice shall be included in all
+* copies or substantial portions of the Software.
+*
+* THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+* IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+* FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+* AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+* LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+* OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+* SOFTWARE.
+*/
+
+use leptos::{
+    create_effect, create_rw_signal, expect_context, provide_context, RwSignal, Signal, SignalGet,
+    SignalSet, SignalWithUntracked,
+};
+
+use crate::{context::use_auth, Auth};
+
+/// The context of a service provided by `provide_authed_service`.
+struct AuthedService<T: 'static>(RwSignal<Option<T>>);
+
+impl<T> Clone for AuthedService<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for AuthedService<T> {}
+
+/// Provides a service (e.g. an API client or a WebSocket manager), which is
+/// constructed by the given factory as soon as the user is authenticated and
+/// dropped again on logout. The service is retrieved with `use_service`.
+///
+/// This needs to be called below `Auth::init`, once per service type.
+pub fn provide_authed_service<T, F>(factory: F)
+where
+    T: 'static,
+    F: Fn(&Auth) -> T + 'static,
+{
+    let auth = use_auth();
+    let service = create_rw_signal(None::<T>);
+
+    create_effect(move |_| {
+        let authenticated = auth.authenticated();
+        match (authenticated, service.with_untracked(Option::is_some)) {
+            (true, false) => service.set(Some(factory(&auth))),
+            (false, true) => service.set(None),
+            _ => {}
+        }
+    });
+
+    provide_context(AuthedService(service));
+}
+
+/// Returns a signal of the service provided by `provide_authed_service`,
+/// which is `None` as long as the user isn't authenticated.
+///
+/// # Panics
+///
+/// Panics if no service of the given type was provided.
+#[must_use]
+pub fn use_service<T: Clone + 'static>() -> Signal<Option<T>> {
+    let AuthedService(service) = expect_context::<AuthedService<T>>();
+    Signal::derive(move || service.get())
+}
@@ -0,0 +1,133 @@
+/*
+* The MIT License (MIT)
+*
+* Copyright (c) 2023 Daniél Kerkmann <daniel@kerkmann.dev>
+*
+* Permission is hereby granted, free of charge, to any person obtaining a copy
+* of this software and associated documentation files (the "Software"), to deal
+* in the Software without restriction, including without limitation the rights
+* to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+* copies of the Software, and to permit persons to whom the Software is
+* furnished to do so, subject to the following conditions:
+*
+* The above copyright notice and this permission notice shall be included in all
+* copies or substantial portions of the Software.
+*
+* THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+* IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+* FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+* AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+* LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+* OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+* SOFTWARE.
+*/
+
+use std::time::Duration;
+
+use leptos::{
+    document, ev, on_cleanup, set_interval_with_handle, wasm_bindgen::JsCast,
+    window_event_listener, SignalGetUntracked,
+};
+use web_sys::HtmlIFrameElement;
+
+use crate::Auth;
+
+/// The message of the OP iframe, if the session at the provider changed.
+const SESSION_CHANGED: &str = "changed";
+
+impl Auth {
+    /// Monitors the session at the provider (OpenID Connect Session
+    /// Management). The `check_session_iframe` of the provider is loaded in a
+    /// hidden iframe and polled with the `client_id` and the `session_state`
+    /// in the given interval. Once the session at the provider ended, e.g. by
+    /// a logout in another application, the local tokens are removed, which
+    /// emits a `SessionEvent::Logout`. The monitoring lives as long as the
+    /// current reactive owner.
+    ///
+    /// The iframe is taken from `AuthParameters::check_session_iframe` or the
+    /// provider metadata. Without it, or without a `session_state` in the
+    /// authorization response, the session isn't monitored.
+    pub fn monitor_session(&self, interval: Duration) {
+        let Some(iframe_url) = self.parameters.check_session_iframe.clone().or_else(|| {
+            self.metadata
+                .get_untracked()
+                .and_then(|metadata| metadata.check_session_iframe)
+        }) else {
+            leptos::logging::warn!("No check_session_iframe, the session isn't monitored");
+            return;
+        };
+        let Ok(origin) = url::Url::parse(&iframe_url).map(|url| url.origin().ascii_serialization())
+        else {
+            leptos::logging::error!("Invalid check_session_iframe: {iframe_url}");
+            return;
+        };
+        let Some(iframe) = create_iframe(&iframe_url) else {
+            leptos::logging::error!("Unable to create the check_session_iframe");
+            return;
+        };
+
+        let listener = window_event_listener(ev::message, {
+            let auth = self.clone();
+            let origin = origin.clone();
+            move |event| {
+                if event.origin() != origin {
+                    return;
+                }
+                match event.data().as_string().as_deref() {
+                    Some(SESSION_CHANGED) if session_state(&auth).is_some() => auth.local_logout(),
+                    Some("error") => {
+                        leptos::logging::error!("The check_session_iframe rejected the message");
+                    }
+                    _ => {}
+                }
+            }
+        });
+
+        let interval = set_interval_with_handle(
+            {
+                let auth = self.clone();
+                let iframe = iframe.clone();
+                move || {
+                    let (Some(session_state), Some(content)) =
+                        (session_state(&auth), iframe.content_window())
+                    else {
+                        return;
+                    };
+                    let message = format!("{} {session_state}", auth.parameters.client_id);
+                    content.post_message(&message.into(), &origin).ok();
+                }
+            },
+            interval,
+        );
+
+        on_cleanup(move || {
+            listener.remove();
+            if let Ok(interval) = interval {
+                interval.clear();
+            }
+            iframe.remove();
+        });
+    }
+}
+
+/// Returns the session state of the current tokens, if authenticated.
+fn session_state(auth: &Auth) -> Option<String> {
+    auth.resource
+        .get_untracked()
+        .and_then(Result::ok)
+        .flatten()
+        .and_then(|tokens| tokens.session_state)
+}
+
+/// Appends a hidden iframe with the given URL to the body.
+fn create_iframe(url: &str) -> Option<HtmlIFrameElement> {
+    let iframe = document()
+        .create_element("iframe")
+        .ok()?
+        .dyn_into::<HtmlIFrameElement>()
+        .ok()?;
+    iframe.set_attribute("style", "display: none").ok()?;
+    iframe.set_src(url);
+    document().body()?.append_child(&iframe).ok()?;
+    Some(iframe)
+}
@@ -0,0 +1,262 @@
+/*
+* The MIT License (MIT)
+*
+* Copyright (c) 2023 Daniél Kerkmann <daniel@kerkmann.dev>
+*
+* Permission is hereby granted, free of charge, to any person obtaining a copy
+* of this software and associated documentation files (the "Software"), to deal
+* in the Software without restriction, including without limitation the rights
+* to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+* copies of the Software, and to permit persons to whom the Software is
+* furnished to do so, subject to the following conditions:
+*
+* The above copyright notice and this permission notice shall be included in all
+* copies or substantial portions of the Software.
+*
+* THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+* IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+* FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+* AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+* LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+* OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+* SOFTWARE.
+*/
+
+use std::{cell::Cell, time::Duration};
+
+use futures::{
+    channel::oneshot,
+    future::{select, Either},
+};
+use leptos::{document, ev, window_event_listener, SignalGetUntracked, SignalSet};
+use leptos_router::{ParamsError, ParamsMap};
+use serde::Deserialize;
+
+use crate::{
+    audit::{record_event, record_result, AuthEventKind},
+    error::{AuthConfigError, AuthError},
+    fetch_token,
+    implicit::fetch_implicit_token,
+    insert_fragment_params,
+    response::{CallbackParams, CallbackResponse},
+    Auth, ResponseMode,
+};
+
+/// The time to wait for the silent login callback. Without an answer, the
+/// browser most likely blocks the iframe or the cookies of the provider.
+const SILENT_LOGIN_TIMEOUT: Duration = Duration::from_secs(10);
+
+thread_local! {
+    /// Whether a silent login in the iframe timed out, because the browser
+    /// blocks third-party cookies. `SilentRenewal::Auto` skips the iframe
+    /// afterwards.
+    static COOKIES_BLOCKED: Cell<bool> = const { Cell::new(false) };
+}
+
+/// An enumeration selecting how `Auth::silent_login` renews the session, see
+/// `AuthParameters::silent_renewal`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SilentRenewal {
+    /// Logs in with `prompt=none` in a hidden iframe. Once the browser turns
+    /// out to block third-party cookies, the tokens are refreshed with the
+    /// refresh token instead.
+    #[default]
+    Auto,
+    /// Only logs in with `prompt=none` in a hidden iframe.
+    Iframe,
+    /// Only refreshes the tokens with the refresh token, e.g. if the provider
+    /// is on another site and the browsers block its cookies anyway.
+    RefreshToken,
+}
+
+impl Auth {
+    /// Logs in without user interaction, if the user has an active session
+    /// at the provider, with the strategy of `AuthParameters::silent_renewal`.
+    /// The authorization request is sent with `prompt=none` in a hidden
+    /// iframe, which is redirected to the `AuthParameters::silent_redirect_uri`.
+    /// That page has to pass its URL to the app with `postMessage`. If the
+    /// browser blocks third-party cookies, `SilentRenewal::Auto` refreshes the
+    /// tokens with the refresh token instead. If the provider requires an
+    /// interaction (e.g. `login_required`), there is no refresh token or the
+    /// refresh token was rejected, the browser is redirected to the
+    /// interactive login.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the silent redirect URI isn't configured, the
+    /// provider requires an interaction, blocks third-party cookies, the
+    /// code exchange or the refresh fails.
+    pub async fn silent_login(&self) -> Result<(), AuthError> {
+        let result = self.silent_renew().await;
+        if let Err(error) = &result {
+            // A refresh failing with a transient error keeps the session, see
+            // `Auth::degraded`.
+            if requires_interaction(error) && !self.degraded.get_untracked() {
+                self.redirect_to_authorization(self.parameters.scope.clone(), false);
+            }
+        }
+        result
+    }
+
+    /// Renews the tokens without user interaction like `Auth::silent_login`,
+    /// but never redirects to the interactive login, e.g. to render a
+    /// fallback instead.
+    pub(crate) async fn silent_renew(&self) -> Result<(), AuthError> {
+        let storage = self.parameters.storage.backend();
+        match self.parameters.silent_renewal {
+            SilentRenewal::RefreshToken => self.refresh_silently().await,
+            SilentRenewal::Auto if COOKIES_BLOCKED.with(Cell::get) => self.refresh_silently().await,
+            strategy => match self.iframe_login().await {
+                Err(AuthError::ThirdPartyCookiesBlocked) => {
+                    COOKIES_BLOCKED.with(|blocked| blocked.set(true));
+                    record_event(storage, (&AuthError::ThirdPartyCookiesBlocked).into());
+                    if strategy == SilentRenewal::Auto {
+                        self.refresh_silently().await
+                    } else {
+                        Err(AuthError::ThirdPartyCookiesBlocked)
+                    }
+                }
+                result => result,
+            },
+        }
+    }
+
+    /// Logs in with `prompt=none` in a hidden iframe.
+    async fn iframe_login(&self) -> Result<(), AuthError> {
+        let silent_redirect_uri = self
+            .parameters
+            .silent_redirect_uri
+            .clone()
+            .ok_or(AuthConfigError::Missing("silent_redirect_uri"))?;
+        let mut silent = self.clone();
+        silent.parameters.redirect_uri = silent_redirect_uri;
+        silent.parameters.prompt = Some("none".to_string());
+        // The implicit and hybrid flow return the callback in the fragment
+        // anyway, see `AuthParameters::callback_response_mode`.
+        silent.parameters.response_mode = ResponseMode::Query;
+
+        let storage = self.parameters.storage.backend();
+        let tokens = match silent_callback(&silent).await? {
+            CallbackResponse::SuccessLogin(response) => {
+                let result = fetch_token(&silent.parameters, response).await;
+                record_result(storage, &result, AuthEventKind::Login);
+                result
+            }
+            CallbackResponse::SuccessImplicitLogin(response) => {
+                let result = fetch_implicit_token(&silent.parameters, response).await;
+                record_result(storage, &result, AuthEventKind::Login);
+                result
+            }
+            CallbackResponse::Error(error) => {
+                let error = AuthError::provider(error);
+                record_event(storage, (&error).into());
+                Err(error)
+            }
+            CallbackResponse::SuccessLogout(_) => Err(AuthError::InvalidCallback(
+                ParamsError::MissingParam("code".to_string()),
+            )),
+        }?;
+        self.resource.set(Ok(Some(tokens)));
+
+        Ok(())
+    }
+
+    /// Renews the tokens with the refresh token instead of the iframe. The
+    /// refresh records its outcome itself.
+    async fn refresh_silently(&self) -> Result<(), AuthError> {
+        let previous = self
+            .current_tokens()
+            .filter(|tokens| tokens.refresh_token.is_some())
+            .ok_or(AuthError::Unauthenticated)?;
+        self.await_refresh().await;
+
+        match self.resource.get_untracked() {
+            Some(Ok(Some(tokens))) if tokens.access_token != previous.access_token => Ok(()),
+            Some(Err(error)) => Err(error),
+            _ => Err(AuthError::Unauthenticated),
+        }
+    }
+}
+
+/// Loads the login URL in a hidden iframe and waits for the callback URL,
+/// which the silent redirect page posts to the app.
+async fn silent_callback(silent: &Auth) -> Result<CallbackResponse, AuthError> {
+    let origin = url::Url::parse(&silent.parameters.redirect_uri)
+        .map_err(|_| AuthConfigError::InvalidUrl {
+            field: "silent_redirect_uri",
+            value: silent.parameters.redirect_uri.clone(),
+        })?
+        .origin()
+        .ascii_serialization();
+
+    let login_url = silent
+        .resolve_authorization_url(&silent.parameters.scope)
+        .await?;
+
+    let (sender, receiver) = oneshot::channel();
+    let sender = Cell::new(Some(sender));
+    let listener = window_event_listener(ev::message, move |event| {
+        if event.origin() != origin {
+            return;
+        }
+        if let (Some(url), Some(sender)) = (event.data().as_string(), sender.take()) {
+            sender.send(url).ok();
+        }
+    });
+
+    let iframe = document()
+        .create_element("iframe")
+        .map_err(|_| AuthError::Document)?;
+    iframe
+        .set_attribute("style", "display: none")
+        .and_then(|()| iframe.set_attribute("src", &login_url))
+        .map_err(|_| AuthError::Document)?;
+    document()
+        .body()
+        .ok_or(AuthError::Document)?
+        .append_child(&iframe)
+        .map_err(|_| AuthError::Document)?;
+
+    let timeout = Box::pin(crate::utils::sleep(SILENT_LOGIN_TIMEOUT));
+    let url = match select(receiver, timeout).await {
+        Either::Left((Ok(url), _)) => Ok(url),
+        _ => Err(AuthError::ThirdPartyCookiesBlocked),
+    };
+    listener.remove();
+    iframe.remove();
+
+    let url = url::Url::parse(&url?)
+        .map_err(|_| AuthError::InvalidCallback(ParamsError::MissingParam("code".to_string())))?;
+    let mut params = ParamsMap::new();
+    for (key, value) in url.query_pairs() {
+        params.insert(key.into_owned(), value.into_owned());
+    }
+    // The implicit and hybrid flow return the parameters in the fragment,
+    // which has the same format as a query string.
+    if let Some(fragment) = url.fragment() {
+        insert_fragment_params(&mut params, fragment);
+    }
+
+    Ok(CallbackResponse::from_map_with_overrides(
+        params,
+        &silent.parameters.callback_param_overrides,
+        silent.parameters.response_type,
+    )?)
+}
+
+/// Checks if the silent login failed, because the user has to interact with
+/// the provider, e.g. the session at the provider or the grant ended.
+fn requires_interaction(error: &AuthError) -> bool {
+    match error {
+        AuthError::LoginRequired(_)
+        | AuthError::ConsentRequired(_)
+        | AuthError::ThirdPartyCookiesBlocked
+        | AuthError::Unauthenticated
+        | AuthError::InvalidGrant(_)
+        | AuthError::RefreshTokenReused => true,
+        AuthError::Provider(response) => response.error == "interaction_required",
+        AuthError::RefreshFailed { cause } => !cause.is_transient(),
+        _ => false,
+    }
+}
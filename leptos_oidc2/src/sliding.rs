@@ -0,0 +1,100 @@
+/*
+* The MIT License (MIT)
+*
+* Copyright (c) 2023 Daniél Kerkmann <daniel@kerkmann.dev>
+*
+* Permission is hereby granted, free of charge, to any person obtaining a copy
+* of this software and associated documentation files (the "Software"), to deal
+* in the Software without restriction, including without limitation the rights
+* to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+* copies of the Software, and to permit persons to whom the Software is
+* furnished to do so, subject to the following conditions:
+*
+* The above copyright notice and this permission notice shall be included in all
+* copies or substantial portions of the Software.
+*
+* THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+* IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+* FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+* AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+* LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+* OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+* SOFTWARE.
+*/
+
+use std::{cell::Cell, rc::Rc, time::Duration};
+
+use chrono::NaiveDateTime;
+use leptos::{window_event_listener_untyped, SignalWithUntracked};
+use serde::Deserialize;
+
+use crate::{utils::now, Auth};
+
+/// The DOM events counted as user activity by default.
+const DEFAULT_ACTIVITY_EVENTS: [&str; 4] = ["pointerdown", "keydown", "scroll", "touchstart"];
+
+/// Options of the sliding session, see `AuthParameters::sliding_session`.
+/// While the user is active, the tokens are refreshed at most once per
+/// `interval`, which extends the session at the provider up to its maximum
+/// lifespan. Idle users aren't extended, their session expires as usual.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct SlidingSession {
+    /// The DOM events counted as user activity, defaults to pointer, key,
+    /// scroll and touch events.
+    #[serde(default = "default_activity_events")]
+    pub events: Vec<String>,
+    /// The minimum time between two refreshes triggered by activity.
+    pub interval: Duration,
+}
+
+impl Default for SlidingSession {
+    fn default() -> Self {
+        Self {
+            events: default_activity_events(),
+            interval: Duration::from_secs(5 * 60),
+        }
+    }
+}
+
+/// Returns the default activity events.
+fn default_activity_events() -> Vec<String> {
+    DEFAULT_ACTIVITY_EVENTS.map(String::from).to_vec()
+}
+
+impl Auth {
+    /// Refreshes the tokens on user activity, at most once per interval, see
+    /// `SlidingSession`. The listeners live as long as the app.
+    pub(crate) fn slide_session(&self, options: &SlidingSession) {
+        if !leptos::leptos_dom::is_browser() {
+            return;
+        }
+
+        let interval = chrono::Duration::from_std(options.interval)
+            .unwrap_or_else(|_| chrono::Duration::zero());
+        let extended = Rc::new(Cell::new(now()));
+        for event in &options.events {
+            let auth = self.clone();
+            let extended = Rc::clone(&extended);
+            // The listeners live as long as the app, the handles aren't needed.
+            let _ = window_event_listener_untyped(event, move |_| {
+                extend(&auth, &extended, interval);
+            });
+        }
+    }
+}
+
+/// Refreshes the tokens, if the last extension is older than the interval
+/// and the refresh token is still valid.
+fn extend(auth: &Auth, extended: &Cell<NaiveDateTime>, interval: chrono::Duration) {
+    let now = now();
+    if now - extended.get() < interval || auth.refreshing.with_untracked(Option::is_some) {
+        return;
+    }
+    let refreshable = auth.current_tokens().is_some_and(|tokens| {
+        tokens.refresh_token.is_some() && !tokens.refresh_token_expired_with(auth.parameters.leeway)
+    });
+    if refreshable {
+        extended.set(now);
+        auth.refresh_token();
+    }
+}
@@ -0,0 +1,166 @@
+/*
+* The MIT License (MIT)
+*
+* Copyright (c) 2023 Daniél Kerkmann <daniel@kerkmann.dev>
+*
+* Permission is hereby granted, free of charge, to any person obtaining a copy
+* of this software and associated documentation files (the "Software"), to deal
+* in the Software without restriction, including without limitation the rights
+* to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+* copies of the Software, and to permit persons to whom the Software is
+* furnished to do so, subject to the following conditions:
+*
+* The above copyright notice and this permission notice shall be included in all
+* copies or substantial portions of the Software.
+*
+* THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+* IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+* FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+* AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+* LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+* OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+* SOFTWARE.
+*/
+
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use chrono::NaiveDateTime;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::{claims::decode_claims_unverified, error::AuthError, storage::TokenStorage};
+
+/// An enumeration representing the authentication state, see `Auth::state`.
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub enum AuthState {
+    /// The state is still loading, e.g. during the code exchange.
+    Loading,
+    /// The user isn't authenticated.
+    Unauthenticated,
+    /// The user is authenticated with the given tokens.
+    Authenticated(TokenStorage),
+    /// The user is authenticated, but a refresh failed offline or with a
+    /// transient error and is retried later on. The tokens might be expired
+    /// in the meantime.
+    Degraded(TokenStorage),
+    /// The authentication failed.
+    Error(AuthError),
+}
+
+impl AuthState {
+    /// Checks if the state is still loading.
+    #[must_use]
+    pub fn is_loading(&self) -> bool {
+        matches!(self, AuthState::Loading)
+    }
+
+    /// Checks if the user is authenticated, which includes a degraded
+    /// session.
+    #[must_use]
+    pub fn is_authenticated(&self) -> bool {
+        matches!(self, AuthState::Authenticated(_) | AuthState::Degraded(_))
+    }
+}
+
+impl From<Option<Result<Option<TokenStorage>, AuthError>>> for AuthState {
+    fn from(state: Option<Result<Option<TokenStorage>, AuthError>>) -> Self {
+        match state {
+            None => AuthState::Loading,
+            Some(Ok(None)) => AuthState::Unauthenticated,
+            Some(Ok(Some(tokens))) => AuthState::Authenticated(tokens),
+            Some(Err(error)) => AuthState::Error(error),
+        }
+    }
+}
+
+/// A serializable snapshot of the `AuthState` without the tokens, which can
+/// be embedded into the server rendered HTML, e.g. with a resource. The
+/// client renders the authenticated shell right away and loads the tokens
+/// later on, see `read_auth_state`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "state", rename_all = "snake_case")]
+pub enum AuthStateSerde {
+    /// The state is still loading.
+    Loading,
+    /// The user isn't authenticated.
+    Unauthenticated,
+    /// The user is authenticated, the tokens are stripped.
+    Authenticated {
+        /// The unverified claims of the ID token.
+        claims: Value,
+        /// The expiry of the access token.
+        expires_in: NaiveDateTime,
+        /// The expiry of the refresh token, if known.
+        refresh_expires_in: Option<NaiveDateTime>,
+    },
+    /// The authentication failed with the given message.
+    Error { message: String },
+}
+
+impl AuthStateSerde {
+    /// Checks if the user is authenticated.
+    #[must_use]
+    pub fn is_authenticated(&self) -> bool {
+        matches!(self, AuthStateSerde::Authenticated { .. })
+    }
+
+    /// Returns the claims of the ID token decoded into `T`, if the user is
+    /// authenticated and the claims match `T`.
+    #[must_use]
+    pub fn claims<T: DeserializeOwned>(&self) -> Option<T> {
+        match self {
+            AuthStateSerde::Authenticated { claims, .. } => {
+                serde_json::from_value(claims.clone()).ok()
+            }
+            _ => None,
+        }
+    }
+}
+
+impl AuthStateSerde {
+    /// Returns a token storage of the snapshot, without the access and the
+    /// refresh token, see `CookieStorage`. The claims are kept as unsigned ID
+    /// token, so the claims of the session are still available.
+    pub(crate) fn into_token_storage(self) -> Option<TokenStorage> {
+        match self {
+            AuthStateSerde::Authenticated {
+                claims,
+                expires_in,
+                refresh_expires_in,
+            } => Some(TokenStorage {
+                id_token: format!(
+                    "{}.{}.",
+                    URL_SAFE_NO_PAD.encode(r#"{"alg":"none"}"#),
+                    URL_SAFE_NO_PAD.encode(claims.to_string())
+                ),
+                expires_in,
+                refresh_expires_in,
+                ..TokenStorage::default()
+            }),
+            _ => None,
+        }
+    }
+}
+
+impl From<&TokenStorage> for AuthStateSerde {
+    fn from(tokens: &TokenStorage) -> Self {
+        AuthStateSerde::Authenticated {
+            claims: decode_claims_unverified(&tokens.id_token).unwrap_or(Value::Null),
+            expires_in: tokens.expires_in,
+            refresh_expires_in: tokens.refresh_expires_in,
+        }
+    }
+}
+
+impl From<&AuthState> for AuthStateSerde {
+    fn from(state: &AuthState) -> Self {
+        match state {
+            AuthState::Loading => AuthStateSerde::Loading,
+            AuthState::Unauthenticated => AuthStateSerde::Unauthenticated,
+            AuthState::Authenticated(tokens) | AuthState::Degraded(tokens) => tokens.into(),
+            AuthState::Error(error) => AuthStateSerde::Error {
+                message: error.to_string(),
+            },
+        }
+    }
+}
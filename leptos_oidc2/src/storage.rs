@@ -22,79 +22,414 @@
 * SOFTWARE.
 */
 
-use std::sync::Arc;
+use std::{cell::RefCell, collections::HashMap, fmt::Debug, sync::Arc};
 
-use chrono::{Duration, NaiveDateTime, Utc};
 use leptos::window;
-use serde::{Deserialize, Serialize};
-use web_sys::Storage;
-
-use crate::{error::AuthError, response::SuccessTokenResponse};
-
-/// The key used for storing authentication token data in local storage.
-const LOCAL_STORAGE_KEY: &str = "auth";
-
-/// A structure representing the storage of authentication tokens.
-#[derive(Debug, Default, Clone, PartialEq, Deserialize, Serialize)]
-pub struct TokenStorage {
-    pub id_token: String,
-    pub access_token: String,
-    pub expires_in: NaiveDateTime,
-    pub refresh_token: String,
-    pub refresh_expires_in: Option<NaiveDateTime>,
-}
-
-/// Converts a `SuccessTokenResponse` into a `TokenStorage` structure.
-impl From<SuccessTokenResponse> for TokenStorage {
-    fn from(value: SuccessTokenResponse) -> Self {
-        Self {
-            id_token: value.id_token,
-            access_token: value.access_token,
-            expires_in: Utc::now().naive_utc() + Duration::seconds(value.expires_in),
-            refresh_token: value.refresh_token,
-            refresh_expires_in: value.refresh_expires_in.map(|refresh_expires_in| {
-                Utc::now().naive_utc() + Duration::seconds(refresh_expires_in)
-            }),
+use serde::Deserialize;
+
+pub(crate) use oidc_core::token::access_token_expiry;
+pub use oidc_core::token::{TokenIdentity, TokenStorage};
+
+use crate::{
+    audit::AuthEvent,
+    compression::{compress, decompress},
+    cookie::CookieStorage,
+    error::{AuthError, StorageErrorKind},
+    instrument::trace_event,
+};
+
+/// The prefix of the key used for storing authentication token data, which
+/// is namespaced by the client ID, see `AuthParameters::token_storage_key`.
+pub(crate) const TOKEN_STORAGE_KEY_PREFIX: &str = "auth";
+
+/// The key of the token storage of older versions, before it was namespaced
+/// by the client ID.
+const LEGACY_TOKEN_STORAGE_KEY: &str = "auth";
+
+/// The key used for storing the audit log.
+pub(crate) const AUDIT_LOG_KEY: &str = "auth_events";
+
+/// The suffix of the key used for storing the PKCE code verifier. Like every
+/// value of a pending login or logout, it's stored next to the tokens of the
+/// provider, see `pending_key`.
+const CODE_VERIFIER_KEY_SUFFIX: &str = "code_verifier";
+
+/// The suffix of the key used for storing the nonce.
+const NONCE_KEY_SUFFIX: &str = "nonce";
+
+/// The suffix of the key used for storing the pending logout state.
+const LOGOUT_STATE_KEY_SUFFIX: &str = "logout_state";
+
+/// The suffix of the key used for storing the route to return to after a
+/// login.
+const RETURN_TO_KEY_SUFFIX: &str = "return_to";
+
+/// The suffix of the key used for storing the route which started a pending
+/// incremental authorization.
+const SCOPE_REQUEST_KEY_SUFFIX: &str = "scope_request";
+
+/// The suffix of the key used for storing the scopes requested by a pending
+/// incremental authorization.
+const REQUESTED_SCOPE_KEY_SUFFIX: &str = "requested_scope";
+
+thread_local! {
+    /// The items of the `InMemoryStorage`.
+    static MEMORY: RefCell<HashMap<String, String>> = RefCell::new(HashMap::new());
+}
+
+/// A trait for key-value storage backends, which keep the authentication
+/// tokens and the audit log.
+pub trait Storage: Debug {
+    /// Returns the value of the given key, if present.
+    fn get(&self, key: &str) -> Result<Option<String>, AuthError>;
+
+    /// Sets the value of the given key.
+    fn set(&self, key: &str, value: &str) -> Result<(), AuthError>;
+
+    /// Removes the given key.
+    fn remove(&self, key: &str) -> Result<(), AuthError>;
+}
+
+/// A storage backend using the local storage of the browser. The tokens
+/// survive closing the browser.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct LocalStorage;
+
+/// A storage backend using the session storage of the browser. The tokens
+/// are scoped to the current tab and removed when it's closed.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct SessionStorage;
+
+/// A storage backend keeping everything in memory. The tokens are lost on
+/// every page reload, but never accessible for other scripts.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct InMemoryStorage;
+
+impl Storage for LocalStorage {
+    fn get(&self, key: &str) -> Result<Option<String>, AuthError> {
+        get_local_storage()?
+            .get(key)
+            .map_err(|_| AuthError::Storage(StorageErrorKind::Read))
+    }
+
+    fn set(&self, key: &str, value: &str) -> Result<(), AuthError> {
+        get_local_storage()?
+            .set(key, value)
+            .map_err(|_| AuthError::Storage(StorageErrorKind::Write))
+    }
+
+    fn remove(&self, key: &str) -> Result<(), AuthError> {
+        get_local_storage()?
+            .delete(key)
+            .map_err(|_| AuthError::Storage(StorageErrorKind::Remove))
+    }
+}
+
+impl Storage for SessionStorage {
+    fn get(&self, key: &str) -> Result<Option<String>, AuthError> {
+        get_session_storage()?
+            .get(key)
+            .map_err(|_| AuthError::Storage(StorageErrorKind::Read))
+    }
+
+    fn set(&self, key: &str, value: &str) -> Result<(), AuthError> {
+        get_session_storage()?
+            .set(key, value)
+            .map_err(|_| AuthError::Storage(StorageErrorKind::Write))
+    }
+
+    fn remove(&self, key: &str) -> Result<(), AuthError> {
+        get_session_storage()?
+            .delete(key)
+            .map_err(|_| AuthError::Storage(StorageErrorKind::Remove))
+    }
+}
+
+impl Storage for InMemoryStorage {
+    fn get(&self, key: &str) -> Result<Option<String>, AuthError> {
+        Ok(MEMORY.with(|memory| memory.borrow().get(key).cloned()))
+    }
+
+    fn set(&self, key: &str, value: &str) -> Result<(), AuthError> {
+        MEMORY.with(|memory| {
+            memory
+                .borrow_mut()
+                .insert(key.to_string(), value.to_string())
+        });
+        Ok(())
+    }
+
+    fn remove(&self, key: &str) -> Result<(), AuthError> {
+        MEMORY.with(|memory| memory.borrow_mut().remove(key));
+        Ok(())
+    }
+}
+
+/// An enumeration selecting the storage backend in the `AuthParameters`.
+#[derive(Debug, Default, Clone, Copy, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StorageKind {
+    #[default]
+    Local,
+    Session,
+    InMemory,
+    /// An `HttpOnly` cookie, which is required for server side rendering, see
+    /// `CookieStorage`.
+    Cookie,
+    /// A custom storage backend, it can't be deserialized.
+    #[serde(skip)]
+    Custom(&'static dyn Storage),
+}
+
+impl StorageKind {
+    /// Returns the storage backend of this kind.
+    #[must_use]
+    pub fn backend(self) -> &'static dyn Storage {
+        match self {
+            StorageKind::Local => &LocalStorage,
+            StorageKind::Session => &SessionStorage,
+            StorageKind::InMemory => &InMemoryStorage,
+            StorageKind::Cookie => &CookieStorage,
+            StorageKind::Custom(storage) => storage,
+        }
+    }
+
+    /// Returns the storage backend of the pending login or logout, i.e. the
+    /// PKCE code verifier, the nonce, the logout state and the routes to
+    /// return to. They have to survive the redirect to the provider, but not
+    /// the tab, so they live in the session storage, unless a custom backend
+    /// is configured.
+    #[must_use]
+    pub fn transient_backend(self) -> &'static dyn Storage {
+        match self {
+            StorageKind::Custom(storage) => storage,
+            _ => &SessionStorage,
         }
     }
 }
 
 /// Retrieves the local storage for the application.
-fn get_storage() -> Result<Storage, AuthError> {
+fn get_local_storage() -> Result<web_sys::Storage, AuthError> {
     window()
         .local_storage()
-        .map_err(|_| AuthError::Storage)?
-        .ok_or(AuthError::Storage)
-}
-
-/// Reads the token storage from local storage and deserializes it into a
-/// `TokenStorage` structure.
-pub(crate) fn read_token_storage() -> Result<Option<TokenStorage>, AuthError> {
-    let storage = get_storage()?;
-    let item = storage
-        .get(LOCAL_STORAGE_KEY)
-        .map_err(|_| AuthError::Storage)?;
-    if let Some(item) = item {
-        let token_storage = serde_json::from_str(item.as_str())
+        .map_err(|_| AuthError::Storage(StorageErrorKind::Unavailable))?
+        .ok_or(AuthError::Storage(StorageErrorKind::Unavailable))
+}
+
+/// Retrieves the session storage for the application.
+fn get_session_storage() -> Result<web_sys::Storage, AuthError> {
+    window()
+        .session_storage()
+        .map_err(|_| AuthError::Storage(StorageErrorKind::Unavailable))?
+        .ok_or(AuthError::Storage(StorageErrorKind::Unavailable))
+}
+
+/// Reads the token storage from the storage backend and deserializes it into
+/// a `TokenStorage` structure.
+pub(crate) fn read_token_storage(
+    storage: &dyn Storage,
+    key: &str,
+) -> Result<Option<TokenStorage>, AuthError> {
+    if let Some(item) = storage.get(key)? {
+        let token_storage: TokenStorage = serde_json::from_str(decompress(item)?.as_str())
             .map_err(|error| AuthError::Serde(Arc::new(error)))?;
+        trace_event!(
+            debug,
+            key,
+            access_token = %crate::instrument::Redacted(&token_storage.access_token),
+            "read token storage"
+        );
         return Ok(Some(token_storage));
     }
 
+    trace_event!(debug, key, "token storage is empty");
     Ok(None)
 }
 
-/// Removes the token storage from local storage.
-pub(crate) fn remove_token_storage() -> Result<(), AuthError> {
-    let storage = get_storage()?;
-    storage
-        .delete(LOCAL_STORAGE_KEY)
-        .map_err(|_| AuthError::Storage)
+/// Moves the token storage of older versions to the given key, so their
+/// sessions survive the upgrade. A token storage already stored under the key
+/// wins, the old one is removed either way.
+pub(crate) fn migrate_legacy_token_storage(
+    storage: &dyn Storage,
+    key: &str,
+) -> Result<(), AuthError> {
+    let Some(item) = storage.get(LEGACY_TOKEN_STORAGE_KEY)? else {
+        return Ok(());
+    };
+    trace_event!(debug, key, "migrating the legacy token storage");
+    if storage.get(key)?.is_none() {
+        storage.set(key, item.as_str())?;
+    }
+    storage.remove(LEGACY_TOKEN_STORAGE_KEY)
+}
+
+/// Removes the token storage from the storage backend.
+pub(crate) fn remove_token_storage(storage: &dyn Storage, key: &str) -> Result<(), AuthError> {
+    trace_event!(debug, key, "removing token storage");
+    storage.remove(key)
+}
+
+/// Writes a JSON representation of the token storage to the storage backend.
+/// With the `compression` feature, it's compressed.
+pub(crate) fn write_to_token_storage(
+    storage: &dyn Storage,
+    key: &str,
+    token_storage_json: &str,
+) -> Result<(), AuthError> {
+    trace_event!(debug, key, "writing token storage");
+    storage.set(key, compress(token_storage_json).as_str())
+}
+
+/// Reads the audit log from the storage backend. A missing log is returned as
+/// an empty list.
+pub(crate) fn read_audit_log(storage: &dyn Storage) -> Result<Vec<AuthEvent>, AuthError> {
+    if let Some(item) = storage.get(AUDIT_LOG_KEY)? {
+        return serde_json::from_str(decompress(item)?.as_str())
+            .map_err(|error| AuthError::Serde(Arc::new(error)));
+    }
+
+    Ok(Vec::new())
+}
+
+/// Writes the audit log to the storage backend.
+pub(crate) fn write_audit_log(
+    storage: &dyn Storage,
+    events: &[AuthEvent],
+) -> Result<(), AuthError> {
+    let events_json = serde_json::to_string(events).map_err(Arc::new)?;
+    storage.set(AUDIT_LOG_KEY, compress(&events_json).as_str())
+}
+
+/// Returns the key of a value of a pending login or logout, namespaced by the
+/// given token storage key (see `AuthParameters::token_storage_key`), so the
+/// pending logins of several providers don't overwrite each other.
+fn pending_key(key: &str, suffix: &str) -> String {
+    format!("{key}_{suffix}")
+}
+
+/// Reads the PKCE code verifier from the given storage, see
+/// `StorageKind::transient_backend`. It has to survive the redirect to the
+/// provider, like every value of a pending login or logout.
+pub(crate) fn read_code_verifier(
+    storage: &dyn Storage,
+    key: &str,
+) -> Result<Option<String>, AuthError> {
+    storage.get(&pending_key(key, CODE_VERIFIER_KEY_SUFFIX))
+}
+
+/// Removes the PKCE code verifier from the given storage.
+pub(crate) fn remove_code_verifier(storage: &dyn Storage, key: &str) -> Result<(), AuthError> {
+    storage.remove(&pending_key(key, CODE_VERIFIER_KEY_SUFFIX))
+}
+
+/// Writes the PKCE code verifier to the given storage.
+pub(crate) fn write_code_verifier(
+    storage: &dyn Storage,
+    key: &str,
+    code_verifier: &str,
+) -> Result<(), AuthError> {
+    storage.set(&pending_key(key, CODE_VERIFIER_KEY_SUFFIX), code_verifier)
+}
+
+/// Reads the nonce from the given storage.
+pub(crate) fn read_nonce(storage: &dyn Storage, key: &str) -> Result<Option<String>, AuthError> {
+    storage.get(&pending_key(key, NONCE_KEY_SUFFIX))
+}
+
+/// Removes the nonce from the given storage.
+pub(crate) fn remove_nonce(storage: &dyn Storage, key: &str) -> Result<(), AuthError> {
+    storage.remove(&pending_key(key, NONCE_KEY_SUFFIX))
+}
+
+/// Writes the nonce to the given storage.
+pub(crate) fn write_nonce(storage: &dyn Storage, key: &str, nonce: &str) -> Result<(), AuthError> {
+    storage.set(&pending_key(key, NONCE_KEY_SUFFIX), nonce)
+}
+
+/// Reads the pending logout state from the given storage.
+pub(crate) fn read_logout_state(
+    storage: &dyn Storage,
+    key: &str,
+) -> Result<Option<String>, AuthError> {
+    storage.get(&pending_key(key, LOGOUT_STATE_KEY_SUFFIX))
+}
+
+/// Removes the pending logout state from the given storage.
+pub(crate) fn remove_logout_state(storage: &dyn Storage, key: &str) -> Result<(), AuthError> {
+    storage.remove(&pending_key(key, LOGOUT_STATE_KEY_SUFFIX))
+}
+
+/// Writes the pending logout state to the given storage.
+pub(crate) fn write_logout_state(
+    storage: &dyn Storage,
+    key: &str,
+    state: &str,
+) -> Result<(), AuthError> {
+    storage.set(&pending_key(key, LOGOUT_STATE_KEY_SUFFIX), state)
+}
+
+/// Reads the route to return to after a login from the given storage.
+pub(crate) fn read_return_to(
+    storage: &dyn Storage,
+    key: &str,
+) -> Result<Option<String>, AuthError> {
+    storage.get(&pending_key(key, RETURN_TO_KEY_SUFFIX))
+}
+
+/// Removes the route to return to after a login from the given storage.
+pub(crate) fn remove_return_to(storage: &dyn Storage, key: &str) -> Result<(), AuthError> {
+    storage.remove(&pending_key(key, RETURN_TO_KEY_SUFFIX))
+}
+
+/// Writes the route to return to after a login to the given storage.
+pub(crate) fn write_return_to(
+    storage: &dyn Storage,
+    key: &str,
+    path: &str,
+) -> Result<(), AuthError> {
+    storage.set(&pending_key(key, RETURN_TO_KEY_SUFFIX), path)
+}
+
+/// Reads the route of the pending incremental authorization from the given storage.
+pub(crate) fn read_scope_request(
+    storage: &dyn Storage,
+    key: &str,
+) -> Result<Option<String>, AuthError> {
+    storage.get(&pending_key(key, SCOPE_REQUEST_KEY_SUFFIX))
+}
+
+/// Removes the route of the pending incremental authorization from the given storage.
+pub(crate) fn remove_scope_request(storage: &dyn Storage, key: &str) -> Result<(), AuthError> {
+    storage.remove(&pending_key(key, SCOPE_REQUEST_KEY_SUFFIX))
+}
+
+/// Writes the route of the pending incremental authorization to the given storage.
+pub(crate) fn write_scope_request(
+    storage: &dyn Storage,
+    key: &str,
+    path: &str,
+) -> Result<(), AuthError> {
+    storage.set(&pending_key(key, SCOPE_REQUEST_KEY_SUFFIX), path)
+}
+
+/// Reads the scopes of the pending incremental authorization from the given storage.
+pub(crate) fn read_requested_scope(
+    storage: &dyn Storage,
+    key: &str,
+) -> Result<Option<String>, AuthError> {
+    storage.get(&pending_key(key, REQUESTED_SCOPE_KEY_SUFFIX))
+}
+
+/// Removes the scopes of the pending incremental authorization from the given storage.
+pub(crate) fn remove_requested_scope(storage: &dyn Storage, key: &str) -> Result<(), AuthError> {
+    storage.remove(&pending_key(key, REQUESTED_SCOPE_KEY_SUFFIX))
 }
 
-/// Writes a JSON representation of the token storage to local storage.
-pub(crate) fn write_to_token_storage(token_storage_json: &str) -> Result<(), AuthError> {
-    let storage = get_storage()?;
-    storage
-        .set(LOCAL_STORAGE_KEY, token_storage_json)
-        .map_err(|_| AuthError::Storage)
+/// Writes the scopes of the pending incremental authorization to the given storage.
+pub(crate) fn write_requested_scope(
+    storage: &dyn Storage,
+    key: &str,
+    scope: &str,
+) -> Result<(), AuthError> {
+    storage.set(&pending_key(key, REQUESTED_SCOPE_KEY_SUFFIX), scope)
 }
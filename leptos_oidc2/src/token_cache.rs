@@ -0,0 +1,312 @@
+/*
+* The MIT License (MIT)
+*
+* Copyright (c) 2023 Daniél Kerkmann <daniel@kerkmann.dev>
+*
+* Permission is hereby granted, free of charge, to any person obtaining a copy
+* of this software and associated documentation files (the "Software"), to deal
+* in the Software without restriction, including without limitation the rights
+* to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+* copies of the Software, and to permit persons to whom the Software is
+* furnished to do so, subject to the following conditions:
+*
+* The above copyright notice and this permission notice shall be included in all
+* copies or substantial portions of the Software.
+*
+* THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+* IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+* FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+* AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+* LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+* OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+* SOFTWARE.
+*/
+
+use std::collections::HashMap;
+
+use chrono::Duration;
+use futures::future::{FutureExt, LocalBoxFuture, Shared};
+use leptos::{
+    create_effect, create_rw_signal, RwSignal, SignalGetUntracked, SignalUpdateUntracked,
+    SignalWith, SignalWithUntracked,
+};
+
+use crate::{
+    error::AuthError,
+    exchange::{ExchangedToken, TokenTarget},
+    utils::now,
+    Auth, Scopes,
+};
+
+/// A pending acquisition of a cache entry, shared by concurrent callers.
+type PendingEntry = Shared<LocalBoxFuture<'static, Result<ExchangedToken, AuthError>>>;
+
+/// The key of a `TokenCache` entry: the downstream service and the requested
+/// scopes. The scopes are compared as a set, their order doesn't matter.
+/// Tokens exchanged for an explicit subject token (`Auth::exchange_token`)
+/// are kept apart from the tokens of the user (`Auth::access_token_for`).
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct TokenCacheKey {
+    target: TokenTarget,
+    scope: Vec<String>,
+    subject_token: Option<String>,
+}
+
+impl TokenCacheKey {
+    /// Creates the key of the given target and scopes.
+    #[must_use]
+    pub fn new(target: TokenTarget, scope: &Scopes) -> Self {
+        let mut scope = scope.iter().map(str::to_string).collect::<Vec<_>>();
+        scope.sort();
+        Self {
+            target,
+            scope,
+            subject_token: None,
+        }
+    }
+
+    /// Creates the key of a token exchanged for the given subject token.
+    pub(crate) fn for_subject(target: TokenTarget, subject_token: &str) -> Self {
+        Self {
+            subject_token: Some(subject_token.to_string()),
+            ..Self::new(target, &Scopes::new())
+        }
+    }
+
+    /// Returns the downstream service of the entry.
+    #[must_use]
+    pub fn target(&self) -> &TokenTarget {
+        &self.target
+    }
+
+    /// Returns the requested scopes of the entry, sorted.
+    #[must_use]
+    pub fn scope(&self) -> Scopes {
+        self.scope.iter().fold(Scopes::new(), Scopes::custom)
+    }
+}
+
+/// A cached token with the subject token it was exchanged for.
+#[derive(Debug, Clone)]
+struct CacheEntry {
+    subject_token: String,
+    token: ExchangedToken,
+}
+
+/// A cache of access tokens for downstream services, keyed by audience or
+/// resource and scope set, see `Auth::access_token_for`. Every entry has its
+/// own expiry and is acquired again once it expires. The cache is cleared
+/// when the user logs out or another user logs in.
+#[derive(Debug, Clone, Copy)]
+pub struct TokenCache {
+    entries: RwSignal<HashMap<TokenCacheKey, CacheEntry>>,
+    pending: RwSignal<HashMap<TokenCacheKey, PendingEntry>>,
+    /// Incremented by every `TokenCache::clear`, tokens acquired before are
+    /// dropped.
+    generation: RwSignal<u64>,
+}
+
+impl TokenCache {
+    pub(crate) fn new() -> Self {
+        Self {
+            entries: create_rw_signal(HashMap::new()),
+            pending: create_rw_signal(HashMap::new()),
+            generation: create_rw_signal(0),
+        }
+    }
+
+    /// Returns the cached token of the key, if it's not expired yet.
+    #[must_use]
+    pub fn get(&self, key: &TokenCacheKey) -> Option<ExchangedToken> {
+        self.fresh(key, None, Duration::zero())
+    }
+
+    /// Returns the keys of the cached tokens.
+    #[must_use]
+    pub fn keys(&self) -> Vec<TokenCacheKey> {
+        self.entries
+            .with_untracked(|entries| entries.keys().cloned().collect())
+    }
+
+    /// Forgets the token of the key, e.g. after the downstream service
+    /// rejected it. The next request acquires a new one.
+    pub fn invalidate(&self, key: &TokenCacheKey) {
+        self.entries.update_untracked(|entries| {
+            entries.remove(key);
+        });
+    }
+
+    /// Forgets every cached token and the pending acquisitions. Tokens of
+    /// acquisitions, which complete afterwards, are dropped.
+    pub fn clear(&self) {
+        self.generation
+            .update_untracked(|generation| *generation += 1);
+        self.entries.update_untracked(HashMap::clear);
+        self.pending.update_untracked(HashMap::clear);
+    }
+
+    /// Returns the generation of the cache, which changes on every clear.
+    pub(crate) fn generation(&self) -> u64 {
+        self.generation.get_untracked()
+    }
+
+    /// Returns the cached token of the key, if it doesn't expire within the
+    /// margin and, if given, was exchanged for the subject token.
+    pub(crate) fn fresh(
+        &self,
+        key: &TokenCacheKey,
+        subject_token: Option<&str>,
+        margin: Duration,
+    ) -> Option<ExchangedToken> {
+        self.entries.with_untracked(|entries| {
+            entries
+                .get(key)
+                .filter(|entry| {
+                    subject_token.map_or(true, |subject| entry.subject_token == subject)
+                })
+                .filter(|entry| {
+                    entry
+                        .token
+                        .expires_at
+                        .is_some_and(|expires_at| expires_at - margin > now())
+                })
+                .map(|entry| entry.token.clone())
+        })
+    }
+
+    /// Caches the token exchanged for the subject token, if the cache wasn't
+    /// cleared since the given generation. Tokens without expiry aren't
+    /// cached.
+    ///
+    /// # Errors
+    ///
+    /// Returns `AuthError::Unauthenticated` if the cache was cleared in the
+    /// meantime, e.g. by a logout, so the token must not be used anymore.
+    pub(crate) fn insert(
+        &self,
+        generation: u64,
+        key: TokenCacheKey,
+        subject_token: String,
+        token: ExchangedToken,
+    ) -> Result<(), AuthError> {
+        if self.generation() != generation {
+            return Err(AuthError::Unauthenticated);
+        }
+        if token.expires_at.is_none() {
+            return Ok(());
+        }
+        self.entries.update_untracked(|entries| {
+            entries.insert(
+                key,
+                CacheEntry {
+                    subject_token,
+                    token,
+                },
+            );
+        });
+
+        Ok(())
+    }
+}
+
+impl Auth {
+    /// Returns the cache of the downstream access tokens.
+    #[must_use]
+    pub fn token_cache(&self) -> TokenCache {
+        self.token_cache
+    }
+
+    /// Returns an access token for the given downstream service and scopes,
+    /// e.g. one per API with a distinct audience. The token is taken from
+    /// the `TokenCache` and acquired with a token exchange of the access
+    /// token of the user once it's missing or expires within the
+    /// `refresh_before_expiry` window, concurrent calls share the exchange.
+    ///
+    /// # Errors
+    ///
+    /// Returns `AuthError::Unauthenticated` without an access token or if the
+    /// user logged out during the exchange, and the error of the token
+    /// exchange, see `OidcClient::exchange_token`.
+    pub async fn access_token_for(
+        &self,
+        target: &TokenTarget,
+        scope: &Scopes,
+    ) -> Result<String, AuthError> {
+        let key = TokenCacheKey::new(target.clone(), scope);
+        if let Some(token) = self.token_cache.fresh(&key, None, self.cache_margin()) {
+            return Ok(token.access_token);
+        }
+
+        let pending = self
+            .token_cache
+            .pending
+            .with_untracked(|pending| pending.get(&key).cloned());
+        let pending = pending.unwrap_or_else(|| {
+            let auth = self.clone();
+            let pending_key = key.clone();
+            let generation = self.token_cache.generation();
+            let future = async move {
+                let result = auth.acquire_cache_entry(generation, &pending_key).await;
+                // A clear already removed the acquisition, and maybe another
+                // one was started since.
+                if auth.token_cache.generation() == generation {
+                    auth.token_cache.pending.update_untracked(|pending| {
+                        pending.remove(&pending_key);
+                    });
+                }
+                result
+            }
+            .boxed_local()
+            .shared();
+            self.token_cache.pending.update_untracked(|pending| {
+                pending.insert(key, future.clone());
+            });
+            future
+        });
+
+        pending.await.map(|token| token.access_token)
+    }
+
+    /// Exchanges the current access token for the token of the cache entry
+    /// and caches it, unless the cache was cleared in the meantime.
+    async fn acquire_cache_entry(
+        &self,
+        generation: u64,
+        key: &TokenCacheKey,
+    ) -> Result<ExchangedToken, AuthError> {
+        let subject_token = self
+            .access_token_async()
+            .await
+            .ok_or(AuthError::Unauthenticated)?;
+        let token = self
+            .oidc_client()
+            .exchange_token(key.target(), &subject_token, &key.scope())
+            .await?;
+        self.token_cache
+            .insert(generation, key.clone(), subject_token, token.clone())?;
+
+        Ok(token)
+    }
+
+    /// Returns the margin before the expiry, within which a cached token is
+    /// acquired again.
+    pub(crate) fn cache_margin(&self) -> Duration {
+        Duration::from_std(self.parameters.leeway + self.parameters.refresh_before_expiry)
+            .unwrap_or_else(|_| Duration::zero())
+    }
+
+    /// Clears the token cache whenever the user logs out or another user
+    /// logs in, e.g. in another tab.
+    pub(crate) fn watch_token_cache(&self) {
+        let token_cache = self.token_cache;
+        let id_token_claims = self.id_token_claims;
+        create_effect(move |previous: Option<Option<String>>| {
+            let subject = id_token_claims
+                .with(|claims| claims.as_ref().and_then(|claims| claims.sub.clone()));
+            if previous.is_some_and(|previous| previous != subject) {
+                token_cache.clear();
+            }
+            subject
+        });
+    }
+}
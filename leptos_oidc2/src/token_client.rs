@@ -0,0 +1,151 @@
+/*
+* The MIT License (MIT)
+*
+* Copyright (c) 2023 Daniél Kerkmann <daniel@kerkmann.dev>
+*
+* Permission is hereby granted, free of charge, to any person obtaining a copy
+* of this software and associated documentation files (the "Software"), to deal
+* in the Software without restriction, including without limitation the rights
+* to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+* copies of the Software, and to permit persons to whom the Software is
+* furnished to do so, subject to the following conditions:
+*
+* The above copyright notice and this permission notice shall be included in all
+* copies or substantial portions of the Software.
+*
+* THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+* IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+* FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+* AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+* LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+* OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+* SOFTWARE.
+*/
+
+use std::sync::Arc;
+
+pub use crate::client::ClientAuthMethod;
+use crate::{
+    client::{client_authentication, http_client, HttpClient, HttpRequest},
+    error::AuthError,
+    request_token,
+    response::SuccessTokenResponse,
+    AuthParameters,
+};
+
+/// A low-level client of the token endpoint for the grants without user
+/// interaction, the client credentials grant and the resource owner password
+/// grant. It's meant for internal tooling and tests, browsers must not hold
+/// a client secret or the password of a user.
+#[derive(Debug, Clone)]
+pub struct TokenClient {
+    token_endpoint: String,
+    client_id: String,
+    client_secret: Option<(String, ClientAuthMethod)>,
+    http_client: Arc<dyn HttpClient>,
+}
+
+impl TokenClient {
+    /// Creates a client of the given token endpoint for a public client.
+    #[must_use]
+    pub fn new(token_endpoint: impl Into<String>, client_id: impl Into<String>) -> Self {
+        Self {
+            token_endpoint: token_endpoint.into(),
+            client_id: client_id.into(),
+            client_secret: None,
+            http_client: http_client(),
+        }
+    }
+
+    /// Creates a client of the token endpoint and the client ID of the given
+    /// parameters.
+    #[must_use]
+    pub fn from_parameters(parameters: &AuthParameters) -> Self {
+        Self {
+            http_client: parameters.client(),
+            ..Self::new(&parameters.token_endpoint, &parameters.client_id)
+        }
+    }
+
+    /// Sets the secret of a confidential client and how it's sent.
+    #[must_use]
+    pub fn client_secret(
+        mut self,
+        client_secret: impl Into<String>,
+        method: ClientAuthMethod,
+    ) -> Self {
+        self.client_secret = Some((client_secret.into(), method));
+        self
+    }
+
+    /// Sets the HTTP client used for the requests.
+    #[must_use]
+    pub fn http_client(mut self, http_client: impl HttpClient + 'static) -> Self {
+        self.http_client = Arc::new(http_client);
+        self
+    }
+
+    /// Requests a token for the client itself (`client_credentials` grant).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the request failed or the provider rejected it.
+    pub async fn client_credentials(
+        &self,
+        scope: Option<&str>,
+    ) -> Result<SuccessTokenResponse, AuthError> {
+        self.grant(vec![("grant_type", "client_credentials")], scope)
+            .await
+    }
+
+    /// Requests a token with the credentials of a user (`password` grant).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the request failed or the provider rejected it.
+    pub async fn password(
+        &self,
+        username: &str,
+        password: &str,
+        scope: Option<&str>,
+    ) -> Result<SuccessTokenResponse, AuthError> {
+        let params = vec![
+            ("grant_type", "password"),
+            ("username", username),
+            ("password", password),
+        ];
+        self.grant(params, scope).await
+    }
+
+    /// Sends a grant with the given parameters and the client authentication.
+    async fn grant<'a>(
+        &'a self,
+        mut params: Vec<(&'a str, &'a str)>,
+        scope: Option<&'a str>,
+    ) -> Result<SuccessTokenResponse, AuthError> {
+        if let Some(scope) = scope {
+            params.push(("scope", scope));
+        }
+        // With the `Authorization` header the client is identified by it.
+        if !matches!(self.client_secret, Some((_, ClientAuthMethod::Basic))) {
+            params.push(("client_id", &self.client_id));
+        }
+
+        let (body, authorization) = client_authentication(
+            &self.client_id,
+            self.client_secret
+                .as_ref()
+                .map(|(client_secret, method)| (client_secret.as_str(), *method)),
+            params
+                .into_iter()
+                .map(|(key, value)| (key.to_string(), value.to_string()))
+                .collect(),
+        );
+        let mut request = HttpRequest::post_form(&self.token_endpoint, body);
+        if let Some(authorization) = authorization {
+            request = request.header("Authorization", authorization);
+        }
+
+        request_token(self.http_client.as_ref(), request).await
+    }
+}
@@ -0,0 +1,103 @@
+/*
+* The MIT License (MIT)
+*
+* Copyright (c) 2023 Daniél Kerkmann <daniel@kerkmann.dev>
+*
+* Permission is hereby granted, free of charge, to any person obtaining a copy
+* of this software and associated documentation files (the "Software"), to deal
+* in the Software without restriction, including without limitation the rights
+* to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+* copies of the Software, and to permit persons to whom the Software is
+* furnished to do so, subject to the following conditions:
+*
+* The above copyright notice and this permission notice shall be included in all
+* copies or substantial portions of the Software.
+*
+* THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+* IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+* FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+* AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+* LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+* OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+* SOFTWARE.
+*/
+
+use std::sync::Arc;
+
+use leptos::{
+    create_local_resource, Memo, Resource, RwSignal, SignalGet, SignalGetUntracked, SignalSet,
+    SignalWith,
+};
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+
+use crate::{
+    client::HttpRequest, discovery::ProviderMetadata, error::AuthError, storage::TokenIdentity,
+    Auth, AuthParameters,
+};
+
+/// The source of the cached user info: whether it was requested, the
+/// endpoint and the access token.
+pub(crate) type UserInfoSource = (bool, Option<String>, Option<String>);
+
+/// Creates the resource caching the user info of the current access token.
+/// It's only fetched once requested by `Auth::user_info` and refetched
+/// whenever the access token changes, e.g. after a refresh.
+pub(crate) fn user_info_resource(
+    parameters: &AuthParameters,
+    metadata: RwSignal<Option<ProviderMetadata>>,
+    tokens: Memo<Option<TokenIdentity>>,
+    requested: RwSignal<bool>,
+) -> Resource<UserInfoSource, Result<Value, AuthError>> {
+    let endpoint = parameters.userinfo_endpoint.clone();
+    let client = parameters.client();
+    create_local_resource(
+        move || {
+            let endpoint = endpoint.clone().or_else(|| {
+                metadata
+                    .get()
+                    .and_then(|metadata| metadata.userinfo_endpoint)
+            });
+            let access_token =
+                tokens.with(|tokens| tokens.as_ref().map(|tokens| tokens.0.access_token.clone()));
+            (requested.get(), endpoint, access_token)
+        },
+        move |(requested, endpoint, access_token)| {
+            let client = client.clone();
+            async move {
+                if !requested {
+                    return Err(AuthError::Unauthenticated);
+                }
+                let endpoint = endpoint.ok_or(AuthError::Discovery("userinfo_endpoint"))?;
+                let access_token = access_token.ok_or(AuthError::Unauthenticated)?;
+
+                let request = HttpRequest::get(endpoint)
+                    .header("Authorization", format!("Bearer {access_token}"));
+                Ok(client.send(request).await?.error_for_status()?.json()?)
+            }
+        },
+    )
+}
+
+impl Auth {
+    /// Returns the claims of the userinfo endpoint, which often contains
+    /// profile data missing in the ID token, like the picture, the locale or
+    /// custom attributes. The response is fetched with the access token on
+    /// the first call, cached and fetched again once the access token
+    /// changes. Returns `None` while it's loading.
+    ///
+    /// The endpoint is taken from `AuthParameters::userinfo_endpoint` or the
+    /// provider metadata.
+    #[must_use]
+    pub fn user_info<T: DeserializeOwned>(&self) -> Option<Result<T, AuthError>> {
+        self.activate();
+        if !self.user_info_requested.get_untracked() {
+            self.user_info_requested.set(true);
+        }
+
+        let user_info = self.user_info.get()?;
+        Some(user_info.and_then(|user_info| {
+            serde_json::from_value(user_info).map_err(|error| AuthError::Serde(Arc::new(error)))
+        }))
+    }
+}
@@ -22,41 +22,21 @@
 * SOFTWARE.
 */
 
-/// A trait for building query and body parameters in a string.
-pub trait ParamBuilder {
-    /// Appends a key-value pair to the string as a query parameter. If the
-    /// string doesn't contain any query parameters, it adds a '?' character.
-    /// Otherwise, it appends '&'.
-    #[must_use]
-    fn push_param_query(self, key: impl AsRef<str>, value: impl AsRef<str>) -> Self;
+use std::time::Duration;
 
-    /// Appends a key-value pair to the string as a body parameter.
-    /// It always appends '&'.
-    #[must_use]
-    fn push_param_body(self, key: impl AsRef<str>, value: impl AsRef<str>) -> Self;
-}
+use futures::channel::oneshot;
 
-/// Implementation of the `ParamBuilder` trait for the `String` type.
-impl ParamBuilder for String {
-    /// Appends a key-value pair to the string as a query parameter.
-    fn push_param_query(mut self, key: impl AsRef<str>, value: impl AsRef<str>) -> Self {
-        if !self.contains('?') {
-            self.push('?');
-        } else if !self.ends_with('&') {
-            self.push('&');
-        }
-        self.push_str(key.as_ref());
-        self.push('=');
-        self.push_str(value.as_ref());
-        self
-    }
+pub(crate) use oidc_core::utils::{form_encode, now, push_query};
+pub use oidc_core::utils::{random_string, ParamBuilder};
 
-    /// Appends a key-value pair to the string as a body parameter.
-    fn push_param_body(mut self, key: impl AsRef<str>, value: impl AsRef<str>) -> Self {
-        self.push('&');
-        self.push_str(key.as_ref());
-        self.push('=');
-        self.push_str(value.as_ref());
-        self
-    }
+/// Waits for the given duration, using the timers of the browser.
+pub(crate) async fn sleep(duration: Duration) {
+    let (sender, receiver) = oneshot::channel();
+    leptos::set_timeout(
+        move || {
+            sender.send(()).ok();
+        },
+        duration,
+    );
+    receiver.await.ok();
 }
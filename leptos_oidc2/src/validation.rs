@@ -0,0 +1,163 @@
+/*
+* The MIT License (MIT)
+*
+* Copyright (c) 2023 Daniél Kerkmann <daniel@kerkmann.dev>
+*
+* Permission is hereby granted, free of charge, to any person obtaining a copy
+* of this software and associated documentation files (the "Software"), to deal
+* in the Software without restriction, including without limitation the rights
+* to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+* copies of the Software, and to permit persons to whom the Software is
+* furnished to do so, subject to the following conditions:
+*
+* The above copyright notice and this permission notice shall be included in all
+* copies or substantial portions of the Software.
+*
+* THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+* IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+* FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+* AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+* LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+* OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+* SOFTWARE.
+*/
+
+use url::Url;
+
+use crate::{error::AuthConfigError, AuthParameters, LogoutBehavior};
+
+/// Hosts which may be reached over plain `http`, e.g. during development.
+const LOCAL_HOSTS: [&str; 3] = ["localhost", "127.0.0.1", "[::1]"];
+
+impl AuthParameters {
+    /// Checks the parameters for mistakes, which would otherwise only show
+    /// up deep inside a request to the provider: invalid or insecure URLs,
+    /// redirect URIs on another origin, a scope without `openid` and
+    /// conflicting options. `Auth::init` logs the problems on startup, the
+    /// `AuthParametersBuilder` rejects them.
+    ///
+    /// # Errors
+    ///
+    /// Returns every problem found, in a human-readable form.
+    pub fn validate(&self) -> Result<(), Vec<AuthConfigError>> {
+        let mut problems = Vec::new();
+
+        let urls = [
+            ("issuer", self.issuer.as_ref()),
+            ("auth_endpoint", Some(&self.auth_endpoint)),
+            ("token_endpoint", Some(&self.token_endpoint)),
+            ("logout_endpoint", Some(&self.logout_endpoint)),
+            ("jwks_uri", self.jwks_uri.as_ref()),
+            ("revocation_endpoint", self.revocation_endpoint.as_ref()),
+            ("check_session_iframe", self.check_session_iframe.as_ref()),
+            (
+                "introspection_endpoint",
+                self.introspection_endpoint.as_ref(),
+            ),
+            (
+                "account_deletion_endpoint",
+                self.account_deletion_endpoint.as_ref(),
+            ),
+            ("userinfo_endpoint", self.userinfo_endpoint.as_ref()),
+            (
+                "pushed_authorization_request_endpoint",
+                self.pushed_authorization_request_endpoint.as_ref(),
+            ),
+            ("maintenance_url", self.maintenance_url.as_ref()),
+            ("redirect_uri", Some(&self.redirect_uri)),
+            (
+                "post_logout_redirect_uri",
+                Some(&self.post_logout_redirect_uri),
+            ),
+            ("silent_redirect_uri", self.silent_redirect_uri.as_ref()),
+        ];
+        for (field, value) in urls {
+            if let Some(value) = value {
+                check_url(field, value, &mut problems);
+            }
+        }
+
+        if let Ok(redirect_uri) = Url::parse(&self.redirect_uri) {
+            let expected = redirect_uri.origin().ascii_serialization();
+            let redirect_uris = [
+                (
+                    "post_logout_redirect_uri",
+                    Some(&self.post_logout_redirect_uri),
+                ),
+                ("silent_redirect_uri", self.silent_redirect_uri.as_ref()),
+            ];
+            for (field, value) in redirect_uris {
+                let Some(url) = value.and_then(|value| Url::parse(value).ok()) else {
+                    continue;
+                };
+                if url.origin().ascii_serialization() != expected {
+                    problems.push(AuthConfigError::OriginMismatch {
+                        field,
+                        value: url.to_string(),
+                        expected: expected.clone(),
+                    });
+                }
+            }
+        }
+
+        if !self.scope.contains("openid") {
+            problems.push(AuthConfigError::MissingOpenidScope);
+        }
+
+        if self.prompt.as_deref().is_some_and(|prompt| {
+            prompt.split_whitespace().any(|prompt| prompt == "none")
+                && prompt.split_whitespace().count() > 1
+        }) {
+            problems.push(AuthConfigError::Conflict(
+                "the prompt 'none' can't be combined with other prompts",
+            ));
+        }
+        if self.silent_redirect_uri.as_ref() == Some(&self.redirect_uri) {
+            problems.push(AuthConfigError::Conflict(
+                "the silent_redirect_uri has to be another page than the redirect_uri",
+            ));
+        }
+        if self.logout_behavior == LogoutBehavior::LocalOnly && self.logout_without_id_token_hint {
+            problems.push(AuthConfigError::Conflict(
+                "logout_without_id_token_hint has no effect with LogoutBehavior::LocalOnly",
+            ));
+        }
+
+        if self.pushed_authorization && self.pushed_authorization_request_endpoint.is_none() {
+            problems.push(AuthConfigError::Conflict(
+                "pushed_authorization requires a pushed_authorization_request_endpoint",
+            ));
+        }
+        if self.dpop && !cfg!(feature = "dpop") {
+            problems.push(AuthConfigError::Conflict(
+                "dpop requires the 'dpop' feature",
+            ));
+        }
+
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(problems)
+        }
+    }
+}
+
+/// Checks that a URL is valid and uses `https`, except for local hosts.
+fn check_url(field: &'static str, value: &str, problems: &mut Vec<AuthConfigError>) {
+    let Ok(url) = Url::parse(value) else {
+        problems.push(AuthConfigError::InvalidUrl {
+            field,
+            value: value.to_string(),
+        });
+        return;
+    };
+    let local = url
+        .host_str()
+        .is_some_and(|host| LOCAL_HOSTS.contains(&host) || host.ends_with(".localhost"));
+    if url.scheme() != "https" && !(url.scheme() == "http" && local) {
+        problems.push(AuthConfigError::InsecureUrl {
+            field,
+            value: value.to_string(),
+        });
+    }
+}
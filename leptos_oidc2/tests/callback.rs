@@ -0,0 +1,266 @@
+//! Browser tests of the callback handling of `Auth::init`, which loads the
+//! initial state from a login, logout or error callback or a previous
+//! session, run with `wasm-pack`:
+//!
+//! ```sh
+//! wasm-pack test --headless --firefox leptos_oidc2
+//! ```
+#![cfg(target_arch = "wasm32")]
+
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    rc::Rc,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use chrono::Utc;
+use futures::{
+    channel::oneshot,
+    future::{FutureExt, LocalBoxFuture},
+};
+use leptos::{mount_to_body, set_timeout, view, wasm_bindgen::JsValue, window};
+use leptos_oidc2::{
+    client::{HttpClient, HttpRequest, HttpResponse},
+    storage::{InMemoryStorage, Storage, StorageKind, TokenStorage},
+    Auth, AuthError, AuthParameters, OidcError,
+};
+use leptos_router::Router;
+use serde_json::json;
+use url::Url;
+use wasm_bindgen_test::{wasm_bindgen_test, wasm_bindgen_test_configure};
+
+wasm_bindgen_test_configure!(run_in_browser);
+
+/// A token endpoint answering every request with new tokens, which counts
+/// the requests.
+#[derive(Debug, Default)]
+struct TokenEndpoint {
+    requests: Mutex<Vec<String>>,
+}
+
+impl HttpClient for TokenEndpoint {
+    fn send(&self, request: HttpRequest) -> LocalBoxFuture<'_, Result<HttpResponse, OidcError>> {
+        self.requests
+            .lock()
+            .unwrap()
+            .push(request.body.unwrap_or_default());
+        let response = HttpResponse {
+            status: 200,
+            headers: vec![("Content-Type".to_string(), "application/json".to_string())],
+            body: json!({
+                "access_token": "access-2",
+                "expires_in": 300,
+                "refresh_token": "refresh-2",
+                "id_token": id_token("user-2"),
+            })
+            .to_string(),
+        };
+        async move { Ok(response) }.boxed_local()
+    }
+}
+
+/// Returns an unsigned ID token of the given subject, only its claims are
+/// read.
+fn id_token(sub: &str) -> String {
+    let claims = URL_SAFE_NO_PAD.encode(json!({ "sub": sub }).to_string());
+    format!("eyJhbGciOiJSUzI1NiJ9.{claims}.signature")
+}
+
+/// Returns the parameters of a client keeping its tokens in memory, under
+/// its own key.
+fn parameters(key: &str, endpoint: &Arc<TokenEndpoint>) -> AuthParameters {
+    AuthParameters {
+        auth_endpoint: "https://idp.example.com/auth".to_string(),
+        token_endpoint: "https://idp.example.com/token".to_string(),
+        logout_endpoint: "https://idp.example.com/logout".to_string(),
+        client_id: "backend-test".to_string(),
+        redirect_uri: "http://localhost:3000/profile".to_string(),
+        post_logout_redirect_uri: "http://localhost:3000/bye".to_string(),
+        storage: StorageKind::InMemory,
+        storage_key: Some(key.to_string()),
+        http_client: Some(Arc::clone(endpoint) as Arc<dyn HttpClient>),
+        ..AuthParameters::default()
+    }
+}
+
+/// Writes the tokens of a previous session, with a valid access token.
+fn seed_tokens(key: &str) {
+    let tokens = TokenStorage {
+        id_token: id_token("user-1"),
+        access_token: "access-1".to_string(),
+        expires_in: Utc::now().naive_utc() + chrono::Duration::hours(1),
+        refresh_token: Some("refresh-1".to_string()),
+        ..TokenStorage::default()
+    };
+    InMemoryStorage
+        .set(key, &serde_json::to_string(&tokens).unwrap())
+        .unwrap();
+}
+
+/// Returns the access token stored under the key, if any.
+fn stored_access_token(key: &str) -> Option<String> {
+    let tokens = InMemoryStorage.get(key).unwrap()?;
+    Some(
+        serde_json::from_str::<TokenStorage>(&tokens)
+            .unwrap()
+            .access_token,
+    )
+}
+
+/// Sets the URL of the page, like a redirect of the provider does.
+fn visit(url: &str) {
+    window()
+        .history()
+        .unwrap()
+        .replace_state_with_url(&JsValue::NULL, "", Some(url))
+        .unwrap();
+}
+
+/// Returns the decoded query parameters of the URL.
+fn query_pairs(url: &str) -> HashMap<String, String> {
+    Url::parse(url)
+        .expect("invalid URL")
+        .query_pairs()
+        .into_owned()
+        .collect()
+}
+
+/// Initializes the auth within a router, like an app does.
+fn mount_auth(parameters: AuthParameters) -> Auth {
+    let auth = Rc::new(RefCell::new(None));
+    mount_to_body({
+        let auth = Rc::clone(&auth);
+        move || {
+            view! {
+                <Router>
+                    {
+                        auth.borrow_mut().replace(Auth::init(parameters));
+                    }
+                </Router>
+            }
+        }
+    });
+
+    let auth = auth.borrow_mut().take();
+    auth.expect("the router didn't render its children")
+}
+
+/// Waits until the state is loaded and no refresh is in flight.
+async fn settled(auth: &Auth) {
+    while auth.loading() || auth.refreshing() {
+        let (sender, receiver) = oneshot::channel();
+        set_timeout(
+            move || {
+                sender.send(()).ok();
+            },
+            Duration::from_millis(10),
+        );
+        receiver.await.ok();
+    }
+}
+
+#[wasm_bindgen_test]
+async fn login_callback_exchanges_the_code() {
+    let endpoint = Arc::new(TokenEndpoint::default());
+    visit("/profile?code=abc&session_state=session-1");
+
+    let auth = mount_auth(parameters("auth_callback_login", &endpoint));
+    settled(&auth).await;
+
+    assert!(auth.authenticated());
+    assert_eq!(auth.access_token().as_deref(), Some("access-2"));
+    assert_eq!(
+        stored_access_token("auth_callback_login").as_deref(),
+        Some("access-2")
+    );
+    let requests = endpoint.requests.lock().unwrap();
+    assert_eq!(requests.len(), 1);
+    assert!(requests[0].contains("grant_type=authorization_code"));
+    assert!(requests[0].contains("code=abc"));
+}
+
+#[wasm_bindgen_test]
+async fn login_callback_on_another_path_is_ignored() {
+    let endpoint = Arc::new(TokenEndpoint::default());
+    visit("/orders?code=abc");
+
+    let auth = mount_auth(parameters("auth_callback_path", &endpoint));
+    settled(&auth).await;
+
+    assert!(!auth.authenticated());
+    assert!(endpoint.requests.lock().unwrap().is_empty());
+}
+
+#[wasm_bindgen_test]
+async fn logout_callback_ends_the_session() {
+    let endpoint = Arc::new(TokenEndpoint::default());
+    seed_tokens("auth_callback_logout");
+    visit("/");
+    let auth = mount_auth(parameters("auth_callback_logout", &endpoint));
+    settled(&auth).await;
+    assert!(auth.authenticated());
+
+    // The logout URL stores the state, the provider passes it back within
+    // the post logout redirect URI.
+    let logout_url = auth.logout_url();
+    let redirect_uri = query_pairs(&logout_url)["post_logout_redirect_uri"].clone();
+    let state = query_pairs(&redirect_uri)["state"].clone();
+    visit(&format!("/bye?destroy_session=true&state={state}"));
+
+    let auth = mount_auth(parameters("auth_callback_logout", &endpoint));
+    settled(&auth).await;
+
+    assert!(!auth.authenticated());
+    assert!(auth.err().is_none());
+    assert_eq!(stored_access_token("auth_callback_logout"), None);
+}
+
+#[wasm_bindgen_test]
+async fn replayed_logout_callback_keeps_the_session() {
+    let endpoint = Arc::new(TokenEndpoint::default());
+    seed_tokens("auth_callback_replay");
+    // A reload of a logout callback, whose state was consumed already.
+    visit("/bye?destroy_session=true&state=consumed");
+
+    let auth = mount_auth(parameters("auth_callback_replay", &endpoint));
+    settled(&auth).await;
+
+    assert!(auth.authenticated());
+    assert_eq!(
+        stored_access_token("auth_callback_replay").as_deref(),
+        Some("access-1")
+    );
+}
+
+#[wasm_bindgen_test]
+async fn error_callback_reports_the_provider_error() {
+    let endpoint = Arc::new(TokenEndpoint::default());
+    visit("/profile?error=access_denied&error_description=denied");
+
+    let auth = mount_auth(parameters("auth_callback_error", &endpoint));
+    settled(&auth).await;
+
+    assert!(matches!(
+        auth.err(),
+        Some(AuthError::Provider(response)) if response.error == "access_denied"
+    ));
+    assert!(!auth.authenticated());
+    assert!(endpoint.requests.lock().unwrap().is_empty());
+}
+
+#[wasm_bindgen_test]
+async fn without_callback_restores_the_previous_session() {
+    let endpoint = Arc::new(TokenEndpoint::default());
+    seed_tokens("auth_callback_restore");
+    visit("/");
+
+    let auth = mount_auth(parameters("auth_callback_restore", &endpoint));
+    settled(&auth).await;
+
+    assert!(auth.authenticated());
+    assert_eq!(auth.access_token().as_deref(), Some("access-1"));
+    assert!(endpoint.requests.lock().unwrap().is_empty());
+}
@@ -0,0 +1,185 @@
+//! Browser tests of the storage failure paths with `FaultyStorage`, run with
+//! `wasm-pack`:
+//!
+//! ```sh
+//! wasm-pack test --headless --firefox leptos_oidc2 --features fault-injection
+//! ```
+#![cfg(all(target_arch = "wasm32", feature = "fault-injection"))]
+
+use std::{cell::RefCell, rc::Rc, sync::Arc, time::Duration};
+
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use chrono::Utc;
+use futures::{
+    channel::oneshot,
+    future::{FutureExt, LocalBoxFuture},
+};
+use leptos::{mount_to_body, set_timeout, view};
+use leptos_oidc2::{
+    client::{HttpClient, HttpRequest, HttpResponse},
+    fault::FaultyStorage,
+    storage::{InMemoryStorage, Storage, StorageKind, TokenStorage},
+    Auth, AuthError, AuthParameters, OidcError, StorageErrorKind,
+};
+use leptos_router::Router;
+use serde_json::{json, Value};
+use wasm_bindgen_test::{wasm_bindgen_test, wasm_bindgen_test_configure};
+
+wasm_bindgen_test_configure!(run_in_browser);
+
+/// A token endpoint answering every request with the given response.
+#[derive(Debug)]
+struct TokenEndpoint(Value);
+
+impl HttpClient for TokenEndpoint {
+    fn send(&self, _request: HttpRequest) -> LocalBoxFuture<'_, Result<HttpResponse, OidcError>> {
+        let response = HttpResponse {
+            status: 200,
+            headers: vec![("Content-Type".to_string(), "application/json".to_string())],
+            body: self.0.to_string(),
+        };
+        async move { Ok(response) }.boxed_local()
+    }
+}
+
+/// Returns the parameters of a client keeping everything in the given
+/// storage, under its own key.
+fn parameters(storage: &'static FaultyStorage, key: &str) -> AuthParameters {
+    AuthParameters {
+        auth_endpoint: "https://idp.example.com/auth".to_string(),
+        token_endpoint: "https://idp.example.com/token".to_string(),
+        logout_endpoint: "https://idp.example.com/logout".to_string(),
+        client_id: "backend-test".to_string(),
+        redirect_uri: "http://localhost:3000/profile".to_string(),
+        post_logout_redirect_uri: "http://localhost:3000/bye".to_string(),
+        storage: StorageKind::Custom(storage),
+        storage_key: Some(key.to_string()),
+        http_client: Some(Arc::new(TokenEndpoint(json!({
+            "access_token": "access-2",
+            "expires_in": 300,
+            "refresh_token": "refresh-2",
+        })))),
+        ..AuthParameters::default()
+    }
+}
+
+/// Writes the tokens of a previous session, with a valid access token.
+fn seed_tokens(storage: &FaultyStorage, key: &str) {
+    let claims = URL_SAFE_NO_PAD.encode(json!({ "sub": "user-1" }).to_string());
+    let tokens = TokenStorage {
+        id_token: format!("eyJhbGciOiJSUzI1NiJ9.{claims}.signature"),
+        access_token: "access-1".to_string(),
+        expires_in: Utc::now().naive_utc() + chrono::Duration::hours(1),
+        refresh_token: Some("refresh-1".to_string()),
+        ..TokenStorage::default()
+    };
+    storage
+        .set(key, &serde_json::to_string(&tokens).unwrap())
+        .unwrap();
+}
+
+/// Initializes the auth within a router, like an app does.
+fn mount_auth(parameters: AuthParameters) -> Auth {
+    let auth = Rc::new(RefCell::new(None));
+    mount_to_body({
+        let auth = Rc::clone(&auth);
+        move || {
+            view! {
+                <Router>
+                    {
+                        auth.borrow_mut().replace(Auth::init(parameters));
+                    }
+                </Router>
+            }
+        }
+    });
+
+    let auth = auth.borrow_mut().take();
+    auth.expect("the router didn't render its children")
+}
+
+/// Waits until the state is loaded and no refresh is in flight.
+async fn settled(auth: &Auth) {
+    while auth.loading() || auth.refreshing() {
+        let (sender, receiver) = oneshot::channel();
+        set_timeout(
+            move || {
+                sender.send(()).ok();
+            },
+            Duration::from_millis(10),
+        );
+        receiver.await.ok();
+    }
+}
+
+#[wasm_bindgen_test]
+async fn init_fails_if_the_tokens_cannot_be_read() {
+    static STORAGE: FaultyStorage = FaultyStorage::new(&InMemoryStorage);
+    seed_tokens(&STORAGE, "auth_fault_init");
+    STORAGE.fail_reads(true);
+
+    let auth = mount_auth(parameters(&STORAGE, "auth_fault_init"));
+    settled(&auth).await;
+
+    assert!(matches!(
+        auth.err(),
+        Some(AuthError::Storage(StorageErrorKind::Read))
+    ));
+    assert!(!auth.authenticated());
+    STORAGE.heal();
+}
+
+#[wasm_bindgen_test]
+async fn login_fails_if_the_code_verifier_cannot_be_stored() {
+    static STORAGE: FaultyStorage = FaultyStorage::new(&InMemoryStorage);
+    let auth = mount_auth(parameters(&STORAGE, "auth_fault_login"));
+    settled(&auth).await;
+
+    STORAGE.fail_writes(true);
+    assert!(matches!(
+        auth.login_url(),
+        Err(AuthError::Storage(StorageErrorKind::Write))
+    ));
+
+    STORAGE.heal();
+    assert!(auth
+        .login_url()
+        .is_ok_and(|url| url.contains("code_challenge=") && url.contains("nonce=")));
+}
+
+#[wasm_bindgen_test]
+async fn refresh_ends_the_session_if_the_tokens_cannot_be_written() {
+    static STORAGE: FaultyStorage = FaultyStorage::new(&InMemoryStorage);
+    seed_tokens(&STORAGE, "auth_fault_refresh");
+    let auth = mount_auth(parameters(&STORAGE, "auth_fault_refresh"));
+    settled(&auth).await;
+    assert!(auth.authenticated());
+
+    STORAGE.fail_writes(true);
+    auth.refresh_token();
+    settled(&auth).await;
+
+    assert!(matches!(
+        auth.err(),
+        Some(AuthError::Storage(StorageErrorKind::Write))
+    ));
+    // A session, which can't be persisted, isn't restored by the next reload.
+    assert_eq!(STORAGE.get("auth_fault_refresh").unwrap(), None);
+    STORAGE.heal();
+}
+
+#[wasm_bindgen_test]
+async fn local_logout_resets_the_state_if_the_tokens_cannot_be_removed() {
+    static STORAGE: FaultyStorage = FaultyStorage::new(&InMemoryStorage);
+    seed_tokens(&STORAGE, "auth_fault_logout");
+    let auth = mount_auth(parameters(&STORAGE, "auth_fault_logout"));
+    settled(&auth).await;
+    assert!(auth.authenticated());
+
+    STORAGE.fail_removes(true);
+    auth.local_logout();
+
+    assert!(!auth.authenticated());
+    assert!(auth.err().is_none());
+    STORAGE.heal();
+}
@@ -0,0 +1,87 @@
+//! Native tests of the Leptos specific parts without a browser, like the
+//! conversion of the router parameters of a callback. The protocol logic is
+//! tested in `oidc_core`:
+//!
+//! ```sh
+//! cargo test -p leptos_oidc2 --test native
+//! ```
+#![cfg(not(target_arch = "wasm32"))]
+
+use std::collections::HashMap;
+
+use leptos_oidc2::{
+    response::{CallbackParams, CallbackResponse},
+    ResponseType, Scopes, TokenCacheKey, TokenTarget,
+};
+use leptos_router::{ParamsError, ParamsMap};
+
+fn params(pairs: &[(&str, &str)]) -> ParamsMap {
+    let mut map = ParamsMap::new();
+    for (key, value) in pairs {
+        map.insert((*key).to_string(), (*value).to_string());
+    }
+    map
+}
+
+#[test]
+fn parses_callbacks() {
+    let login = CallbackResponse::from_map(&params(&[("code", "abc"), ("session_state", "s")]));
+    assert!(matches!(
+        login,
+        Ok(CallbackResponse::SuccessLogin(response))
+            if response.code == "abc" && response.session_state.as_deref() == Some("s")
+    ));
+
+    let error = CallbackResponse::from_map(&params(&[
+        ("error", "access_denied"),
+        ("error_description", "denied"),
+    ]));
+    assert!(matches!(
+        error,
+        Ok(CallbackResponse::Error(response)) if response.error == "access_denied"
+    ));
+
+    assert!(matches!(
+        CallbackResponse::from_map(&params(&[("page", "2")])),
+        Err(ParamsError::MissingParam(_))
+    ));
+}
+
+#[test]
+fn only_accepts_tokens_for_the_implicit_flow() {
+    let callback_params = params(&[("access_token", "access"), ("id_token", "id")]);
+
+    assert!(CallbackResponse::from_map(&callback_params).is_err());
+    assert!(matches!(
+        CallbackResponse::from_map_for(&callback_params, ResponseType::IdTokenToken),
+        Ok(CallbackResponse::SuccessImplicitLogin(_))
+    ));
+}
+
+#[test]
+fn renames_the_overridden_callback_params() {
+    let overrides = HashMap::from([("authorization_code".to_string(), "code".to_string())]);
+
+    let login = CallbackResponse::from_map_with_overrides(
+        params(&[("authorization_code", "abc")]),
+        &overrides,
+        ResponseType::Code,
+    );
+
+    assert!(matches!(
+        login,
+        Ok(CallbackResponse::SuccessLogin(response)) if response.code == "abc"
+    ));
+}
+
+#[test]
+fn keys_the_token_cache_by_scope_set() {
+    let target = TokenTarget::Audience("orders".to_string());
+    let key = TokenCacheKey::new(target.clone(), &Scopes::new().custom("b").custom("a"));
+
+    assert_eq!(
+        key,
+        TokenCacheKey::new(target.clone(), &Scopes::new().custom("a").custom("b"))
+    );
+    assert_ne!(key, TokenCacheKey::new(target, &Scopes::new().custom("a")));
+}
@@ -0,0 +1,26 @@
+//! Browser tests of the storage backends, run with `wasm-pack`:
+//!
+//! ```sh
+//! wasm-pack test --headless --firefox leptos_oidc2
+//! ```
+#![cfg(target_arch = "wasm32")]
+
+use leptos_oidc2::storage::StorageKind;
+use wasm_bindgen_test::{wasm_bindgen_test, wasm_bindgen_test_configure};
+
+wasm_bindgen_test_configure!(run_in_browser);
+
+#[wasm_bindgen_test]
+fn storage_backends_round_trip() {
+    for kind in [
+        StorageKind::Local,
+        StorageKind::Session,
+        StorageKind::InMemory,
+    ] {
+        let storage = kind.backend();
+        storage.set("auth_test", "value").unwrap();
+        assert_eq!(storage.get("auth_test").unwrap().as_deref(), Some("value"));
+        storage.remove("auth_test").unwrap();
+        assert_eq!(storage.get("auth_test").unwrap(), None);
+    }
+}
@@ -0,0 +1,101 @@
+/*
+* The MIT License (MIT)
+*
+* Copyright (c) 2023 Daniél Kerkmann <daniel@kerkmann.dev>
+*
+* Permission is hereby granted, free of charge, to any person obtaining a copy
+* of this software and associated documentation files (the "Software"), to deal
+* in the Software without restriction, including without limitation the rights
+* to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+* copies of the Software, and to permit persons to whom the Software is
+* furnished to do so, subject to the following conditions:
+*
+* The above copyright notice and this permission notice shall be included in all
+* copies or substantial portions of the Software.
+*
+* THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+* IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+* FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+* AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+* LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+* OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+* SOFTWARE.
+*/
+
+use std::sync::Arc;
+
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use jsonwebtoken::Algorithm;
+use serde::{de::DeserializeOwned, Deserialize};
+use serde_json::Value;
+
+use crate::error::OidcError;
+
+/// The claim path of the roles used if none was configured, as issued by
+/// Keycloak.
+pub const DEFAULT_ROLE_CLAIM: &str = "realm_access.roles";
+
+/// The claim path of the permissions used if none was configured, as issued
+/// by Auth0.
+pub const DEFAULT_PERMISSION_CLAIM: &str = "permissions";
+
+/// Decodes the claims (payload) of a JWT without validating its signature or
+/// any of its claims. Only use this for tokens which were received directly
+/// from the token endpoint.
+pub fn decode_claims_unverified<T: DeserializeOwned>(token: &str) -> Result<T, OidcError> {
+    decode_segment(token, 1)
+}
+
+/// Returns the algorithm declared in the header of a JWT.
+///
+/// # Errors
+///
+/// Returns `OidcError::UnsupportedAlgorithm` if the algorithm isn't supported
+/// by `jsonwebtoken`, e.g. `none` or `EdDSA` variants of newer drafts, and
+/// `OidcError::MalformedToken` if the header can't be read.
+pub fn token_algorithm(token: &str) -> Result<Algorithm, OidcError> {
+    #[derive(Deserialize)]
+    struct Header {
+        alg: String,
+    }
+
+    let header = decode_segment::<Header>(token, 0).map_err(|_| OidcError::MalformedToken)?;
+    header
+        .alg
+        .parse()
+        .map_err(|_| OidcError::UnsupportedAlgorithm(header.alg))
+}
+
+/// Decodes the JSON of the segment of a JWT at the given index.
+fn decode_segment<T: DeserializeOwned>(token: &str, index: usize) -> Result<T, OidcError> {
+    let segment = token
+        .split('.')
+        .nth(index)
+        .ok_or(OidcError::MalformedToken)?;
+    let segment = URL_SAFE_NO_PAD
+        .decode(segment.trim_end_matches('='))
+        .map_err(|_| OidcError::MalformedToken)?;
+
+    serde_json::from_slice(&segment).map_err(|error| OidcError::Serde(Arc::new(error)))
+}
+
+/// Returns the values of the claim at the given dot separated path, e.g.
+/// `realm_access.roles`. The claim may either be an array of strings or a
+/// space separated string, like the `scope` claim. Returns an empty list if
+/// the claim doesn't exist.
+#[must_use]
+pub fn claim_values(claims: &Value, path: &str) -> Vec<String> {
+    let claim = path
+        .split('.')
+        .try_fold(claims, |value, segment| value.get(segment));
+
+    match claim {
+        Some(Value::Array(values)) => values
+            .iter()
+            .filter_map(Value::as_str)
+            .map(str::to_string)
+            .collect(),
+        Some(Value::String(value)) => value.split_whitespace().map(str::to_string).collect(),
+        _ => Vec::new(),
+    }
+}
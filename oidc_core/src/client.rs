@@ -0,0 +1,375 @@
+/*
+* The MIT License (MIT)
+*
+* Copyright (c) 2023 Daniél Kerkmann <daniel@kerkmann.dev>
+*
+* Permission is hereby granted, free of charge, to any person obtaining a copy
+* of this software and associated documentation files (the "Software"), to deal
+* in the Software without restriction, including without limitation the rights
+* to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+* copies of the Software, and to permit persons to whom the Software is
+* furnished to do so, subject to the following conditions:
+*
+* The above copyright notice and this permission notice shall be included in all
+* copies or substantial portions of the Software.
+*
+* THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+* IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+* FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+* AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+* LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+* OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+* SOFTWARE.
+*/
+
+use std::{fmt::Debug, sync::Arc};
+#[cfg(feature = "reqwest")]
+use std::{sync::OnceLock, time::Duration};
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use futures::future::{FutureExt, LocalBoxFuture};
+use serde::{de::DeserializeOwned, Deserialize};
+use url::form_urlencoded::byte_serialize;
+
+use crate::{error::OidcError, utils::form_encode};
+
+/// The HTTP client shared by every request to the authentication provider.
+#[cfg(feature = "reqwest")]
+static CLIENT: OnceLock<reqwest::Client> = OnceLock::new();
+
+/// Options for the shared HTTP client. They only apply to native targets
+/// (e.g. the server side exchange), in the browser connections are managed
+/// by the fetch API.
+#[cfg(feature = "reqwest")]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HttpClientOptions {
+    /// How long idle connections are kept in the pool.
+    pub pool_idle_timeout: Option<Duration>,
+    /// The maximum amount of idle connections per host.
+    pub pool_max_idle_per_host: usize,
+    /// The interval of TCP keep-alive probes.
+    pub tcp_keepalive: Option<Duration>,
+    /// The interval of HTTP/2 keep-alive pings.
+    pub http2_keep_alive_interval: Option<Duration>,
+}
+
+#[cfg(feature = "reqwest")]
+impl Default for HttpClientOptions {
+    fn default() -> Self {
+        Self {
+            pool_idle_timeout: Some(Duration::from_secs(90)),
+            pool_max_idle_per_host: usize::MAX,
+            tcp_keepalive: Some(Duration::from_secs(60)),
+            http2_keep_alive_interval: Some(Duration::from_secs(30)),
+        }
+    }
+}
+
+/// Configures the shared HTTP client. This needs to be called before the
+/// first request is sent, returns `false` if the client was already
+/// initialized.
+#[cfg(feature = "reqwest")]
+pub fn configure_http_client(options: &HttpClientOptions) -> bool {
+    CLIENT.set(build_client(options)).is_ok()
+}
+
+/// Returns the shared HTTP client, initializing it with the default options
+/// if it wasn't configured before. Cloning the client is cheap, the clones
+/// share the same connection pool.
+#[cfg(feature = "reqwest")]
+#[must_use]
+pub fn http_client() -> Arc<dyn HttpClient> {
+    Arc::new(
+        CLIENT
+            .get_or_init(|| build_client(&HttpClientOptions::default()))
+            .clone(),
+    )
+}
+
+/// Returns the `GlooNetClient`, the default without the `reqwest` feature.
+#[cfg(all(not(feature = "reqwest"), feature = "gloo-net"))]
+#[must_use]
+pub fn http_client() -> Arc<dyn HttpClient> {
+    Arc::new(GlooNetClient)
+}
+
+/// Returns a client failing every request, the client has to be set by the
+/// caller, e.g. with `OidcConfig::http_client`.
+#[cfg(not(any(feature = "reqwest", feature = "gloo-net")))]
+#[must_use]
+pub fn http_client() -> Arc<dyn HttpClient> {
+    Arc::new(MissingHttpClient)
+}
+
+/// Builds a new HTTP client with the given options.
+#[cfg(all(feature = "reqwest", not(target_arch = "wasm32")))]
+fn build_client(options: &HttpClientOptions) -> reqwest::Client {
+    reqwest::Client::builder()
+        .pool_idle_timeout(options.pool_idle_timeout)
+        .pool_max_idle_per_host(options.pool_max_idle_per_host)
+        .tcp_keepalive(options.tcp_keepalive)
+        .http2_keep_alive_interval(options.http2_keep_alive_interval)
+        .http2_keep_alive_while_idle(true)
+        .build()
+        // The options can't make the builder fail without a TLS backend,
+        // which the default client wouldn't have either.
+        .unwrap_or_else(|_| reqwest::Client::new())
+}
+
+/// Builds a new HTTP client, the options don't apply in the browser.
+#[cfg(all(feature = "reqwest", target_arch = "wasm32"))]
+fn build_client(_options: &HttpClientOptions) -> reqwest::Client {
+    reqwest::Client::new()
+}
+
+/// An enumeration selecting how a confidential client sends its secret to
+/// the token endpoint.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ClientAuthMethod {
+    /// The secret is sent in the `Authorization` header
+    /// (`client_secret_basic`).
+    #[default]
+    Basic,
+    /// The secret is sent in the request body (`client_secret_post`).
+    Body,
+}
+
+/// Returns the value of the `Authorization` header authenticating the client
+/// with its secret (`client_secret_basic`). The client ID and the secret are
+/// form encoded before they are joined, as RFC 6749 section 2.3.1 requires,
+/// so a `:` in the ID doesn't shift it into the secret.
+#[must_use]
+pub fn basic_authorization(client_id: &str, client_secret: &str) -> String {
+    let client_id: String = byte_serialize(client_id.as_bytes()).collect();
+    let client_secret: String = byte_serialize(client_secret.as_bytes()).collect();
+    format!(
+        "Basic {}",
+        STANDARD.encode(format!("{client_id}:{client_secret}"))
+    )
+}
+
+/// Encodes the parameters of a request to an endpoint of the provider, like
+/// the token endpoint, as form body and adds the authentication of a
+/// confidential client. The secret is either added to the body
+/// (`client_secret_post`) or returned as `Authorization` header
+/// (`client_secret_basic`).
+#[must_use]
+pub fn client_authentication(
+    client_id: &str,
+    client_secret: Option<(&str, ClientAuthMethod)>,
+    mut params: Vec<(String, String)>,
+) -> (String, Option<String>) {
+    let mut authorization = None;
+    match client_secret {
+        Some((client_secret, ClientAuthMethod::Basic)) => {
+            authorization = Some(basic_authorization(client_id, client_secret));
+        }
+        Some((client_secret, ClientAuthMethod::Body)) => {
+            params.push(("client_secret".to_string(), client_secret.to_string()));
+        }
+        None => {}
+    }
+
+    (form_encode(params), authorization)
+}
+
+/// A structure representing an HTTP request sent by an `HttpClient`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HttpRequest {
+    /// The method, e.g. `GET` or `POST`.
+    pub method: &'static str,
+    pub url: String,
+    pub headers: Vec<(String, String)>,
+    pub body: Option<String>,
+}
+
+impl HttpRequest {
+    /// Creates a request with the given method and URL, without headers and
+    /// body.
+    #[must_use]
+    pub fn new(method: &'static str, url: impl Into<String>) -> Self {
+        Self {
+            method,
+            url: url.into(),
+            headers: Vec::new(),
+            body: None,
+        }
+    }
+
+    /// Creates a `GET` request of the given URL.
+    #[must_use]
+    pub fn get(url: impl Into<String>) -> Self {
+        Self::new("GET", url)
+    }
+
+    /// Creates a `POST` request of the given URL with the given form encoded
+    /// body.
+    #[must_use]
+    pub fn post_form(url: impl Into<String>, body: String) -> Self {
+        Self::new("POST", url)
+            .header("Content-Type", "application/x-www-form-urlencoded")
+            .body(body)
+    }
+
+    /// Adds the given header.
+    #[must_use]
+    pub fn header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.headers.push((name.into(), value.into()));
+        self
+    }
+
+    /// Sets the body.
+    #[must_use]
+    pub fn body(mut self, body: impl Into<String>) -> Self {
+        self.body = Some(body.into());
+        self
+    }
+}
+
+/// A structure representing an HTTP response received by an `HttpClient`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HttpResponse {
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+    pub body: String,
+}
+
+impl HttpResponse {
+    /// Returns the value of the given header, the name is case-insensitive.
+    #[must_use]
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(header, _)| header.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Checks if the status is successful (`2xx`).
+    #[must_use]
+    pub fn is_success(&self) -> bool {
+        (200..300).contains(&self.status)
+    }
+
+    /// Returns the response, unless the status is a client or server error.
+    ///
+    /// # Errors
+    ///
+    /// Returns `OidcError::Http` with the status of an error response.
+    pub fn error_for_status(self) -> Result<Self, OidcError> {
+        if self.status >= 400 {
+            return Err(OidcError::Http(self.status));
+        }
+
+        Ok(self)
+    }
+
+    /// Parses the body as JSON.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the body isn't valid JSON of the given type.
+    pub fn json<T: DeserializeOwned>(&self) -> Result<T, OidcError> {
+        Ok(serde_json::from_str(&self.body).map_err(Arc::new)?)
+    }
+}
+
+/// A trait for the HTTP client sending every request to the provider: the
+/// token requests, the discovery, the key set and the userinfo. It's
+/// implemented for `reqwest::Client` with the `reqwest` feature, which is
+/// used by default, and for `GlooNetClient` with the `gloo-net` feature,
+/// which uses the fetch API directly and keeps the WASM bundle smaller.
+pub trait HttpClient: Debug {
+    /// Sends the request and returns the response, whatever its status.
+    /// Only a request, which didn't get a response, fails with
+    /// `OidcError::Network`.
+    fn send(&self, request: HttpRequest) -> LocalBoxFuture<'_, Result<HttpResponse, OidcError>>;
+}
+
+#[cfg(feature = "reqwest")]
+impl HttpClient for reqwest::Client {
+    fn send(&self, request: HttpRequest) -> LocalBoxFuture<'_, Result<HttpResponse, OidcError>> {
+        async move {
+            let network_error = |error: reqwest::Error| OidcError::Network(error.to_string());
+            let method = reqwest::Method::from_bytes(request.method.as_bytes())
+                .map_err(|error| OidcError::Network(error.to_string()))?;
+            let mut builder = self.request(method, &request.url);
+            for (name, value) in &request.headers {
+                builder = builder.header(name.as_str(), value.as_str());
+            }
+            if let Some(body) = request.body {
+                builder = builder.body(body);
+            }
+            let response = builder.send().await.map_err(network_error)?;
+
+            let status = response.status().as_u16();
+            let headers = response
+                .headers()
+                .iter()
+                .filter_map(|(name, value)| {
+                    Some((name.as_str().to_string(), value.to_str().ok()?.to_string()))
+                })
+                .collect();
+            let body = response.text().await.map_err(network_error)?;
+
+            Ok(HttpResponse {
+                status,
+                headers,
+                body,
+            })
+        }
+        .boxed_local()
+    }
+}
+
+/// An `HttpClient` using the fetch API of the browser via `gloo-net`.
+#[cfg(feature = "gloo-net")]
+#[derive(Debug, Default, Clone, Copy)]
+pub struct GlooNetClient;
+
+#[cfg(feature = "gloo-net")]
+impl HttpClient for GlooNetClient {
+    fn send(&self, request: HttpRequest) -> LocalBoxFuture<'_, Result<HttpResponse, OidcError>> {
+        async move {
+            use gloo_net::http::Request;
+
+            let network_error = |error: gloo_net::Error| OidcError::Network(error.to_string());
+            let mut builder = match request.method {
+                "GET" => Request::get(&request.url),
+                "POST" => Request::post(&request.url),
+                "PUT" => Request::put(&request.url),
+                "PATCH" => Request::patch(&request.url),
+                "DELETE" => Request::delete(&request.url),
+                method => return Err(OidcError::Network(format!("unsupported method {method}"))),
+            };
+            for (name, value) in &request.headers {
+                builder = builder.header(name, value);
+            }
+            let response = match request.body {
+                Some(body) => builder.body(body).map_err(network_error)?.send().await,
+                None => builder.send().await,
+            }
+            .map_err(network_error)?;
+
+            Ok(HttpResponse {
+                status: response.status(),
+                headers: response.headers().entries().collect(),
+                body: response.text().await.map_err(network_error)?,
+            })
+        }
+        .boxed_local()
+    }
+}
+
+/// The default `HttpClient` without the `reqwest` and the `gloo-net`
+/// feature, which fails every request.
+#[cfg(not(any(feature = "reqwest", feature = "gloo-net")))]
+#[derive(Debug)]
+struct MissingHttpClient;
+
+#[cfg(not(any(feature = "reqwest", feature = "gloo-net")))]
+impl HttpClient for MissingHttpClient {
+    fn send(&self, _request: HttpRequest) -> LocalBoxFuture<'_, Result<HttpResponse, OidcError>> {
+        async { Err(OidcError::MissingParameter("http_client")) }.boxed_local()
+    }
+}
@@ -0,0 +1,76 @@
+/*
+* The MIT License (MIT)
+*
+* Copyright (c) 2023 Daniél Kerkmann <daniel@kerkmann.dev>
+*
+* Permission is hereby granted, free of charge, to any person obtaining a copy
+* of this software and associated documentation files (the "Software"), to deal
+* in the Software without restriction, including without limitation the rights
+* to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+* copies of the Software, and to permit persons to whom the Software is
+* furnished to do so, subject to the following conditions:
+*
+* The above copyright notice and this permission notice shall be included in all
+* copies or substantial portions of the Software.
+*
+* THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+* IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+* FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+* AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+* LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+* OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+* SOFTWARE.
+*/
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    client::{http_client, HttpClient, HttpRequest},
+    error::OidcError,
+};
+
+/// The path of the discovery document, relative to the issuer.
+const DISCOVERY_PATH: &str = "/.well-known/openid-configuration";
+
+/// A structure representing the OpenID Connect provider metadata, as served
+/// by the discovery endpoint. Only the fields used by this crate are
+/// included.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct ProviderMetadata {
+    pub issuer: String,
+    pub authorization_endpoint: String,
+    pub token_endpoint: String,
+    pub end_session_endpoint: Option<String>,
+    pub jwks_uri: Option<String>,
+    pub revocation_endpoint: Option<String>,
+    pub check_session_iframe: Option<String>,
+    pub introspection_endpoint: Option<String>,
+    pub userinfo_endpoint: Option<String>,
+    pub pushed_authorization_request_endpoint: Option<String>,
+    #[serde(default)]
+    pub frontchannel_logout_session_supported: Option<bool>,
+    pub scopes_supported: Option<Vec<String>>,
+    pub code_challenge_methods_supported: Option<Vec<String>>,
+}
+
+impl ProviderMetadata {
+    /// Fetches the discovery document of the given issuer.
+    pub async fn fetch(issuer_url: &str) -> Result<Self, OidcError> {
+        Self::fetch_with_client(http_client().as_ref(), issuer_url).await
+    }
+
+    /// Fetches the discovery document of the given issuer with the given
+    /// HTTP client.
+    pub async fn fetch_with_client(
+        client: &dyn HttpClient,
+        issuer_url: &str,
+    ) -> Result<Self, OidcError> {
+        let url = format!("{}{DISCOVERY_PATH}", issuer_url.trim_end_matches('/'));
+
+        client
+            .send(HttpRequest::get(url))
+            .await?
+            .error_for_status()?
+            .json()
+    }
+}
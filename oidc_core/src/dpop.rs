@@ -0,0 +1,180 @@
+/*
+* The MIT License (MIT)
+*
+* Copyright (c) 2023 Daniél Kerkmann <daniel@kerkmann.dev>
+*
+* Permission is hereby granted, free of charge, to any person obtaining a copy
+* of this software and associated documentation files (the "Software"), to deal
+* in the Software without restriction, including without limitation the rights
+* to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+* copies of the Software, and to permit persons to whom the Software is
+* furnished to do so, subject to the following conditions:
+*
+* The above copyright notice and this permission notice shall be included in all
+* copies or substantial portions of the Software.
+*
+* THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+* IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+* FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+* AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+* LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+* OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+* SOFTWARE.
+*/
+
+use std::{cell::RefCell, collections::HashMap, fmt::Debug};
+
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use futures::future::LocalBoxFuture;
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use url::Url;
+
+use crate::{
+    client::{HttpClient, HttpRequest, HttpResponse},
+    error::OidcError,
+    utils::{now, random_string},
+};
+
+/// The amount of random bytes of the `jti` claim of a DPoP proof.
+const JTI_BYTES: usize = 16;
+
+/// The error of a provider or resource server requiring a nonce in the
+/// proof.
+const USE_DPOP_NONCE: &str = "use_dpop_nonce";
+
+thread_local! {
+    /// The last nonce of every server by its origin, sent with the following
+    /// proofs.
+    static NONCES: RefCell<HashMap<String, String>> = RefCell::default();
+}
+
+/// A trait for the signer of the DPoP proofs (RFC 9449) sent with the token
+/// requests and the requests to resource servers. The key pair is kept by
+/// the implementation, e.g. in WebCrypto or the keychain of the OS. The
+/// claims of a proof are built by `proof_claims`.
+pub trait DpopProver: Debug {
+    /// Creates a proof for the request with the given method and URL. With
+    /// an access token, its hash is included as `ath` claim, a nonce of the
+    /// server as `nonce` claim.
+    fn proof<'a>(
+        &'a self,
+        method: &'a str,
+        url: &'a str,
+        access_token: Option<&'a str>,
+        nonce: Option<&'a str>,
+    ) -> LocalBoxFuture<'a, Result<String, OidcError>>;
+}
+
+/// Returns the claims of a DPoP proof of the request with the given method
+/// and URL: a unique `jti`, the method as `htm`, the URL without query and
+/// fragment as `htu` and the `iat`. With an access token, its hash is added
+/// as `ath`, with a nonce of the server as `nonce`.
+///
+/// # Errors
+///
+/// Returns `OidcError::Random` if no `jti` could be generated.
+pub fn proof_claims(
+    method: &str,
+    url: &str,
+    access_token: Option<&str>,
+    nonce: Option<&str>,
+) -> Result<Value, OidcError> {
+    let mut claims = json!({
+        "jti": random_string(JTI_BYTES)?,
+        "htm": method.to_uppercase(),
+        "htu": target_uri(url),
+        "iat": now().and_utc().timestamp(),
+    });
+    if let Some(access_token) = access_token {
+        claims["ath"] = URL_SAFE_NO_PAD
+            .encode(Sha256::digest(access_token.as_bytes()))
+            .into();
+    }
+    if let Some(nonce) = nonce {
+        claims["nonce"] = nonce.into();
+    }
+
+    Ok(claims)
+}
+
+/// Returns the last nonce of the server of the URL, see `send_with_dpop`.
+#[must_use]
+pub fn dpop_nonce(url: &str) -> Option<String> {
+    NONCES.with(|nonces| nonces.borrow().get(&origin(url)).cloned())
+}
+
+/// Sends the request with a proof of the prover. A server requiring a nonce
+/// (RFC 9449, section 8 and 9) rejects the proof with `use_dpop_nonce` and
+/// returns the nonce in the `DPoP-Nonce` header, the request is then sent
+/// once more with the nonce. The last nonce of every server is sent with the
+/// following proofs right away.
+///
+/// # Errors
+///
+/// Returns the error of the prover or a failed request.
+pub async fn send_with_dpop(
+    client: &dyn HttpClient,
+    prover: &dyn DpopProver,
+    request: HttpRequest,
+    access_token: Option<&str>,
+) -> Result<HttpResponse, OidcError> {
+    let origin = origin(&request.url);
+    let mut nonce = NONCES.with(|nonces| nonces.borrow().get(&origin).cloned());
+    let mut retried = false;
+    loop {
+        let proof = prover
+            .proof(request.method, &request.url, access_token, nonce.as_deref())
+            .await?;
+        let response = client.send(request.clone().header("DPoP", proof)).await?;
+        let Some(next_nonce) = response.header("DPoP-Nonce").map(str::to_string) else {
+            return Ok(response);
+        };
+        NONCES.with(|nonces| {
+            nonces
+                .borrow_mut()
+                .insert(origin.clone(), next_nonce.clone())
+        });
+
+        if retried || !requires_nonce(&response) {
+            return Ok(response);
+        }
+        nonce = Some(next_nonce);
+        retried = true;
+    }
+}
+
+/// Checks if the response rejects the proof for a missing or stale nonce:
+/// with the error response of the provider, or the `WWW-Authenticate` header
+/// of a resource server.
+fn requires_nonce(response: &HttpResponse) -> bool {
+    match response.status {
+        400 => response
+            .json::<Value>()
+            .is_ok_and(|body| body["error"] == USE_DPOP_NONCE),
+        401 => response
+            .header("WWW-Authenticate")
+            .is_some_and(|challenge| challenge.contains(USE_DPOP_NONCE)),
+        _ => false,
+    }
+}
+
+/// Returns the URL without query and fragment, the `htu` claim of a proof.
+fn target_uri(url: &str) -> String {
+    match Url::parse(url) {
+        Ok(mut url) => {
+            url.set_query(None);
+            url.set_fragment(None);
+            url.to_string()
+        }
+        Err(_) => url.to_string(),
+    }
+}
+
+/// Returns the origin of the URL, the nonces are issued per server.
+fn origin(url: &str) -> String {
+    Url::parse(url).map_or_else(
+        |_| url.to_string(),
+        |url| url.origin().ascii_serialization(),
+    )
+}
@@ -0,0 +1,169 @@
+/*
+* The MIT License (MIT)
+*
+* Copyright (c) 2023 Daniél Kerkmann <daniel@kerkmann.dev>
+*
+* Permission is hereby granted, free of charge, to any person obtaining a copy
+* of this software and associated documentation files (the "Software"), to deal
+* in the Software without restriction, including without limitation the rights
+* to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+* copies of the Software, and to permit persons to whom the Software is
+* furnished to do so, subject to the following conditions:
+*
+* The above copyright notice and this permission notice shall be included in all
+* copies or substantial portions of the Software.
+*
+* THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+* IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+* FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+* AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+* LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+* OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+* SOFTWARE.
+*/
+
+use std::sync::Arc;
+
+use thiserror::Error;
+
+use crate::response::ErrorResponse;
+
+/// An enumeration representing the errors of the protocol logic, e.g. of a
+/// token request or the validation of a token.
+#[derive(Debug, Clone, Error)]
+pub enum OidcError {
+    /// An error caused by the authentication provider, which has no dedicated
+    /// variant.
+    #[error("provider error {0:?}")]
+    Provider(ErrorResponse),
+
+    /// The provider rejected the authorization code or the refresh token
+    /// (`invalid_grant`), e.g. because it expired or was revoked.
+    #[error("invalid grant {0:?}")]
+    InvalidGrant(ErrorResponse),
+
+    /// The provider requires the user to log in (`login_required`).
+    #[error("login required {0:?}")]
+    LoginRequired(ErrorResponse),
+
+    /// The provider requires the consent of the user (`consent_required`).
+    #[error("consent required {0:?}")]
+    ConsentRequired(ErrorResponse),
+
+    /// An error indicating that a request of the `HttpClient` didn't get a
+    /// response, e.g. offline, after a timeout or blocked by CORS.
+    #[error("network error: {0}")]
+    Network(String),
+
+    /// An error indicating that the server answered with the given error
+    /// status.
+    #[error("request failed with status {0}")]
+    Http(u16),
+
+    /// An error indicating that the callback parameters are missing or
+    /// invalid.
+    #[error("invalid callback: {0}")]
+    InvalidCallback(String),
+
+    /// An error related to the serialization or deserialization of JSON data.
+    #[error("failed to serialize/deserialilze json: {0}")]
+    Serde(#[from] Arc<serde_json::Error>),
+
+    /// An error indicating that a required parameter, like the `jwks_uri`,
+    /// isn't configured.
+    #[error("missing required parameter '{0}'")]
+    MissingParameter(&'static str),
+
+    /// An error indicating that the provider metadata is missing a required
+    /// field.
+    #[error("provider metadata is missing '{0}'")]
+    Discovery(&'static str),
+
+    /// An error related to decoding or validating a JWT.
+    #[error("token error: {0}")]
+    Token(#[from] Arc<jsonwebtoken::errors::Error>),
+
+    /// An error indicating that the signature of a JWT is invalid.
+    #[error("invalid token signature")]
+    SignatureInvalid,
+
+    /// An error indicating that a token is expired.
+    #[error("token expired")]
+    TokenExpired,
+
+    /// An error indicating that the JSON Web Key Set doesn't contain the key
+    /// the token was signed with.
+    #[error("no key found for kid {0:?}")]
+    UnknownKey(Option<String>),
+
+    /// An error indicating that a token is not a well-formed JWT.
+    #[error("malformed token")]
+    MalformedToken,
+
+    /// An error indicating that the header of a JWT declares an algorithm,
+    /// which isn't supported, so its signature can't be verified.
+    #[error("unsupported token algorithm '{0}'")]
+    UnsupportedAlgorithm(String),
+
+    /// An error indicating that the `nonce` claim of the ID token doesn't
+    /// match the nonce sent with the authentication request.
+    #[error("nonce mismatch")]
+    Nonce,
+
+    /// An error indicating that a hash claim of the ID token (`at_hash` or
+    /// `c_hash`) doesn't match the access token or the code returned with it
+    /// by the implicit or hybrid flow.
+    #[error("{0} mismatch")]
+    TokenHash(&'static str),
+
+    /// An error indicating the inability to generate random data, e.g. for
+    /// the PKCE code verifier.
+    #[error("unable to generate random data")]
+    Random,
+
+    /// An error indicating that the token response of a login is missing the
+    /// ID token, e.g. because the `openid` scope wasn't requested.
+    #[error("token response is missing the id_token")]
+    MissingIdToken,
+
+    /// An error of creating a DPoP proof.
+    #[error("DPoP failed: {0}")]
+    Dpop(String),
+}
+
+impl OidcError {
+    /// Converts an error response of the provider, well-known OAuth error
+    /// codes are mapped to dedicated variants.
+    #[must_use]
+    pub fn provider(response: ErrorResponse) -> Self {
+        match response.error.as_str() {
+            "invalid_grant" => OidcError::InvalidGrant(response),
+            "login_required" => OidcError::LoginRequired(response),
+            "consent_required" => OidcError::ConsentRequired(response),
+            _ => OidcError::Provider(response),
+        }
+    }
+
+    /// Converts an error of decoding or validating a JWT, an invalid signature
+    /// and an expired token are mapped to dedicated variants.
+    #[must_use]
+    pub fn token(error: jsonwebtoken::errors::Error) -> Self {
+        match error.kind() {
+            jsonwebtoken::errors::ErrorKind::InvalidSignature => OidcError::SignatureInvalid,
+            jsonwebtoken::errors::ErrorKind::ExpiredSignature => OidcError::TokenExpired,
+            _ => OidcError::Token(Arc::new(error)),
+        }
+    }
+
+    /// Returns `true` if the error is likely transient, like a network error,
+    /// a timeout or a server error of the provider, and worth a retry.
+    #[must_use]
+    pub fn is_transient(&self) -> bool {
+        match self {
+            // The request never got a response of the provider.
+            OidcError::Network(_) => true,
+            OidcError::Http(status) => *status >= 500,
+            _ => false,
+        }
+    }
+}
@@ -0,0 +1,116 @@
+/*
+* The MIT License (MIT)
+*
+* Copyright (c) 2023 Daniél Kerkmann <daniel@kerkmann.dev>
+*
+* Permission is hereby granted, free of charge, to any person obtaining a copy
+* of this software and associated documentation files (the "Software"), to deal
+* in the Software without restriction, including without limitation the rights
+* to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+* copies of the Software, and to permit persons to whom the Software is
+* furnished to do so, subject to the following conditions:
+*
+* The above copyright notice and this permission notice shall be included in all
+* copies or substantial portions of the Software.
+*
+* THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+* IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+* FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+* AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+* LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+* OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+* SOFTWARE.
+*/
+
+use std::sync::Arc;
+
+use chrono::{Duration, NaiveDateTime};
+
+use crate::{
+    error::OidcError,
+    oidc::OidcClient,
+    response::TokenExchangeResponse,
+    scopes::Scopes,
+    token::access_token_expiry,
+    utils::now,
+};
+
+/// The grant type of a token exchange (RFC 8693).
+pub const TOKEN_EXCHANGE_GRANT_TYPE: &str = "urn:ietf:params:oauth:grant-type:token-exchange";
+
+/// The token type of an access token in a token exchange.
+pub const ACCESS_TOKEN_TYPE: &str = "urn:ietf:params:oauth:token-type:access_token";
+
+/// An enumeration of the targets of a token exchange, the downstream service
+/// the exchanged token is meant for.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum TokenTarget {
+    /// The logical name of the service (`audience`), e.g. a client ID.
+    Audience(String),
+    /// The URI of the service (`resource`, RFC 8707).
+    Resource(String),
+}
+
+/// A structure representing a token issued by a token exchange.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExchangedToken {
+    /// The issued token, returned as `access_token` by the provider.
+    pub access_token: String,
+    /// The type of the issued token, e.g.
+    /// `urn:ietf:params:oauth:token-type:access_token`.
+    pub issued_token_type: String,
+    /// The expiry of the token, from `expires_in` or the `exp` claim.
+    pub expires_at: Option<NaiveDateTime>,
+}
+
+impl OidcClient {
+    /// Exchanges the subject token, usually the access token of the user, for
+    /// a token of the given downstream service (token exchange, RFC 8693),
+    /// with the given scopes, if any. The client authenticates like at the
+    /// token endpoint.
+    ///
+    /// # Errors
+    ///
+    /// Returns the provider error of the token endpoint, e.g. if the token
+    /// exchange isn't enabled for the client, or a failed request.
+    pub async fn exchange_token(
+        &self,
+        target: &TokenTarget,
+        subject_token: &str,
+        scope: &Scopes,
+    ) -> Result<ExchangedToken, OidcError> {
+        let config = self.config();
+        let (target_param, target_value) = match target {
+            TokenTarget::Audience(audience) => ("audience", audience),
+            TokenTarget::Resource(resource) => ("resource", resource),
+        };
+        let mut params = vec![
+            ("grant_type", TOKEN_EXCHANGE_GRANT_TYPE.to_string()),
+            ("client_id", config.client_id.clone()),
+            ("subject_token", subject_token.to_string()),
+            ("subject_token_type", ACCESS_TOKEN_TYPE.to_string()),
+            ("requested_token_type", ACCESS_TOKEN_TYPE.to_string()),
+            (target_param, target_value.clone()),
+        ];
+        if !scope.is_empty() {
+            params.push(("scope", scope.to_string()));
+        }
+        let params = params
+            .into_iter()
+            .map(|(key, value)| (key.to_string(), value))
+            .collect();
+        let response = self.post_form(&config.token_endpoint, params).await?;
+
+        match serde_json::from_str::<TokenExchangeResponse>(&response).map_err(Arc::new)? {
+            TokenExchangeResponse::Success(success) => Ok(ExchangedToken {
+                expires_at: match success.expires_in {
+                    Some(expires_in) => Some(now() + Duration::seconds(expires_in)),
+                    None => access_token_expiry(&success.access_token),
+                },
+                access_token: success.access_token,
+                issued_token_type: success.issued_token_type,
+            }),
+            TokenExchangeResponse::Error(error) => Err(OidcError::provider(error)),
+        }
+    }
+}
@@ -0,0 +1,68 @@
+/*
+* The MIT License (MIT)
+*
+* Copyright (c) 2023 Daniél Kerkmann <daniel@kerkmann.dev>
+*
+* Permission is hereby granted, free of charge, to any person obtaining a copy
+* of this software and associated documentation files (the "Software"), to deal
+* in the Software without restriction, including without limitation the rights
+* to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+* copies of the Software, and to permit persons to whom the Software is
+* furnished to do so, subject to the following conditions:
+*
+* The above copyright notice and this permission notice shall be included in all
+* copies or substantial portions of the Software.
+*
+* THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+* IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+* FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+* AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+* LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+* OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+* SOFTWARE.
+*/
+
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use jsonwebtoken::Algorithm;
+use serde_json::Value;
+use sha2::{Digest, Sha256, Sha384, Sha512};
+
+use crate::{
+    claims::{decode_claims_unverified, token_algorithm},
+    error::OidcError,
+};
+
+/// Checks a hash claim of the ID token (`at_hash` or `c_hash`) against the
+/// value returned alongside it: the left half of the hash of the value,
+/// using the hash function of the signing algorithm, encoded as base64url.
+/// A missing claim is only rejected if it's `required`.
+pub(crate) fn verify_token_hash(
+    id_token: &str,
+    claim: &'static str,
+    value: &str,
+    required: bool,
+) -> Result<(), OidcError> {
+    let claims = decode_claims_unverified::<Value>(id_token)?;
+    match claims.get(claim).and_then(Value::as_str) {
+        Some(hash) if hash == token_hash(token_algorithm(id_token)?, value) => Ok(()),
+        None if !required => Ok(()),
+        _ => Err(OidcError::TokenHash(claim)),
+    }
+}
+
+/// Returns the base64url encoded left half of the hash of the value.
+fn token_hash(algorithm: Algorithm, value: &str) -> String {
+    let digest = match algorithm {
+        Algorithm::HS256 | Algorithm::RS256 | Algorithm::PS256 | Algorithm::ES256 => {
+            Sha256::digest(value.as_bytes()).to_vec()
+        }
+        Algorithm::HS384 | Algorithm::RS384 | Algorithm::PS384 | Algorithm::ES384 => {
+            Sha384::digest(value.as_bytes()).to_vec()
+        }
+        Algorithm::HS512 | Algorithm::RS512 | Algorithm::PS512 | Algorithm::EdDSA => {
+            Sha512::digest(value.as_bytes()).to_vec()
+        }
+    };
+
+    URL_SAFE_NO_PAD.encode(&digest[..digest.len() / 2])
+}
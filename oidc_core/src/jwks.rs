@@ -0,0 +1,112 @@
+/*
+* The MIT License (MIT)
+*
+* Copyright (c) 2023 Daniél Kerkmann <daniel@kerkmann.dev>
+*
+* Permission is hereby granted, free of charge, to any person obtaining a copy
+* of this software and associated documentation files (the "Software"), to deal
+* in the Software without restriction, including without limitation the rights
+* to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+* copies of the Software, and to permit persons to whom the Software is
+* furnished to do so, subject to the following conditions:
+*
+* The above copyright notice and this permission notice shall be included in all
+* copies or substantial portions of the Software.
+*
+* THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+* IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+* FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+* AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+* LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+* OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+* SOFTWARE.
+*/
+
+use std::{collections::HashMap, sync::Arc};
+
+use jsonwebtoken::{
+    decode, decode_header,
+    jwk::{Jwk, JwkSet},
+    DecodingKey, TokenData, Validation,
+};
+use serde::de::DeserializeOwned;
+
+use crate::{
+    claims::token_algorithm,
+    client::{http_client, HttpClient, HttpRequest},
+    error::OidcError,
+};
+
+/// A structure representing the JSON Web Key Set of the provider. The keys
+/// are cached by their key ID (`kid`).
+#[derive(Debug, Clone, Default)]
+pub struct Jwks {
+    keys: HashMap<String, Jwk>,
+}
+
+impl Jwks {
+    /// Downloads the JSON Web Key Set from the given `jwks_uri`.
+    pub async fn fetch(jwks_uri: &str) -> Result<Self, OidcError> {
+        Self::fetch_with_client(http_client().as_ref(), jwks_uri).await
+    }
+
+    /// Downloads the JSON Web Key Set from the given `jwks_uri` with the
+    /// given HTTP client.
+    pub async fn fetch_with_client(
+        client: &dyn HttpClient,
+        jwks_uri: &str,
+    ) -> Result<Self, OidcError> {
+        let set = client
+            .send(HttpRequest::get(jwks_uri))
+            .await?
+            .error_for_status()?
+            .json::<JwkSet>()?;
+
+        Ok(set.into())
+    }
+
+    /// Returns the key with the given key ID. If the token doesn't name a key
+    /// ID, the only key of the set is returned, if there is exactly one.
+    #[must_use]
+    pub fn find(&self, kid: Option<&str>) -> Option<&Jwk> {
+        match kid {
+            Some(kid) => self.keys.get(kid),
+            None if self.keys.len() == 1 => self.keys.values().next(),
+            None => None,
+        }
+    }
+
+    /// Decodes the token and validates its signature with the matching key.
+    /// The algorithm of the token header is used, the configured algorithms
+    /// of the validation are overwritten.
+    pub fn decode<T: DeserializeOwned>(
+        &self,
+        token: &str,
+        validation: &Validation,
+    ) -> Result<TokenData<T>, OidcError> {
+        let algorithm = token_algorithm(token)?;
+        let header = decode_header(token).map_err(Arc::new)?;
+        let jwk = self
+            .find(header.kid.as_deref())
+            .ok_or_else(|| OidcError::UnknownKey(header.kid.clone()))?;
+        let key = DecodingKey::from_jwk(jwk).map_err(Arc::new)?;
+
+        let mut validation = validation.clone();
+        validation.algorithms = vec![algorithm];
+
+        decode::<T>(token, &key, &validation).map_err(OidcError::token)
+    }
+}
+
+/// Converts a `JwkSet` into a `Jwks`, keys without a key ID are skipped.
+impl From<JwkSet> for Jwks {
+    fn from(set: JwkSet) -> Self {
+        let keys = set
+            .keys
+            .into_iter()
+            .filter_map(|jwk| Some((jwk.common.key_id.clone()?, jwk)))
+            .collect();
+
+        Self { keys }
+    }
+}
@@ -0,0 +1,114 @@
+/*
+* The MIT License (MIT)
+*
+* Copyright (c) 2023 Daniél Kerkmann <daniel@kerkmann.dev>
+*
+* Permission is hereby granted, free of charge, to any person obtaining a copy
+* of this software and associated documentation files (the "Software"), to deal
+* in the Software without restriction, including without limitation the rights
+* to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+* copies of the Software, and to permit persons to whom the Software is
+* furnished to do so, subject to the following conditions:
+*
+* The above copyright notice and this permission notice shall be included in all
+* copies or substantial portions of the Software.
+*
+* THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+* IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+* FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+* AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+* LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+* OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+* SOFTWARE.
+*/
+
+//! The protocol logic of OpenID Connect without any framework, reactive
+//! state or browser API: the HTTP client abstraction, the discovery, the key
+//! set, PKCE, the callback and token responses, the token model and
+//! `OidcClient`, which builds the authorization and logout URLs and sends the
+//! token requests.
+//! `leptos_oidc2` wraps it with signals and the browser storage, a Tauri or
+//! CLI app can use it directly.
+
+#![allow(clippy::module_name_repetitions)]
+
+use serde::Deserialize;
+
+pub mod claims;
+pub mod client;
+pub mod discovery;
+pub mod dpop;
+pub mod error;
+pub mod exchange;
+mod implicit;
+pub mod jwks;
+pub mod oidc;
+pub mod pkce;
+pub mod profile;
+pub mod response;
+pub mod scopes;
+pub mod token;
+pub mod utils;
+
+pub use dpop::DpopProver;
+pub use error::OidcError;
+pub use exchange::{ExchangedToken, TokenTarget};
+pub use oidc::{AuthorizationRequest, LogoutBehavior, OidcClient, OidcConfig};
+pub use profile::ProviderProfile;
+pub use scopes::Scopes;
+pub use token::TokenStorage;
+
+/// An enumeration selecting how the provider returns the callback
+/// parameters, see the `response_mode` of the authorization request.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ResponseMode {
+    /// The parameters are returned in the query string.
+    #[default]
+    Query,
+    /// The parameters are returned in the URL fragment.
+    Fragment,
+}
+
+/// An enumeration selecting the `response_type` of the authorization
+/// request. Besides the authorization code flow, the implicit and hybrid
+/// flows are supported, e.g. for providers without a CORS-enabled token
+/// endpoint. The `id_token` response type isn't supported, because it
+/// issues no access token.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+pub enum ResponseType {
+    /// The authorization code flow, the code is exchanged at the token
+    /// endpoint.
+    #[default]
+    #[serde(rename = "code")]
+    Code,
+    /// The implicit flow, the tokens are returned in the callback.
+    #[serde(rename = "id_token token")]
+    IdTokenToken,
+    /// The hybrid flow returning the code and the ID token, the code is
+    /// exchanged at the token endpoint.
+    #[serde(rename = "code id_token")]
+    CodeIdToken,
+    /// The hybrid flow returning the code and the access token, the ID token
+    /// is taken from the token endpoint.
+    #[serde(rename = "code token")]
+    CodeToken,
+    /// The hybrid flow returning the code and the tokens, the code isn't
+    /// exchanged.
+    #[serde(rename = "code id_token token")]
+    CodeIdTokenToken,
+}
+
+impl ResponseType {
+    /// Returns the value of the `response_type` parameter.
+    #[must_use]
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ResponseType::Code => "code",
+            ResponseType::IdTokenToken => "id_token token",
+            ResponseType::CodeIdToken => "code id_token",
+            ResponseType::CodeToken => "code token",
+            ResponseType::CodeIdTokenToken => "code id_token token",
+        }
+    }
+}
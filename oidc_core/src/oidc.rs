@@ -0,0 +1,574 @@
+/*
+* The MIT License (MIT)
+*
+* Copyright (c) 2023 Daniél Kerkmann <daniel@kerkmann.dev>
+*
+* Permission is hereby granted, free of charge, to any person obtaining a copy
+* of this software and associated documentation files (the "Software"), to deal
+* in the Software without restriction, including without limitation the rights
+* to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+* copies of the Software, and to permit persons to whom the Software is
+* furnished to do so, subject to the following conditions:
+*
+* The above copyright notice and this permission notice shall be included in all
+* copies or substantial portions of the Software.
+*
+* THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+* IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+* FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+* AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+* LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+* OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+* SOFTWARE.
+*/
+
+use std::{sync::Arc, time::Duration};
+
+use jsonwebtoken::{Algorithm, Validation};
+use serde::Deserialize;
+use serde_json::Value;
+
+use crate::{
+    claims::decode_claims_unverified,
+    client::{client_authentication, http_client, ClientAuthMethod, HttpClient, HttpRequest},
+    discovery::ProviderMetadata,
+    dpop::{send_with_dpop, DpopProver},
+    error::OidcError,
+    implicit::verify_token_hash,
+    jwks::Jwks,
+    pkce::{code_challenge, generate_code_verifier, CODE_CHALLENGE_METHOD},
+    profile::ProviderProfile,
+    response::{
+        ImplicitCallbackResponse, PushedAuthorizationResponse, SuccessTokenResponse, TokenResponse,
+    },
+    scopes::Scopes,
+    token::TokenStorage,
+    utils::{push_query, random_string},
+    ResponseMode, ResponseType,
+};
+
+/// The amount of random bytes used for the nonce.
+pub const NONCE_BYTES: usize = 32;
+
+/// An enumeration selecting what a logout ends, see `OidcClient::logout_url`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LogoutBehavior {
+    /// Ends the session at the provider, the local tokens are removed when
+    /// the provider redirects back.
+    #[default]
+    Provider,
+    /// Only removes the local tokens, the session at the provider (e.g. a
+    /// shared SSO session) is kept.
+    LocalOnly,
+    /// Removes the local tokens right away and ends the session at the
+    /// provider afterwards.
+    Both,
+}
+
+/// The configuration of an `OidcClient`: the endpoints of the provider, the
+/// client and the parameters of the authorization request.
+#[derive(Debug, Default, Clone)]
+pub struct OidcConfig {
+    pub issuer: Option<String>,
+    pub auth_endpoint: String,
+    pub token_endpoint: String,
+    pub logout_endpoint: String,
+    pub jwks_uri: Option<String>,
+    /// The pushed authorization request endpoint (RFC 9126), see
+    /// `OidcClient::push_authorization_request`.
+    pub pushed_authorization_request_endpoint: Option<String>,
+    /// The issuer the ID tokens have to be issued by (`iss`). Defaults to
+    /// the `issuer`.
+    pub expected_issuer: Option<String>,
+    /// The tolerated clock skew between the client and the provider, applied
+    /// to the token validation.
+    pub leeway: Duration,
+    pub client_id: String,
+    /// The secret of a confidential client, sent with the token requests.
+    pub client_secret: Option<String>,
+    /// How the `client_secret` is sent, defaults to the `Authorization`
+    /// header.
+    pub client_auth_method: ClientAuthMethod,
+    pub redirect_uri: String,
+    pub post_logout_redirect_uri: String,
+    /// The API the access token is requested for, required by e.g. Auth0 to
+    /// issue a JWT access token.
+    pub audience: Option<String>,
+    pub prompt: Option<String>,
+    pub max_age: Option<u64>,
+    pub login_hint: Option<String>,
+    pub ui_locales: Option<String>,
+    /// Additional parameters, which are appended to the login URL as is.
+    pub extra_params: Vec<(String, String)>,
+    /// Sends the logout to the end session endpoint without ID token, for
+    /// providers which accept a logout without `id_token_hint`.
+    pub logout_without_id_token_hint: bool,
+    pub logout_behavior: LogoutBehavior,
+    /// Adapts the requests to the quirks of the provider.
+    pub profile: ProviderProfile,
+    pub response_mode: ResponseMode,
+    pub response_type: ResponseType,
+    /// The HTTP client of every request to the provider. Defaults to the
+    /// shared client, see `configure_http_client`.
+    pub http_client: Option<Arc<dyn HttpClient>>,
+    /// Binds the tokens to a key with DPoP (RFC 9449), the token requests
+    /// send a proof of the prover.
+    pub dpop: Option<Arc<dyn DpopProver>>,
+}
+
+impl OidcConfig {
+    /// Returns the injected HTTP client or the shared one.
+    #[must_use]
+    pub fn client(&self) -> Arc<dyn HttpClient> {
+        self.http_client.clone().unwrap_or_else(http_client)
+    }
+
+    /// Encodes the parameters of a token request as form body with the
+    /// client authentication of a confidential client, see
+    /// `client_authentication`.
+    fn authenticate_client(&self, params: Vec<(String, String)>) -> (String, Option<String>) {
+        client_authentication(
+            &self.client_id,
+            self.client_secret
+                .as_deref()
+                .map(|client_secret| (client_secret, self.client_auth_method)),
+            params,
+        )
+    }
+}
+
+/// The protocol logic of the authorization code flow without any reactive
+/// state, storage or browser API, e.g. to share the token handling with a
+/// Tauri or CLI app. The caller keeps the values of a pending login, like
+/// the code verifier, and the tokens.
+#[derive(Debug, Clone)]
+pub struct OidcClient {
+    config: OidcConfig,
+}
+
+/// A prepared authorization request, see `OidcClient::authorization_request`.
+/// The code verifier and the nonce have to be kept until the callback.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AuthorizationRequest {
+    /// The URL to send the user to.
+    pub url: String,
+    /// The PKCE code verifier, passed to `OidcClient::exchange_code`.
+    pub code_verifier: String,
+    /// The nonce, passed to `OidcClient::exchange_code`.
+    pub nonce: String,
+}
+
+impl OidcClient {
+    /// Creates a client for the given configuration.
+    #[must_use]
+    pub fn new(config: impl Into<OidcConfig>) -> Self {
+        Self {
+            config: config.into(),
+        }
+    }
+
+    /// Returns the configuration of the client.
+    #[must_use]
+    pub fn config(&self) -> &OidcConfig {
+        &self.config
+    }
+
+    /// Prepares an authorization request for the given scopes with a fresh
+    /// PKCE code verifier and nonce.
+    ///
+    /// # Errors
+    ///
+    /// Returns `OidcError::Random` if no random values could be generated.
+    pub fn authorization_request(&self, scope: &Scopes) -> Result<AuthorizationRequest, OidcError> {
+        let code_verifier = generate_code_verifier()?;
+        let nonce = random_string(NONCE_BYTES)?;
+
+        Ok(AuthorizationRequest {
+            url: self.authorization_url(scope, Some(&code_verifier), Some(&nonce)),
+            code_verifier,
+            nonce,
+        })
+    }
+
+    /// Generates the URL of the authorization request for the given scopes,
+    /// with the code challenge of the code verifier and the nonce, if any.
+    #[must_use]
+    pub fn authorization_url(
+        &self,
+        scope: &Scopes,
+        code_verifier: Option<&str>,
+        nonce: Option<&str>,
+    ) -> String {
+        push_query(
+            self.config.auth_endpoint.clone(),
+            self.authorization_params(scope, code_verifier, nonce),
+        )
+    }
+
+    /// Pushes the authorization request for the given scopes to the pushed
+    /// authorization request endpoint (RFC 9126) and returns the URL of the
+    /// login page, which only carries the `client_id` and the returned
+    /// `request_uri`. The client authenticates like at the token endpoint.
+    ///
+    /// # Errors
+    ///
+    /// Returns `OidcError::MissingParameter` without a
+    /// `pushed_authorization_request_endpoint`, the provider error of the
+    /// endpoint or a failed request.
+    pub async fn push_authorization_request(
+        &self,
+        scope: &Scopes,
+        code_verifier: Option<&str>,
+        nonce: Option<&str>,
+    ) -> Result<String, OidcError> {
+        let config = &self.config;
+        let endpoint = config
+            .pushed_authorization_request_endpoint
+            .as_deref()
+            .ok_or(OidcError::MissingParameter(
+                "pushed_authorization_request_endpoint",
+            ))?;
+        let params = self.authorization_params(scope, code_verifier, nonce);
+
+        let response = self.post_form(endpoint, params).await?;
+        match serde_json::from_str::<PushedAuthorizationResponse>(&response).map_err(Arc::new)? {
+            PushedAuthorizationResponse::Success(success) => Ok(push_query(
+                config.auth_endpoint.clone(),
+                vec![
+                    ("client_id".to_string(), config.client_id.clone()),
+                    ("request_uri".to_string(), success.request_uri),
+                ],
+            )),
+            PushedAuthorizationResponse::Error(error) => Err(OidcError::provider(error)),
+        }
+    }
+
+    /// Returns the parameters of the authorization request, see
+    /// `OidcClient::authorization_url`.
+    fn authorization_params(
+        &self,
+        scope: &Scopes,
+        code_verifier: Option<&str>,
+        nonce: Option<&str>,
+    ) -> Vec<(String, String)> {
+        let config = &self.config;
+        let mut params = vec![
+            (
+                "response_type".to_string(),
+                config.response_type.as_str().to_string(),
+            ),
+            ("client_id".to_string(), config.client_id.clone()),
+            ("redirect_uri".to_string(), config.redirect_uri.clone()),
+            ("scope".to_string(), scope.to_string()),
+        ];
+
+        let optional_params = [
+            ("audience", config.audience.clone()),
+            ("prompt", config.prompt.clone()),
+            ("max_age", config.max_age.map(|max_age| max_age.to_string())),
+            ("login_hint", config.login_hint.clone()),
+            ("ui_locales", config.ui_locales.clone()),
+            (
+                "response_mode",
+                (config.response_mode == ResponseMode::Fragment).then(|| "fragment".to_string()),
+            ),
+        ];
+        for (key, value) in optional_params {
+            if let Some(value) = value {
+                params.push((key.to_string(), value));
+            }
+        }
+        params.extend(config.extra_params.clone());
+
+        if let Some(code_verifier) = code_verifier {
+            params.push(("code_challenge".to_string(), code_challenge(code_verifier)));
+            params.push((
+                "code_challenge_method".to_string(),
+                CODE_CHALLENGE_METHOD.to_string(),
+            ));
+        }
+        if let Some(nonce) = nonce {
+            params.push(("nonce".to_string(), nonce.to_string()));
+        }
+
+        params
+    }
+
+    /// Generates the URL of the logout request with the given hints. Without
+    /// an ID token, or with `LogoutBehavior::LocalOnly`, the post logout
+    /// redirect URI is returned instead, unless `logout_without_id_token_hint`
+    /// is set. The `state` is appended to the post logout redirect URI.
+    #[must_use]
+    pub fn logout_url(
+        &self,
+        id_token: Option<&str>,
+        state: Option<&str>,
+        logout_hint: Option<&str>,
+        ui_locales: Option<&str>,
+    ) -> String {
+        let config = &self.config;
+        let mut redirect_params = vec![("destroy_session".to_string(), "true".to_string())];
+        if let Some(state) = state {
+            redirect_params.push(("state".to_string(), state.to_string()));
+        }
+        let post_logout_redirect_uri =
+            push_query(config.post_logout_redirect_uri.clone(), redirect_params);
+        let profile = config.profile;
+        let local_only = config.logout_behavior == LogoutBehavior::LocalOnly;
+        let without_hint =
+            config.logout_without_id_token_hint || profile.logout_without_id_token_hint();
+        if local_only || (id_token.is_none() && !without_hint) {
+            return post_logout_redirect_uri;
+        }
+
+        // The post logout redirect URI carries its own query, which the
+        // provider has to pass back as is, so it's encoded as a whole.
+        let mut params = vec![(
+            profile.post_logout_redirect_param().to_string(),
+            post_logout_redirect_uri,
+        )];
+        if profile.logout_with_client_id() {
+            params.push(("client_id".to_string(), config.client_id.clone()));
+        }
+        let optional_params = [
+            ("logout_hint", logout_hint),
+            ("ui_locales", ui_locales),
+            ("id_token_hint", id_token),
+        ];
+        for (key, value) in optional_params {
+            if let Some(value) = value {
+                params.push((key.to_string(), value.to_string()));
+            }
+        }
+
+        push_query(config.logout_endpoint.clone(), params)
+    }
+
+    /// Exchanges the authorization code of a login callback for the tokens.
+    /// The code verifier and the nonce are the ones of the authorization
+    /// request, the nonce is verified against the ID token.
+    ///
+    /// # Errors
+    ///
+    /// Returns the provider error of the token endpoint, a failed request or
+    /// `OidcError::Nonce` if the nonce doesn't match.
+    pub async fn exchange_code(
+        &self,
+        code: &str,
+        session_state: Option<&str>,
+        code_verifier: Option<&str>,
+        nonce: Option<&str>,
+    ) -> Result<TokenStorage, OidcError> {
+        let config = &self.config;
+        let mut params = vec![
+            ("grant_type".to_string(), "authorization_code".to_string()),
+            ("client_id".to_string(), config.client_id.clone()),
+            ("redirect_uri".to_string(), config.redirect_uri.clone()),
+            ("code".to_string(), code.to_string()),
+        ];
+        if let Some(state) = session_state {
+            params.push(("state".to_string(), state.to_string()));
+        }
+        if let Some(code_verifier) = code_verifier {
+            params.push(("code_verifier".to_string(), code_verifier.to_string()));
+        }
+        let response = self.token_request(params).await?;
+        let mut token_storage = TokenStorage::from_response(response, None)?;
+        // The session state of the authorization response is the one the OP
+        // iframe compares against.
+        if session_state.is_some() {
+            token_storage.session_state = session_state.map(str::to_string);
+        }
+        if let Some(nonce) = nonce {
+            verify_nonce(&token_storage.id_token, nonce)?;
+        }
+
+        Ok(token_storage)
+    }
+
+    /// Completes a login callback of the implicit or hybrid flow, see
+    /// `OidcConfig::response_type`. An ID token of the callback is validated
+    /// like `verify_id_token` does, and its `at_hash` and `c_hash` claims are
+    /// checked against the returned access token and code. Without the access
+    /// token and the ID token in the callback, the code is exchanged like
+    /// `exchange_code` does.
+    ///
+    /// # Errors
+    ///
+    /// Returns the errors of `verify_id_token`, `OidcError::Nonce` if the
+    /// nonce doesn't match, `OidcError::TokenHash` if a hash claim doesn't
+    /// match and `OidcError::MissingIdToken` if neither the callback nor the
+    /// token endpoint returned an ID token.
+    pub async fn complete_implicit(
+        &self,
+        response: ImplicitCallbackResponse,
+        code_verifier: Option<&str>,
+        nonce: Option<&str>,
+    ) -> Result<TokenStorage, OidcError> {
+        if let Some(id_token) = &response.id_token {
+            // Unlike the response of the token endpoint, the URL could have
+            // been crafted by anyone, so the ID token has to be validated.
+            self.verify_id_token(id_token).await?;
+            // The nonce is required by the implicit and hybrid flow, the
+            // tokens could be injected into the fragment otherwise.
+            verify_nonce(id_token, nonce.ok_or(OidcError::Nonce)?)?;
+            if let Some(code) = &response.code {
+                verify_token_hash(id_token, "c_hash", code, true)?;
+            }
+            if let Some(access_token) = &response.access_token {
+                verify_token_hash(id_token, "at_hash", access_token, response.code.is_none())?;
+            }
+        }
+
+        match (response.access_token, response.id_token, response.code) {
+            (Some(access_token), Some(id_token), _) => TokenStorage::from_response(
+                SuccessTokenResponse {
+                    access_token,
+                    expires_in: response.expires_in,
+                    refresh_expires_in: None,
+                    refresh_token: None,
+                    token_type: response.token_type,
+                    id_token: Some(id_token),
+                    not_before_policy: None,
+                    session_state: response.session_state,
+                    scope: response.scope,
+                },
+                None,
+            ),
+            (_, _, Some(code)) => {
+                self.exchange_code(
+                    &code,
+                    response.session_state.as_deref(),
+                    code_verifier,
+                    nonce,
+                )
+                .await
+            }
+            (Some(_), None, None) => Err(OidcError::MissingIdToken),
+            (None, _, None) => Err(OidcError::InvalidCallback(
+                "Missing parameter 'access_token' or 'code'".to_string(),
+            )),
+        }
+    }
+
+    /// Validates the signature of the ID token against the JSON Web Key Set
+    /// of the provider, its expiry and that it was issued by the provider
+    /// (`iss`) for this client (`aud`). The key set is taken from the
+    /// `jwks_uri`, or the discovery document of the `issuer`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `OidcError::MissingParameter` without a `jwks_uri` or an
+    /// issuer, the validation error of the token or a failed request.
+    pub async fn verify_id_token(&self, id_token: &str) -> Result<(), OidcError> {
+        let config = &self.config;
+        let issuer = config
+            .expected_issuer
+            .as_ref()
+            .or(config.issuer.as_ref())
+            .ok_or(OidcError::MissingParameter("issuer"))?;
+        let jwks_uri = match &config.jwks_uri {
+            Some(jwks_uri) => jwks_uri.clone(),
+            None => ProviderMetadata::fetch_with_client(config.client().as_ref(), issuer)
+                .await?
+                .jwks_uri
+                .ok_or(OidcError::MissingParameter("jwks_uri"))?,
+        };
+        let jwks = Jwks::fetch_with_client(config.client().as_ref(), &jwks_uri).await?;
+
+        let mut validation = Validation::new(Algorithm::RS256);
+        validation.leeway = config.leeway.as_secs();
+        validation.set_issuer(&[issuer]);
+        validation.set_audience(&[&config.client_id]);
+        jwks.decode::<Value>(id_token, &validation)?;
+
+        Ok(())
+    }
+
+    /// Exchanges the refresh token for new tokens. Values missing in the
+    /// response, like a rotated refresh token, are taken from the previous
+    /// tokens.
+    ///
+    /// # Errors
+    ///
+    /// Returns the provider error of the token endpoint or a failed request.
+    pub async fn refresh(
+        &self,
+        refresh_token: &str,
+        previous: Option<&TokenStorage>,
+    ) -> Result<TokenStorage, OidcError> {
+        let params = vec![
+            ("grant_type".to_string(), "refresh_token".to_string()),
+            ("client_id".to_string(), self.config.client_id.clone()),
+            ("refresh_token".to_string(), refresh_token.to_string()),
+        ];
+        let response = self.token_request(params).await?;
+
+        TokenStorage::from_response(response, previous)
+    }
+
+    /// Sends the parameters, form encoded with the client authentication (see
+    /// `client_authentication`), and a DPoP proof with `OidcConfig::dpop`
+    /// (see `send_with_dpop`) to the given endpoint of the provider and
+    /// returns the response body. Responses with a client error status (e.g.
+    /// a rejected refresh token) carry an error response, server errors fail.
+    ///
+    /// # Errors
+    ///
+    /// Returns `OidcError::Http` on a server error, the error of the DPoP
+    /// prover or a failed request.
+    pub async fn post_form(
+        &self,
+        endpoint: &str,
+        params: Vec<(String, String)>,
+    ) -> Result<String, OidcError> {
+        let config = &self.config;
+        let (body, authorization) = config.authenticate_client(params);
+        let mut request = HttpRequest::post_form(endpoint, body);
+        if let Some(authorization) = authorization {
+            request = request.header("Authorization", authorization);
+        }
+
+        let client = config.client();
+        let response = match &config.dpop {
+            Some(dpop) => send_with_dpop(client.as_ref(), dpop.as_ref(), request, None).await?,
+            None => client.send(request).await?,
+        };
+        if response.status >= 500 {
+            return Err(OidcError::Http(response.status));
+        }
+        Ok(response.body)
+    }
+
+    /// Sends the parameters to the token endpoint and returns the successful
+    /// token response, an error response of the provider is returned as
+    /// error.
+    async fn token_request(
+        &self,
+        params: Vec<(String, String)>,
+    ) -> Result<SuccessTokenResponse, OidcError> {
+        let response = self.post_form(&self.config.token_endpoint, params).await?;
+
+        match serde_json::from_str::<TokenResponse>(&response).map_err(Arc::new)? {
+            TokenResponse::Success(success) => Ok(success),
+            TokenResponse::Error(error) => Err(OidcError::provider(error)),
+        }
+    }
+}
+
+/// Verifies that the `nonce` claim of the ID token matches the nonce which
+/// was sent with the authentication request.
+fn verify_nonce(id_token: &str, nonce: &str) -> Result<(), OidcError> {
+    #[derive(Deserialize)]
+    struct NonceClaims {
+        nonce: Option<String>,
+    }
+
+    let claims = decode_claims_unverified::<NonceClaims>(id_token)?;
+    if claims.nonce.as_deref() != Some(nonce) {
+        return Err(OidcError::Nonce);
+    }
+
+    Ok(())
+}
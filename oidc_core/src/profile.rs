@@ -0,0 +1,65 @@
+/*
+* The MIT License (MIT)
+*
+* Copyright (c) 2023 Daniél Kerkmann <daniel@kerkmann.dev>
+*
+* Permission is hereby granted, free of charge, to any person obtaining a copy
+* of this software and associated documentation files (the "Software"), to deal
+* in the Software without restriction, including without limitation the rights
+* to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+* copies of the Software, and to permit persons to whom the Software is
+* furnished to do so, subject to the following conditions:
+*
+* The above copyright notice and this permission notice shall be included in all
+* copies or substantial portions of the Software.
+*
+* THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+* IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+* FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+* AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+* LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+* OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+* SOFTWARE.
+*/
+
+use serde::Deserialize;
+
+/// An enumeration of provider profiles, which adapt the requests to the
+/// quirks of the respective provider.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProviderProfile {
+    /// A provider following the OpenID Connect specifications.
+    #[default]
+    Generic,
+    Keycloak,
+    Auth0,
+    /// Azure AD / Entra ID.
+    AzureAd,
+}
+
+impl ProviderProfile {
+    /// Returns the name of the logout parameter carrying the post logout
+    /// redirect URI.
+    #[must_use]
+    pub fn post_logout_redirect_param(self) -> &'static str {
+        match self {
+            ProviderProfile::Auth0 => "returnTo",
+            _ => "post_logout_redirect_uri",
+        }
+    }
+
+    /// Checks if the client ID has to be sent with the logout, to identify
+    /// the client without an `id_token_hint`.
+    #[must_use]
+    pub fn logout_with_client_id(self) -> bool {
+        matches!(self, ProviderProfile::Keycloak | ProviderProfile::Auth0)
+    }
+
+    /// Checks if the provider accepts a logout without `id_token_hint`, see
+    /// `OidcConfig::logout_without_id_token_hint`.
+    #[must_use]
+    pub fn logout_without_id_token_hint(self) -> bool {
+        !matches!(self, ProviderProfile::Generic)
+    }
+}
@@ -0,0 +1,308 @@
+/*
+* The MIT License (MIT)
+*
+* Copyright (c) 2023 Daniél Kerkmann <daniel@kerkmann.dev>
+*
+* Permission is hereby granted, free of charge, to any person obtaining a copy
+* of this software and associated documentation files (the "Software"), to deal
+* in the Software without restriction, including without limitation the rights
+* to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+* copies of the Software, and to permit persons to whom the Software is
+* furnished to do so, subject to the following conditions:
+*
+* The above copyright notice and this permission notice shall be included in all
+* copies or substantial portions of the Software.
+*
+* THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+* IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+* FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+* AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+* LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+* OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+* SOFTWARE.
+*/
+
+use serde::{Deserialize, Deserializer, Serialize};
+use url::form_urlencoded;
+
+use crate::{error::OidcError, ResponseType};
+
+/// An enumeration representing different callback responses during the
+/// authentication process.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub enum CallbackResponse {
+    SuccessLogin(SuccessCallbackResponse),
+    SuccessImplicitLogin(ImplicitCallbackResponse),
+    SuccessLogout(SuccessLogoutResponse),
+    Error(ErrorResponse),
+}
+
+/// A structure representing a successful login callback response.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct SuccessCallbackResponse {
+    pub session_state: Option<String>,
+    pub code: String,
+}
+
+/// A structure representing a successful login callback response of the
+/// implicit or hybrid flow, which returns the tokens in the URL fragment,
+/// see `ResponseType`.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct ImplicitCallbackResponse {
+    pub session_state: Option<String>,
+    /// The authorization code of the hybrid flow.
+    pub code: Option<String>,
+    pub id_token: Option<String>,
+    pub access_token: Option<String>,
+    pub token_type: Option<String>,
+    pub expires_in: Option<i64>,
+    pub scope: Option<String>,
+}
+
+/// A structure representing a successful logout callback response.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct SuccessLogoutResponse {
+    pub destroy_session: bool,
+    pub state: Option<String>,
+}
+
+/// An enumeration representing the response to token requests, including
+/// success and error responses.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(untagged)]
+pub enum TokenResponse {
+    Success(SuccessTokenResponse),
+    Error(ErrorResponse),
+}
+
+/// A structure representing a successful token response.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct SuccessTokenResponse {
+    pub access_token: String,
+    /// Some providers omit the expiry or send it as string (Azure AD).
+    #[serde(default, deserialize_with = "optional_number")]
+    pub expires_in: Option<i64>,
+    #[serde(default, deserialize_with = "optional_number")]
+    pub refresh_expires_in: Option<i64>,
+    /// Azure AD only issues a refresh token with the `offline_access` scope.
+    pub refresh_token: Option<String>,
+    pub token_type: Option<String>,
+    /// Some providers omit the ID token on refreshes.
+    pub id_token: Option<String>,
+    #[serde(rename = "not-before-policy")]
+    pub not_before_policy: Option<i64>,
+    pub session_state: Option<String>,
+    pub scope: Option<String>,
+}
+
+/// An enumeration representing the response to token exchange requests
+/// (RFC 8693), including success and error responses.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(untagged)]
+pub enum TokenExchangeResponse {
+    Success(SuccessTokenExchangeResponse),
+    Error(ErrorResponse),
+}
+
+/// A structure representing a successful token exchange response. The issued
+/// token is always returned as `access_token`, its type is given by
+/// `issued_token_type`.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct SuccessTokenExchangeResponse {
+    pub access_token: String,
+    pub issued_token_type: String,
+    pub expires_in: Option<i64>,
+}
+
+/// An enumeration representing the response to pushed authorization requests
+/// (RFC 9126), including success and error responses.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(untagged)]
+pub enum PushedAuthorizationResponse {
+    Success(SuccessPushedAuthorizationResponse),
+    Error(ErrorResponse),
+}
+
+/// A structure representing a successful pushed authorization response. The
+/// `request_uri` references the pushed request in the authorization request.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct SuccessPushedAuthorizationResponse {
+    pub request_uri: String,
+    pub expires_in: i64,
+}
+
+/// A structure representing an error response during the authentication
+/// process.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct ErrorResponse {
+    pub error: String,
+    pub error_description: String,
+}
+
+/// The phrases of the `error_description`, with which providers report the
+/// reuse of a rotated refresh token, e.g. Keycloak's "Maximum allowed
+/// refresh token reuse exceeded".
+const REUSE_DESCRIPTIONS: [&str; 3] = ["reuse", "already used", "replay"];
+
+impl ErrorResponse {
+    /// Checks if the `error_description` reports the reuse of a refresh
+    /// token, unlike an expired or revoked grant.
+    #[must_use]
+    pub fn indicates_reuse(&self) -> bool {
+        let description = self.error_description.to_lowercase();
+        REUSE_DESCRIPTIONS
+            .iter()
+            .any(|phrase| description.contains(phrase))
+    }
+}
+
+/// A function returning the value of a callback parameter, e.g. of the query
+/// of the redirect URI.
+type Lookup<'a> = &'a dyn Fn(&str) -> Option<String>;
+
+impl SuccessCallbackResponse {
+    /// Reads the parameters of a successful login callback.
+    fn from_lookup(get: Lookup) -> Result<Self, OidcError> {
+        if let (session_state, Some(code)) = (get("session_state"), get("code")) {
+            return Ok(SuccessCallbackResponse {
+                session_state,
+                code,
+            });
+        }
+        Err(OidcError::InvalidCallback(
+            "Missing parameter 'code'".to_string(),
+        ))
+    }
+}
+
+impl ImplicitCallbackResponse {
+    /// Reads the parameters of a successful login callback of the implicit
+    /// or hybrid flow.
+    fn from_lookup(get: Lookup) -> Result<Self, OidcError> {
+        if get("id_token").is_none() && get("access_token").is_none() {
+            return Err(OidcError::InvalidCallback(
+                "Missing parameter 'id_token' or 'access_token'".to_string(),
+            ));
+        }
+
+        Ok(ImplicitCallbackResponse {
+            session_state: get("session_state"),
+            code: get("code"),
+            id_token: get("id_token"),
+            access_token: get("access_token"),
+            token_type: get("token_type"),
+            expires_in: get("expires_in").and_then(|expires_in| expires_in.parse().ok()),
+            scope: get("scope"),
+        })
+    }
+}
+
+impl SuccessLogoutResponse {
+    /// Reads the parameters of a successful logout callback.
+    fn from_lookup(get: Lookup) -> Result<Self, OidcError> {
+        if let Some(destroy_session) = get("destroy_session") {
+            return Ok(SuccessLogoutResponse {
+                destroy_session: destroy_session.parse().unwrap_or_default(),
+                state: get("state"),
+            });
+        }
+        Err(OidcError::InvalidCallback(
+            "Missing parameter 'destroy_session'".to_string(),
+        ))
+    }
+}
+
+impl ErrorResponse {
+    /// Reads the parameters of an error callback.
+    fn from_lookup(get: Lookup) -> Result<Self, OidcError> {
+        if let (Some(error), Some(error_description)) = (get("error"), get("error_description")) {
+            return Ok(ErrorResponse {
+                error,
+                error_description,
+            });
+        }
+        Err(OidcError::InvalidCallback(
+            "Missing parameter 'error' and 'error_description'".to_string(),
+        ))
+    }
+}
+
+impl CallbackResponse {
+    /// Converts the parameters of a callback of the given response type,
+    /// which are read with the given lookup. Tokens in the callback are only
+    /// read if the response type returns them (implicit or hybrid flow),
+    /// otherwise an app using the code flow would accept tokens injected into
+    /// the callback URL.
+    ///
+    /// # Errors
+    ///
+    /// Returns `OidcError::InvalidCallback` if the parameters are neither a
+    /// login, a logout nor an error callback.
+    pub fn from_lookup(
+        get: impl Fn(&str) -> Option<String>,
+        response_type: ResponseType,
+    ) -> Result<Self, OidcError> {
+        // Tokens in the callback take precedence, the code of the hybrid flow
+        // is part of the implicit response.
+        if response_type != ResponseType::Code {
+            if let Ok(response) = ImplicitCallbackResponse::from_lookup(&get) {
+                return Ok(CallbackResponse::SuccessImplicitLogin(response));
+            }
+        }
+        if let Ok(response) = SuccessCallbackResponse::from_lookup(&get) {
+            return Ok(CallbackResponse::SuccessLogin(response));
+        } else if let Ok(reponse) = SuccessLogoutResponse::from_lookup(&get) {
+            return Ok(CallbackResponse::SuccessLogout(reponse));
+        } else if let Ok(reponse) = ErrorResponse::from_lookup(&get) {
+            return Ok(CallbackResponse::Error(reponse));
+        }
+
+        Err(OidcError::InvalidCallback(
+            "Missing parameter 'session_state' and 'code' or 'error' and 'error_description'"
+                .to_string(),
+        ))
+    }
+
+    /// Converts the form encoded parameters of a callback, e.g. the query or
+    /// the fragment of the redirect URI a Tauri or CLI app received, see
+    /// `CallbackResponse::from_lookup`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `OidcError::InvalidCallback` if the parameters are neither a
+    /// login, a logout nor an error callback.
+    pub fn from_query(query: &str, response_type: ResponseType) -> Result<Self, OidcError> {
+        let params = form_urlencoded::parse(query.trim_start_matches(['?', '#']).as_bytes())
+            .into_owned()
+            .collect::<Vec<_>>();
+
+        Self::from_lookup(
+            |key| {
+                params
+                    .iter()
+                    .find(|(name, _)| name == key)
+                    .map(|(_, value)| value.clone())
+            },
+            response_type,
+        )
+    }
+}
+
+/// Deserializes an optional number, which some providers (e.g. Azure AD) send
+/// as string.
+fn optional_number<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<i64>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum NumberOrString {
+        Number(i64),
+        String(String),
+    }
+
+    match Option::<NumberOrString>::deserialize(deserializer)? {
+        None => Ok(None),
+        Some(NumberOrString::Number(number)) => Ok(Some(number)),
+        Some(NumberOrString::String(string)) => {
+            string.parse().map(Some).map_err(serde::de::Error::custom)
+        }
+    }
+}
@@ -0,0 +1,167 @@
+/*
+* The MIT License (MIT)
+*
+* Copyright (c) 2023 Daniél Kerkmann <daniel@kerkmann.dev>
+*
+* Permission is hereby granted, free of charge, to any person obtaining a copy
+* of this software and associated documentation files (the "Software"), to deal
+* in the Software without restriction, including without limitation the rights
+* to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+* copies of the Software, and to permit persons to whom the Software is
+* furnished to do so, subject to the following conditions:
+*
+* The above copyright notice and this permission notice shall be included in all
+* copies or substantial portions of the Software.
+*
+* THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+* IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+* FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+* AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+* LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+* OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+* SOFTWARE.
+*/
+
+use std::fmt::{self, Display};
+
+use serde::{Deserialize, Serialize};
+
+/// A set of OAuth scopes, like `openid profile email`. It keeps the order
+/// and drops duplicates, (de)serialized as a space separated string.
+///
+/// ```rust,ignore
+/// let scopes = Scopes::openid().profile().email().custom("offline_access");
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(from = "String", into = "String")]
+pub struct Scopes(Vec<String>);
+
+impl Scopes {
+    /// Creates an empty set of scopes.
+    #[must_use]
+    pub fn new() -> Self {
+        Self(Vec::new())
+    }
+
+    /// Creates a set containing the `openid` scope, which requests an ID
+    /// token.
+    #[must_use]
+    pub fn openid() -> Self {
+        Self::new().custom("openid")
+    }
+
+    /// Adds the `profile` scope.
+    #[must_use]
+    pub fn profile(self) -> Self {
+        self.custom("profile")
+    }
+
+    /// Adds the `email` scope.
+    #[must_use]
+    pub fn email(self) -> Self {
+        self.custom("email")
+    }
+
+    /// Adds the `phone` scope.
+    #[must_use]
+    pub fn phone(self) -> Self {
+        self.custom("phone")
+    }
+
+    /// Adds the `address` scope.
+    #[must_use]
+    pub fn address(self) -> Self {
+        self.custom("address")
+    }
+
+    /// Adds the `offline_access` scope, which requests a refresh token from
+    /// some providers.
+    #[must_use]
+    pub fn offline_access(self) -> Self {
+        self.custom("offline_access")
+    }
+
+    /// Adds any other scope, e.g. an API scope.
+    #[must_use]
+    pub fn custom(mut self, scope: impl Into<String>) -> Self {
+        let scope = scope.into();
+        if !self.contains(&scope) {
+            self.0.push(scope);
+        }
+        self
+    }
+
+    /// Checks if the set contains the given scope.
+    #[must_use]
+    pub fn contains(&self, scope: &str) -> bool {
+        self.0.iter().any(|value| value == scope)
+    }
+
+    /// Checks if the set is empty.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Returns an iterator over the scopes.
+    pub fn iter(&self) -> impl Iterator<Item = &str> {
+        self.0.iter().map(String::as_str)
+    }
+
+    /// Returns the scopes of this set, which are missing in the other one,
+    /// e.g. the requested scopes the provider didn't grant.
+    #[must_use]
+    pub fn missing_in(&self, other: &Scopes) -> Scopes {
+        self.iter()
+            .filter(|scope| !other.contains(scope))
+            .fold(Scopes::new(), Scopes::custom)
+    }
+
+    /// Returns the scopes of both sets, e.g. the configured scopes and the
+    /// ones requested incrementally.
+    #[must_use]
+    pub fn union(&self, other: &Scopes) -> Scopes {
+        other.iter().fold(self.clone(), Scopes::custom)
+    }
+}
+
+/// Defaults to the `openid` scope only.
+impl Default for Scopes {
+    fn default() -> Self {
+        Self::openid()
+    }
+}
+
+impl Display for Scopes {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0.join(" "))
+    }
+}
+
+/// Parses a space separated list of scopes.
+impl From<&str> for Scopes {
+    fn from(value: &str) -> Self {
+        value.split_whitespace().fold(Scopes::new(), Scopes::custom)
+    }
+}
+
+impl From<String> for Scopes {
+    fn from(value: String) -> Self {
+        Self::from(value.as_str())
+    }
+}
+
+impl<'a> IntoIterator for &'a Scopes {
+    type Item = &'a String;
+    type IntoIter = std::slice::Iter<'a, String>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.iter()
+    }
+}
+
+impl From<Scopes> for String {
+    fn from(value: Scopes) -> Self {
+        value.to_string()
+    }
+}
@@ -0,0 +1,200 @@
+/*
+* The MIT License (MIT)
+*
+* Copyright (c) 2023 Daniél Kerkmann <daniel@kerkmann.dev>
+*
+* Permission is hereby granted, free of charge, to any person obtaining a copy
+* of this software and associated documentation files (the "Software"), to deal
+* in the Software without restriction, including without limitation the rights
+* to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+* copies of the Software, and to permit persons to whom the Software is
+* furnished to do so, subject to the following conditions:
+*
+* The above copyright notice and this permission notice shall be included in all
+* copies or substantial portions of the Software.
+*
+* THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+* IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+* FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+* AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+* LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+* OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+* SOFTWARE.
+*/
+
+use chrono::{DateTime, Duration, NaiveDateTime};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    claims::decode_claims_unverified, error::OidcError, response::SuccessTokenResponse, utils::now,
+};
+
+/// The lifetime of an access token in seconds, if neither the token response
+/// nor the access token contains its expiry.
+const DEFAULT_EXPIRES_IN: i64 = 300;
+
+/// A structure representing the storage of authentication tokens.
+#[derive(Debug, Default, Clone, PartialEq, Deserialize, Serialize)]
+pub struct TokenStorage {
+    pub id_token: String,
+    pub access_token: String,
+    pub expires_in: NaiveDateTime,
+    /// The refresh token, if the provider issued one.
+    #[serde(default)]
+    pub refresh_token: Option<String>,
+    pub refresh_expires_in: Option<NaiveDateTime>,
+    /// The session state of the provider, compared by its OP iframe.
+    #[serde(default)]
+    pub session_state: Option<String>,
+    /// The granted scopes, if the token response listed them.
+    #[serde(default)]
+    pub scope: Option<String>,
+    /// The generation of the refresh token, incremented whenever the provider
+    /// rotates it. Used to tell a concurrent rotation in another tab from a
+    /// reused refresh token.
+    #[serde(default)]
+    pub refresh_generation: u32,
+    /// The time the tokens were received, which bounds the
+    /// `refresh_before_expiry` window to the lifetime of the access token.
+    #[serde(default)]
+    pub issued_at: Option<NaiveDateTime>,
+}
+
+impl TokenStorage {
+    /// Checks if the storage carries the tokens. The session restored from
+    /// the cookie storage of `leptos_oidc2` only carries the claims and
+    /// expiries in the browser, the tokens stay on the server.
+    #[must_use]
+    pub fn has_tokens(&self) -> bool {
+        !self.access_token.is_empty()
+    }
+
+    /// Checks if the access token is expired.
+    #[must_use]
+    pub fn access_token_expired(&self) -> bool {
+        self.access_token_expired_with(std::time::Duration::ZERO)
+    }
+
+    /// Checks if the access token is expired, tolerating the given clock
+    /// skew.
+    #[must_use]
+    pub fn access_token_expired_with(&self, leeway: std::time::Duration) -> bool {
+        self.expires_in + chrono_duration(leeway) <= now()
+    }
+
+    /// Checks if the access token expires within the given window before its
+    /// expiry. The window is clamped to half the lifetime of the access
+    /// token, so a fresh token isn't refreshed right away.
+    #[must_use]
+    pub fn access_token_expires_within(&self, window: std::time::Duration) -> bool {
+        let window = match self.issued_at {
+            Some(issued_at) => chrono_duration(window).min((self.expires_in - issued_at) / 2),
+            None => chrono_duration(window),
+        };
+        self.expires_in - window <= now()
+    }
+
+    /// Checks if the refresh token is expired, tolerating the given clock
+    /// skew. A refresh token without expiry never expires.
+    #[must_use]
+    pub fn refresh_token_expired_with(&self, leeway: std::time::Duration) -> bool {
+        self.refresh_expires_in
+            .is_some_and(|refresh_expires_in| refresh_expires_in + chrono_duration(leeway) <= now())
+    }
+}
+
+/// Converts the leeway into a chrono duration, saturating at zero.
+fn chrono_duration(leeway: std::time::Duration) -> Duration {
+    Duration::from_std(leeway).unwrap_or_else(|_| Duration::zero())
+}
+
+/// A wrapper of `TokenStorage`, which compares the token identity and the
+/// expiry only. Changes of the refresh bookkeeping aren't considered a
+/// change, which avoids redundant re-renders of the dependent views.
+#[derive(Debug, Clone)]
+pub struct TokenIdentity(pub TokenStorage);
+
+impl PartialEq for TokenIdentity {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.id_token == other.0.id_token
+            && self.0.access_token == other.0.access_token
+            && self.0.expires_in == other.0.expires_in
+    }
+}
+
+impl TokenStorage {
+    /// Creates the token storage out of a token response. Tokens missing in
+    /// the response, which some providers omit on refreshes, are taken from
+    /// the previous token storage. A missing expiry is taken from the `exp`
+    /// claim of the access token, if it's a JWT.
+    ///
+    /// # Errors
+    ///
+    /// Returns `OidcError::MissingIdToken` if neither the response nor the
+    /// previous token storage carries an ID token.
+    pub fn from_response(
+        response: SuccessTokenResponse,
+        previous: Option<&TokenStorage>,
+    ) -> Result<Self, OidcError> {
+        let id_token = response
+            .id_token
+            .or_else(|| previous.map(|previous| previous.id_token.clone()))
+            .ok_or(OidcError::MissingIdToken)?;
+        let expires_in = match response.expires_in {
+            Some(expires_in) => now() + Duration::seconds(expires_in),
+            None => access_token_expiry(&response.access_token)
+                .unwrap_or_else(|| now() + Duration::seconds(DEFAULT_EXPIRES_IN)),
+        };
+        let previous_generation = previous.map_or(0, |previous| previous.refresh_generation);
+        let refresh_generation = match (&response.refresh_token, previous) {
+            (Some(refresh_token), Some(previous))
+                if previous.refresh_token.as_ref() != Some(refresh_token) =>
+            {
+                previous_generation + 1
+            }
+            _ => previous_generation,
+        };
+        let (refresh_token, refresh_expires_in) = match response.refresh_token {
+            Some(refresh_token) => (
+                Some(refresh_token),
+                response
+                    .refresh_expires_in
+                    .map(|refresh_expires_in| now() + Duration::seconds(refresh_expires_in)),
+            ),
+            None => previous
+                .map(|previous| (previous.refresh_token.clone(), previous.refresh_expires_in))
+                .unwrap_or_default(),
+        };
+
+        let session_state = response
+            .session_state
+            .or_else(|| previous.and_then(|previous| previous.session_state.clone()));
+        let scope = response
+            .scope
+            .or_else(|| previous.and_then(|previous| previous.scope.clone()));
+
+        Ok(Self {
+            id_token,
+            access_token: response.access_token,
+            expires_in,
+            refresh_token,
+            refresh_expires_in,
+            session_state,
+            scope,
+            refresh_generation,
+            issued_at: Some(now()),
+        })
+    }
+}
+
+/// Returns the expiry of a JWT access token, taken from its `exp` claim.
+#[must_use]
+pub fn access_token_expiry(access_token: &str) -> Option<NaiveDateTime> {
+    #[derive(Deserialize)]
+    struct ExpiryClaims {
+        exp: i64,
+    }
+
+    let claims = decode_claims_unverified::<ExpiryClaims>(access_token).ok()?;
+    DateTime::from_timestamp(claims.exp, 0).map(|date_time| date_time.naive_utc())
+}
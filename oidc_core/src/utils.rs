@@ -0,0 +1,127 @@
+/*
+* The MIT License (MIT)
+*
+* Copyright (c) 2023 Daniél Kerkmann <daniel@kerkmann.dev>
+*
+* Permission is hereby granted, free of charge, to any person obtaining a copy
+* of this software and associated documentation files (the "Software"), to deal
+* in the Software without restriction, including without limitation the rights
+* to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+* copies of the Software, and to permit persons to whom the Software is
+* furnished to do so, subject to the following conditions:
+*
+* The above copyright notice and this permission notice shall be included in all
+* copies or substantial portions of the Software.
+*
+* THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+* IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+* FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+* AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+* LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+* OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+* SOFTWARE.
+*/
+
+use std::cell::Cell;
+
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use chrono::{NaiveDateTime, Utc};
+
+use crate::error::OidcError;
+
+thread_local! {
+    /// The fixed time of the current thread, see `set_fixed_time`.
+    static FIXED_TIME: Cell<Option<NaiveDateTime>> = const { Cell::new(None) };
+}
+
+/// A trait for building query and body parameters in a string.
+pub trait ParamBuilder {
+    /// Appends a key-value pair to the string as a query parameter. If the
+    /// string doesn't contain any query parameters, it adds a '?' character.
+    /// Otherwise, it appends '&'.
+    #[must_use]
+    fn push_param_query(self, key: impl AsRef<str>, value: impl AsRef<str>) -> Self;
+
+    /// Appends a key-value pair to the string as a body parameter.
+    /// It always appends '&'.
+    #[must_use]
+    fn push_param_body(self, key: impl AsRef<str>, value: impl AsRef<str>) -> Self;
+}
+
+/// Implementation of the `ParamBuilder` trait for the `String` type.
+impl ParamBuilder for String {
+    /// Appends a key-value pair to the string as a query parameter.
+    fn push_param_query(mut self, key: impl AsRef<str>, value: impl AsRef<str>) -> Self {
+        if !self.contains('?') {
+            self.push('?');
+        } else if !self.ends_with('&') {
+            self.push('&');
+        }
+        self.push_str(key.as_ref());
+        self.push('=');
+        self.push_str(value.as_ref());
+        self
+    }
+
+    /// Appends a key-value pair to the string as a body parameter.
+    fn push_param_body(mut self, key: impl AsRef<str>, value: impl AsRef<str>) -> Self {
+        self.push('&');
+        self.push_str(key.as_ref());
+        self.push('=');
+        self.push_str(value.as_ref());
+        self
+    }
+}
+
+/// Encodes the key-value pairs as `application/x-www-form-urlencoded` body.
+#[must_use]
+pub fn form_encode(params: Vec<(String, String)>) -> String {
+    // The query serializer of the URL encodes the pairs, the base URL is only
+    // needed to build it.
+    let Ok(mut url) = url::Url::parse("http://localhost/") else {
+        return String::new();
+    };
+    url.query_pairs_mut().extend_pairs(params);
+
+    url.query().unwrap_or_default().to_string()
+}
+
+/// Appends the key-value pairs to the query of the URL, form encoded like
+/// `form_encode`, so values like a nested URL keep their own query.
+#[must_use]
+pub fn push_query(mut url: String, params: Vec<(String, String)>) -> String {
+    if params.is_empty() {
+        return url;
+    }
+    if !url.contains('?') {
+        url.push('?');
+    } else if !url.ends_with('?') && !url.ends_with('&') {
+        url.push('&');
+    }
+    url.push_str(&form_encode(params));
+    url
+}
+
+/// Generates a random string out of the given amount of random bytes, encoded
+/// as base64url without padding. It's used for values like the PKCE code
+/// verifier or the nonce.
+pub fn random_string(bytes: usize) -> Result<String, OidcError> {
+    let mut buffer = vec![0u8; bytes];
+    getrandom::getrandom(&mut buffer).map_err(|_| OidcError::Random)?;
+    Ok(URL_SAFE_NO_PAD.encode(buffer))
+}
+
+/// Fixes the time returned by `now` for the current thread, e.g. to check
+/// the expiry of the tokens in previews or tests. `None` returns to the
+/// system clock.
+pub fn set_fixed_time(time: Option<NaiveDateTime>) {
+    FIXED_TIME.with(|fixed_time| fixed_time.set(time));
+}
+
+/// Returns the current time, or the fixed time, see `set_fixed_time`.
+#[must_use]
+pub fn now() -> NaiveDateTime {
+    FIXED_TIME
+        .with(Cell::get)
+        .unwrap_or_else(|| Utc::now().naive_utc())
+}
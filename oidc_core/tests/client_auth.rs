@@ -0,0 +1,102 @@
+//! Native tests of the form encoding and the client authentication of the
+//! requests to the token endpoint, against a mock identity provider:
+//!
+//! ```sh
+//! cargo test -p oidc_core --test client_auth
+//! ```
+#![cfg(all(not(target_arch = "wasm32"), feature = "reqwest"))]
+
+use oidc_core::{
+    client::{basic_authorization, ClientAuthMethod},
+    OidcClient, OidcConfig,
+};
+use serde_json::json;
+use wiremock::{
+    matchers::{body_string, header, method, path},
+    Mock, MockServer, ResponseTemplate,
+};
+
+/// A client secret with characters that have a meaning in a form body.
+const CLIENT_SECRET: &str = "s3cr&t +/";
+
+/// Returns the configuration of a confidential client of the given mock
+/// provider.
+fn config(server: &MockServer, client_id: &str, method: ClientAuthMethod) -> OidcConfig {
+    OidcConfig {
+        token_endpoint: format!("{}/token", server.uri()),
+        client_id: client_id.to_string(),
+        client_secret: Some(CLIENT_SECRET.to_string()),
+        client_auth_method: method,
+        redirect_uri: "https://app.example.org/callback?next=/orders".to_string(),
+        ..OidcConfig::default()
+    }
+}
+
+fn token_response() -> ResponseTemplate {
+    ResponseTemplate::new(200).set_body_json(json!({
+        "access_token": "access-1",
+        "expires_in": 300,
+        "refresh_token": "refresh-1",
+        "token_type": "Bearer",
+        "id_token": "eyJhbGciOiJSUzI1NiJ9.eyJzdWIiOiJ1c2VyIn0.signature",
+    }))
+}
+
+#[test]
+fn form_encodes_the_credentials_of_the_basic_authorization() {
+    // base64("backend%3Atest:s3cr%26t+%2B%2F")
+    assert_eq!(
+        basic_authorization("backend:test", CLIENT_SECRET),
+        "Basic YmFja2VuZCUzQXRlc3Q6czNjciUyNnQrJTJCJTJG"
+    );
+}
+
+#[tokio::test]
+async fn form_encodes_the_code_exchange_with_the_secret_in_the_body() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/token"))
+        .and(body_string(
+            "grant_type=authorization_code&client_id=backend-test\
+             &redirect_uri=https%3A%2F%2Fapp.example.org%2Fcallback%3Fnext%3D%2Forders\
+             &code=a%26b%2Bc&code_verifier=verifier&client_secret=s3cr%26t+%2B%2F",
+        ))
+        .respond_with(token_response())
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let client = OidcClient::new(config(&server, "backend-test", ClientAuthMethod::Body));
+    let tokens = client
+        .exchange_code("a&b+c", None, Some("verifier"), None)
+        .await
+        .expect("unable to exchange the code");
+
+    assert_eq!(tokens.access_token, "access-1");
+}
+
+#[tokio::test]
+async fn form_encodes_the_refresh_with_the_secret_in_the_header() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/token"))
+        .and(header(
+            "Authorization",
+            "Basic YmFja2VuZCUzQXRlc3Q6czNjciUyNnQrJTJCJTJG",
+        ))
+        .and(body_string(
+            "grant_type=refresh_token&client_id=backend%3Atest&refresh_token=r%2B1%26x",
+        ))
+        .respond_with(token_response())
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let client = OidcClient::new(config(&server, "backend:test", ClientAuthMethod::Basic));
+    let tokens = client
+        .refresh("r+1&x", None)
+        .await
+        .expect("unable to refresh the tokens");
+
+    assert_eq!(tokens.refresh_token.as_deref(), Some("refresh-1"));
+}
@@ -0,0 +1,248 @@
+//! Native tests of the DPoP proof claims and the nonce handling against a
+//! mock identity provider:
+//!
+//! ```sh
+//! cargo test -p oidc_core --test dpop
+//! ```
+#![cfg(all(not(target_arch = "wasm32"), feature = "reqwest"))]
+
+use std::sync::{Arc, Mutex};
+
+use futures::future::{FutureExt, LocalBoxFuture};
+use oidc_core::{
+    client::HttpRequest,
+    dpop::{dpop_nonce, proof_claims, send_with_dpop},
+    DpopProver, OidcClient, OidcConfig, OidcError,
+};
+use serde_json::json;
+use wiremock::{
+    matchers::{header, method, path},
+    Mock, MockServer, ResponseTemplate,
+};
+
+/// An access token and its `ath` claim, from the example of RFC 9449.
+const ACCESS_TOKEN: &str = "Kz~8mXK1EalYznwH-LC-1fBAo.4Ljp~zsPE_NeO.gxU";
+const ACCESS_TOKEN_HASH: &str = "fUHyO2r2Z3DZ53EsNrWBb0xWXoaNy59IiKCAqksmQEo";
+
+/// A prover returning the nonce as proof, which records the requests it
+/// signed.
+#[derive(Debug, Default)]
+struct RecordingProver {
+    requests: Mutex<Vec<(String, String, Option<String>)>>,
+}
+
+impl RecordingProver {
+    fn nonces(&self) -> Vec<Option<String>> {
+        self.requests
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(_, _, nonce)| nonce.clone())
+            .collect()
+    }
+}
+
+impl DpopProver for RecordingProver {
+    fn proof<'a>(
+        &'a self,
+        method: &'a str,
+        url: &'a str,
+        _access_token: Option<&'a str>,
+        nonce: Option<&'a str>,
+    ) -> LocalBoxFuture<'a, Result<String, OidcError>> {
+        self.requests.lock().unwrap().push((
+            method.to_string(),
+            url.to_string(),
+            nonce.map(str::to_string),
+        ));
+        let proof = format!("proof;nonce={}", nonce.unwrap_or("none"));
+        async move { Ok(proof) }.boxed_local()
+    }
+}
+
+/// Returns the configuration of a client of the given mock provider, which
+/// sends the proofs of the prover.
+fn config(server: &MockServer, prover: &Arc<RecordingProver>) -> OidcConfig {
+    OidcConfig {
+        token_endpoint: format!("{}/token", server.uri()),
+        client_id: "backend-test".to_string(),
+        dpop: Some(Arc::clone(prover) as Arc<dyn DpopProver>),
+        ..OidcConfig::default()
+    }
+}
+
+/// Returns the rejection of a proof without the current nonce.
+fn use_dpop_nonce(nonce: &str) -> ResponseTemplate {
+    ResponseTemplate::new(400)
+        .insert_header("DPoP-Nonce", nonce)
+        .set_body_json(json!({
+            "error": "use_dpop_nonce",
+            "error_description": "Authorization server requires nonce in DPoP proof",
+        }))
+}
+
+fn token_response() -> ResponseTemplate {
+    ResponseTemplate::new(200).set_body_json(json!({
+        "access_token": "access-2",
+        "expires_in": 300,
+        "refresh_token": "refresh-2",
+        "token_type": "DPoP",
+        "id_token": "eyJhbGciOiJSUzI1NiJ9.eyJzdWIiOiJ1c2VyIn0.signature",
+    }))
+}
+
+#[test]
+fn binds_the_proof_claims_to_the_request() {
+    let claims = proof_claims(
+        "post",
+        "https://resource.example.org/orders?page=2#top",
+        Some(ACCESS_TOKEN),
+        Some("nonce-1"),
+    )
+    .expect("unable to build the claims");
+
+    assert_eq!(claims["htm"], "POST");
+    assert_eq!(claims["htu"], "https://resource.example.org/orders");
+    assert_eq!(claims["ath"], ACCESS_TOKEN_HASH);
+    assert_eq!(claims["nonce"], "nonce-1");
+    assert!(claims["jti"].as_str().is_some_and(|jti| !jti.is_empty()));
+    assert!(claims["iat"].is_i64());
+}
+
+#[test]
+fn omits_the_token_hash_and_nonce_without_them() {
+    let claims = proof_claims("GET", "https://server.example.com/token", None, None)
+        .expect("unable to build the claims");
+
+    assert_eq!(claims["htm"], "GET");
+    assert_eq!(claims["htu"], "https://server.example.com/token");
+    assert!(claims.get("ath").is_none());
+    assert!(claims.get("nonce").is_none());
+}
+
+#[test]
+fn generates_a_unique_jti() {
+    let first = proof_claims("GET", "https://server.example.com/token", None, None).unwrap();
+    let second = proof_claims("GET", "https://server.example.com/token", None, None).unwrap();
+
+    assert_ne!(first["jti"], second["jti"]);
+}
+
+#[tokio::test]
+async fn retries_the_token_request_with_the_nonce_of_the_provider() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/token"))
+        .and(header("DPoP", "proof;nonce=nonce-1"))
+        .respond_with(token_response())
+        .expect(2)
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/token"))
+        .respond_with(use_dpop_nonce("nonce-1"))
+        .expect(1)
+        .mount(&server)
+        .await;
+    let prover = Arc::new(RecordingProver::default());
+    let client = OidcClient::new(config(&server, &prover));
+
+    let tokens = client.refresh("refresh-1", None).await;
+    assert!(matches!(tokens, Ok(tokens) if tokens.access_token == "access-2"));
+    assert_eq!(prover.nonces(), vec![None, Some("nonce-1".to_string())]);
+
+    // The nonce of the provider is sent with the next proof right away.
+    client
+        .refresh("refresh-2", None)
+        .await
+        .expect("unable to refresh");
+    assert_eq!(prover.nonces()[2].as_deref(), Some("nonce-1"));
+    assert_eq!(
+        dpop_nonce(&format!("{}/token", server.uri())).as_deref(),
+        Some("nonce-1")
+    );
+
+    let (method, url, _) = prover.requests.lock().unwrap()[0].clone();
+    assert_eq!(method, "POST");
+    assert_eq!(url, format!("{}/token", server.uri()));
+}
+
+#[tokio::test]
+async fn retries_the_token_request_only_once() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/token"))
+        .respond_with(use_dpop_nonce("nonce-2"))
+        .expect(2)
+        .mount(&server)
+        .await;
+    let prover = Arc::new(RecordingProver::default());
+    let client = OidcClient::new(config(&server, &prover));
+
+    let result = client.refresh("refresh-1", None).await;
+
+    assert!(matches!(
+        result,
+        Err(OidcError::Provider(response)) if response.error == "use_dpop_nonce"
+    ));
+    assert_eq!(prover.nonces().len(), 2);
+}
+
+#[tokio::test]
+async fn retries_a_resource_request_with_the_nonce_of_the_server() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/orders"))
+        .and(header("DPoP", "proof;nonce=nonce-3"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!([])))
+        .expect(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/orders"))
+        .respond_with(
+            ResponseTemplate::new(401)
+                .insert_header("DPoP-Nonce", "nonce-3")
+                .insert_header("WWW-Authenticate", r#"DPoP error="use_dpop_nonce""#),
+        )
+        .expect(1)
+        .mount(&server)
+        .await;
+    let prover = RecordingProver::default();
+
+    let response = send_with_dpop(
+        &reqwest::Client::new(),
+        &prover,
+        HttpRequest::get(format!("{}/orders", server.uri())),
+        Some(ACCESS_TOKEN),
+    )
+    .await
+    .expect("unable to send the request");
+
+    assert_eq!(response.status, 200);
+    assert_eq!(prover.nonces(), vec![None, Some("nonce-3".to_string())]);
+}
+
+#[tokio::test]
+async fn keeps_an_unauthorized_response_without_nonce_challenge() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/orders"))
+        .respond_with(ResponseTemplate::new(401).insert_header("DPoP-Nonce", "nonce-4"))
+        .expect(1)
+        .mount(&server)
+        .await;
+    let prover = RecordingProver::default();
+
+    let response = send_with_dpop(
+        &reqwest::Client::new(),
+        &prover,
+        HttpRequest::get(format!("{}/orders", server.uri())),
+        Some(ACCESS_TOKEN),
+    )
+    .await
+    .expect("unable to send the request");
+
+    assert_eq!(response.status, 401);
+    assert_eq!(prover.nonces(), vec![None]);
+}
@@ -0,0 +1,600 @@
+//! Native tests of the protocol logic (`OidcClient`, the callback parsing
+//! and the token expiry) against a mock identity provider:
+//!
+//! ```sh
+//! cargo test -p oidc_core --test mock_idp
+//! ```
+#![cfg(all(not(target_arch = "wasm32"), feature = "reqwest"))]
+
+use std::{collections::HashMap, time::Duration};
+
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use chrono::Utc;
+use jsonwebtoken::{encode, Algorithm, EncodingKey, Header};
+use oidc_core::{
+    client::ClientAuthMethod,
+    response::{CallbackResponse, ImplicitCallbackResponse},
+    utils::form_encode,
+    LogoutBehavior, OidcClient, OidcConfig, OidcError, ResponseType, Scopes, TokenStorage,
+    TokenTarget,
+};
+use serde_json::{json, Value};
+use sha2::{Digest, Sha256};
+use url::Url;
+use wiremock::{
+    matchers::{body_string_contains, header, method, path},
+    Mock, MockServer, ResponseTemplate,
+};
+
+const CLIENT_ID: &str = "backend-test";
+const NONCE: &str = "nonce-1";
+
+/// Returns the configuration of a client of the given mock provider.
+fn config(server: &MockServer) -> OidcConfig {
+    OidcConfig {
+        auth_endpoint: format!("{}/auth", server.uri()),
+        token_endpoint: format!("{}/token", server.uri()),
+        logout_endpoint: format!("{}/logout", server.uri()),
+        client_id: CLIENT_ID.to_string(),
+        redirect_uri: "http://localhost:3000/profile".to_string(),
+        post_logout_redirect_uri: "http://localhost:3000/bye".to_string(),
+        ..OidcConfig::default()
+    }
+}
+
+/// Returns an unsigned JWT with the given claims, only its claims are read.
+fn unsigned_jwt(claims: &Value) -> String {
+    let header = URL_SAFE_NO_PAD.encode(r#"{"alg":"RS256","typ":"JWT"}"#);
+    let claims = URL_SAFE_NO_PAD.encode(claims.to_string());
+    format!("{header}.{claims}.signature")
+}
+
+/// The secret of the key set of the mock provider.
+const SIGNING_SECRET: &[u8] = b"secret-of-the-mock-provider";
+
+/// Returns the configuration of a client, which validates the ID tokens
+/// against the key set of the given mock provider, see `mount_jwks`.
+fn validating_config(server: &MockServer) -> OidcConfig {
+    OidcConfig {
+        issuer: Some(server.uri()),
+        jwks_uri: Some(format!("{}/jwks", server.uri())),
+        ..config(server)
+    }
+}
+
+/// Serves the key set of the mock provider.
+async fn mount_jwks(server: &MockServer) {
+    Mock::given(method("GET"))
+        .and(path("/jwks"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "keys": [{
+                "kty": "oct",
+                "kid": "mock",
+                "alg": "HS256",
+                "k": URL_SAFE_NO_PAD.encode(SIGNING_SECRET),
+            }],
+        })))
+        .mount(server)
+        .await;
+}
+
+/// Returns an ID token of the mock provider with the given claims, signed
+/// with the key of `mount_jwks`.
+fn signed_id_token(server: &MockServer, claims: &Value) -> String {
+    let mut claims = claims.clone();
+    claims["iss"] = server.uri().into();
+    claims["aud"] = CLIENT_ID.into();
+    claims["exp"] = (Utc::now().timestamp() + 300).into();
+    let header = Header {
+        kid: Some("mock".to_string()),
+        ..Header::new(Algorithm::HS256)
+    };
+    encode(&header, &claims, &EncodingKey::from_secret(SIGNING_SECRET))
+        .expect("unable to sign the ID token")
+}
+
+/// Returns a successful token response with the given refresh token.
+fn token_response(refresh_token: &str) -> ResponseTemplate {
+    ResponseTemplate::new(200).set_body_json(json!({
+        "access_token": format!("access-{refresh_token}"),
+        "expires_in": 300,
+        "refresh_token": refresh_token,
+        "refresh_expires_in": 1800,
+        "token_type": "Bearer",
+        "id_token": unsigned_jwt(&json!({ "sub": "user", "nonce": NONCE })),
+    }))
+}
+
+/// Returns the decoded query parameters of the URL.
+fn query_pairs(url: &str) -> HashMap<String, String> {
+    Url::parse(url)
+        .expect("invalid URL")
+        .query_pairs()
+        .into_owned()
+        .collect()
+}
+
+/// Returns the form encoded query of a callback with the given parameters.
+fn query(pairs: &[(&str, &str)]) -> String {
+    form_encode(
+        pairs
+            .iter()
+            .map(|(key, value)| ((*key).to_string(), (*value).to_string()))
+            .collect(),
+    )
+}
+
+#[test]
+fn parses_callbacks() {
+    let login = CallbackResponse::from_query("?code=abc&session_state=s", ResponseType::Code);
+    assert!(matches!(
+        login,
+        Ok(CallbackResponse::SuccessLogin(response))
+            if response.code == "abc" && response.session_state.as_deref() == Some("s")
+    ));
+
+    let logout = CallbackResponse::from_query("destroy_session=true&state=xyz", ResponseType::Code);
+    assert!(matches!(
+        logout,
+        Ok(CallbackResponse::SuccessLogout(response))
+            if response.destroy_session && response.state.as_deref() == Some("xyz")
+    ));
+
+    let error = CallbackResponse::from_query(
+        "#error=access_denied&error_description=denied",
+        ResponseType::Code,
+    );
+    assert!(matches!(
+        error,
+        Ok(CallbackResponse::Error(response)) if response.error == "access_denied"
+    ));
+
+    assert!(matches!(
+        CallbackResponse::from_query("page=2", ResponseType::Code),
+        Err(OidcError::InvalidCallback(_))
+    ));
+}
+
+#[tokio::test]
+async fn builds_the_authorization_request() {
+    let server = MockServer::start().await;
+    let client = OidcClient::new(config(&server));
+
+    let request = client
+        .authorization_request(&Scopes::default())
+        .expect("unable to generate the request");
+
+    let query = query_pairs(&request.url);
+    assert!(request.url.starts_with(&format!("{}/auth?", server.uri())));
+    assert_eq!(query["response_type"], "code");
+    assert_eq!(query["client_id"], CLIENT_ID);
+    assert_eq!(query["code_challenge_method"], "S256");
+    assert_eq!(query["nonce"], request.nonce);
+    assert!(!request.url.contains(&request.code_verifier));
+}
+
+#[tokio::test]
+async fn encodes_the_authorization_url() {
+    let server = MockServer::start().await;
+    let client = OidcClient::new(OidcConfig {
+        redirect_uri: "https://app.example.com/callback?tenant=acme".to_string(),
+        login_hint: Some("jane+work@example.com".to_string()),
+        ..config(&server)
+    });
+
+    let url = client.authorization_url(&Scopes::default().custom("orders:read"), None, None);
+
+    let query = query_pairs(&url);
+    assert_eq!(
+        query["redirect_uri"],
+        "https://app.example.com/callback?tenant=acme"
+    );
+    assert_eq!(query["login_hint"], "jane+work@example.com");
+    assert!(query["scope"].ends_with(" orders:read"));
+    assert!(!query.contains_key("tenant"));
+}
+
+#[tokio::test]
+async fn exchanges_the_code() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/token"))
+        .and(body_string_contains("grant_type=authorization_code"))
+        .and(body_string_contains("code=abc"))
+        .and(body_string_contains("code_verifier=verifier"))
+        .respond_with(token_response("refresh-1"))
+        .expect(1)
+        .mount(&server)
+        .await;
+    let client = OidcClient::new(config(&server));
+
+    let tokens = client
+        .exchange_code("abc", Some("session"), Some("verifier"), Some(NONCE))
+        .await
+        .expect("unable to exchange the code");
+
+    assert_eq!(tokens.access_token, "access-refresh-1");
+    assert_eq!(tokens.refresh_token.as_deref(), Some("refresh-1"));
+    assert_eq!(tokens.session_state.as_deref(), Some("session"));
+    assert!(!tokens.access_token_expired());
+}
+
+#[tokio::test]
+async fn rejects_another_nonce() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/token"))
+        .respond_with(token_response("refresh-1"))
+        .mount(&server)
+        .await;
+    let client = OidcClient::new(config(&server));
+
+    let result = client
+        .exchange_code("abc", None, None, Some("another-nonce"))
+        .await;
+
+    assert!(matches!(result, Err(OidcError::Nonce)));
+}
+
+#[tokio::test]
+async fn refreshes_and_rotates_the_refresh_token() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/token"))
+        .and(body_string_contains("grant_type=refresh_token"))
+        .and(body_string_contains("refresh_token=refresh-1"))
+        .respond_with(token_response("refresh-2"))
+        .expect(1)
+        .mount(&server)
+        .await;
+    let client = OidcClient::new(config(&server));
+    let previous = TokenStorage {
+        id_token: "id".to_string(),
+        access_token: "access".to_string(),
+        expires_in: Utc::now().naive_utc(),
+        refresh_token: Some("refresh-1".to_string()),
+        ..TokenStorage::default()
+    };
+
+    let tokens = client
+        .refresh("refresh-1", Some(&previous))
+        .await
+        .expect("unable to refresh");
+
+    assert_eq!(tokens.refresh_token.as_deref(), Some("refresh-2"));
+    assert_eq!(tokens.refresh_generation, previous.refresh_generation + 1);
+}
+
+#[tokio::test]
+async fn reports_a_rejected_refresh_token() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/token"))
+        .respond_with(ResponseTemplate::new(400).set_body_json(json!({
+            "error": "invalid_grant",
+            "error_description": "Token is not active",
+        })))
+        .mount(&server)
+        .await;
+    let client = OidcClient::new(config(&server));
+
+    let result = client.refresh("expired", None).await;
+
+    assert!(matches!(result, Err(OidcError::InvalidGrant(_))));
+}
+
+#[tokio::test]
+async fn authenticates_confidential_clients() {
+    let server = MockServer::start().await;
+    let credentials =
+        base64::engine::general_purpose::STANDARD.encode(format!("{CLIENT_ID}:secret"));
+    Mock::given(method("POST"))
+        .and(path("/token"))
+        .and(header(
+            "Authorization",
+            format!("Basic {credentials}").as_str(),
+        ))
+        .respond_with(token_response("refresh-1"))
+        .expect(1)
+        .mount(&server)
+        .await;
+    let client = OidcClient::new(OidcConfig {
+        client_secret: Some("secret".to_string()),
+        client_auth_method: ClientAuthMethod::Basic,
+        ..config(&server)
+    });
+
+    client
+        .refresh("refresh-1", None)
+        .await
+        .expect("unable to refresh");
+}
+
+/// Returns the `at_hash` or `c_hash` of the value for an `RS256` ID token.
+fn token_hash(value: &str) -> String {
+    URL_SAFE_NO_PAD.encode(&Sha256::digest(value.as_bytes())[..16])
+}
+
+#[tokio::test]
+async fn pushes_the_authorization_request() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/par"))
+        .and(body_string_contains("response_type=code"))
+        .and(body_string_contains("scope=openid"))
+        .and(body_string_contains(format!("nonce={NONCE}")))
+        .and(body_string_contains("login_hint=jane%40example.com"))
+        .respond_with(ResponseTemplate::new(201).set_body_json(json!({
+            "request_uri": "urn:ietf:params:oauth:request_uri:abc",
+            "expires_in": 60,
+        })))
+        .expect(1)
+        .mount(&server)
+        .await;
+    let client = OidcClient::new(OidcConfig {
+        pushed_authorization_request_endpoint: Some(format!("{}/par", server.uri())),
+        login_hint: Some("jane@example.com".to_string()),
+        ..config(&server)
+    });
+
+    let url = client
+        .push_authorization_request(&Scopes::default(), Some("verifier"), Some(NONCE))
+        .await
+        .expect("unable to push the request");
+
+    let query = query_pairs(&url);
+    assert!(url.starts_with(&format!("{}/auth?", server.uri())));
+    assert_eq!(query.len(), 2);
+    assert_eq!(query["client_id"], CLIENT_ID);
+    assert_eq!(
+        query["request_uri"],
+        "urn:ietf:params:oauth:request_uri:abc"
+    );
+}
+
+#[tokio::test]
+async fn completes_the_implicit_flow() {
+    let server = MockServer::start().await;
+    mount_jwks(&server).await;
+    let client = OidcClient::new(OidcConfig {
+        response_type: ResponseType::IdTokenToken,
+        ..validating_config(&server)
+    });
+
+    let url = client.authorization_url(&Scopes::default(), None, Some(NONCE));
+    assert_eq!(query_pairs(&url)["response_type"], "id_token token");
+
+    let id_token = signed_id_token(
+        &server,
+        &json!({
+            "sub": "user",
+            "nonce": NONCE,
+            "at_hash": token_hash("access"),
+        }),
+    );
+    let callback_query = query(&[
+        ("access_token", "access"),
+        ("id_token", &id_token),
+        ("expires_in", "300"),
+    ]);
+    // The code flow doesn't accept tokens in the callback.
+    assert!(CallbackResponse::from_query(&callback_query, ResponseType::Code).is_err());
+    let callback = CallbackResponse::from_query(&callback_query, ResponseType::IdTokenToken);
+    let Ok(CallbackResponse::SuccessImplicitLogin(response)) = callback else {
+        panic!("unexpected callback {callback:?}");
+    };
+
+    let tokens = client
+        .complete_implicit(response.clone(), None, Some(NONCE))
+        .await
+        .expect("unable to complete the login");
+    assert_eq!(tokens.access_token, "access");
+    assert_eq!(tokens.refresh_token, None);
+
+    let forged = ImplicitCallbackResponse {
+        access_token: Some("forged".to_string()),
+        ..response.clone()
+    };
+    let result = client.complete_implicit(forged, None, Some(NONCE)).await;
+    assert!(matches!(result, Err(OidcError::TokenHash("at_hash"))));
+
+    // An ID token injected into the URL without the signature of the
+    // provider is rejected.
+    let unsigned = ImplicitCallbackResponse {
+        id_token: Some(unsigned_jwt(&json!({
+            "sub": "attacker",
+            "nonce": NONCE,
+            "at_hash": token_hash("access"),
+        }))),
+        ..response
+    };
+    let result = client.complete_implicit(unsigned, None, Some(NONCE)).await;
+    assert!(result.is_err());
+}
+
+#[tokio::test]
+async fn exchanges_the_code_of_the_hybrid_flow() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/token"))
+        .and(body_string_contains("code=abc"))
+        .respond_with(token_response("refresh"))
+        .expect(1)
+        .mount(&server)
+        .await;
+    mount_jwks(&server).await;
+    let client = OidcClient::new(OidcConfig {
+        response_type: ResponseType::CodeIdToken,
+        ..validating_config(&server)
+    });
+
+    let id_token = signed_id_token(
+        &server,
+        &json!({
+            "sub": "user",
+            "nonce": NONCE,
+            "c_hash": token_hash("abc"),
+        }),
+    );
+    let callback_query = query(&[("code", "abc"), ("id_token", &id_token)]);
+    assert!(matches!(
+        CallbackResponse::from_query(&callback_query, ResponseType::Code),
+        Ok(CallbackResponse::SuccessLogin(_))
+    ));
+    let callback = CallbackResponse::from_query(&callback_query, ResponseType::CodeIdToken);
+    let Ok(CallbackResponse::SuccessImplicitLogin(response)) = callback else {
+        panic!("unexpected callback {callback:?}");
+    };
+
+    let tokens = client
+        .complete_implicit(response.clone(), None, Some(NONCE))
+        .await
+        .expect("unable to exchange the code");
+    assert_eq!(tokens.refresh_token.as_deref(), Some("refresh"));
+
+    let forged = ImplicitCallbackResponse {
+        code: Some("other".to_string()),
+        ..response
+    };
+    let result = client.complete_implicit(forged, None, Some(NONCE)).await;
+    assert!(matches!(result, Err(OidcError::TokenHash("c_hash"))));
+}
+
+#[tokio::test]
+async fn exchanges_the_token_for_an_audience() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/token"))
+        .and(body_string_contains(
+            "grant_type=urn%3Aietf%3Aparams%3Aoauth%3Agrant-type%3Atoken-exchange",
+        ))
+        .and(body_string_contains("subject_token=access"))
+        .and(body_string_contains("audience=orders"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "access_token": "orders-token",
+            "issued_token_type": "urn:ietf:params:oauth:token-type:access_token",
+            "token_type": "Bearer",
+            "expires_in": 300,
+        })))
+        .expect(1)
+        .mount(&server)
+        .await;
+    let client = OidcClient::new(config(&server));
+
+    let token = client
+        .exchange_token(
+            &TokenTarget::Audience("orders".to_string()),
+            "access",
+            &Scopes::new(),
+        )
+        .await
+        .expect("unable to exchange the token");
+
+    assert_eq!(token.access_token, "orders-token");
+    assert!(token.expires_at.is_some());
+}
+
+#[tokio::test]
+async fn exchanges_the_token_for_a_scoped_resource() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/token"))
+        .and(body_string_contains(
+            "resource=https%3A%2F%2Fapi.example.com%2Forders%3Ftenant%3Dacme",
+        ))
+        .and(body_string_contains("scope=orders%3Aread+orders%3Awrite"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "access_token": "api-token",
+            "issued_token_type": "urn:ietf:params:oauth:token-type:access_token",
+            "token_type": "Bearer",
+            "expires_in": 300,
+        })))
+        .expect(1)
+        .mount(&server)
+        .await;
+    let client = OidcClient::new(config(&server));
+
+    let token = client
+        .exchange_token(
+            &TokenTarget::Resource("https://api.example.com/orders?tenant=acme".to_string()),
+            "access",
+            &Scopes::new().custom("orders:read").custom("orders:write"),
+        )
+        .await
+        .expect("unable to exchange the token");
+
+    assert_eq!(token.access_token, "api-token");
+}
+
+#[test]
+fn detects_expired_tokens() {
+    let now = Utc::now().naive_utc();
+    let tokens = TokenStorage {
+        expires_in: now - chrono::Duration::seconds(30),
+        refresh_expires_in: Some(now - chrono::Duration::seconds(1)),
+        ..TokenStorage::default()
+    };
+
+    assert!(tokens.access_token_expired());
+    // The leeway tolerates a clock skew, the token is still accepted.
+    assert!(!tokens.access_token_expired_with(Duration::from_secs(60)));
+    assert!(tokens.refresh_token_expired_with(Duration::ZERO));
+}
+
+#[test]
+fn clamps_the_refresh_window_to_the_token_lifetime() {
+    let now = Utc::now().naive_utc();
+    let fresh = TokenStorage {
+        expires_in: now + chrono::Duration::seconds(60),
+        issued_at: Some(now),
+        ..TokenStorage::default()
+    };
+    let aged = TokenStorage {
+        expires_in: now + chrono::Duration::seconds(20),
+        issued_at: Some(now - chrono::Duration::seconds(40)),
+        ..TokenStorage::default()
+    };
+
+    assert!(!fresh.access_token_expires_within(Duration::from_secs(120)));
+    assert!(aged.access_token_expires_within(Duration::from_secs(120)));
+}
+
+#[tokio::test]
+async fn builds_the_logout_url() {
+    let server = MockServer::start().await;
+    let client = OidcClient::new(config(&server));
+
+    let url = client.logout_url(Some("id-token"), Some("state"), None, None);
+    assert!(url.starts_with(&format!("{}/logout?", server.uri())));
+    let query = query_pairs(&url);
+    assert_eq!(
+        query.get("id_token_hint").map(String::as_str),
+        Some("id-token")
+    );
+    // The state belongs to the post logout redirect URI, not to the provider.
+    assert!(!query.contains_key("state"));
+    let redirect_uri = query
+        .get("post_logout_redirect_uri")
+        .expect("missing post_logout_redirect_uri");
+    assert!(redirect_uri.starts_with("http://localhost:3000/bye?"));
+    let redirect_query = query_pairs(redirect_uri);
+    assert_eq!(
+        redirect_query.get("state").map(String::as_str),
+        Some("state")
+    );
+    assert_eq!(
+        redirect_query.get("destroy_session").map(String::as_str),
+        Some("true")
+    );
+
+    // Without an ID token, the provider would show an error page.
+    let url = client.logout_url(None, None, None, None);
+    assert!(url.starts_with("http://localhost:3000/bye"));
+
+    let client = OidcClient::new(OidcConfig {
+        logout_behavior: LogoutBehavior::LocalOnly,
+        ..config(&server)
+    });
+    let url = client.logout_url(Some("id-token"), None, None, None);
+    assert!(url.starts_with("http://localhost:3000/bye"));
+}
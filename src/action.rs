@@ -0,0 +1,69 @@
+use std::future::Future;
+
+use futures::future::{AbortHandle, Abortable};
+use leptos::*;
+use leptos_oidc2::try_use_auth;
+
+/// Runs the app's server interactions (send, upload, export) as cancellable
+/// tasks. Every task is aborted when the owning component is unmounted, e.g.
+/// by navigating away, or when the user logs out. Dropping the aborted
+/// future drops the in-flight request with it.
+#[derive(Clone, Copy)]
+pub struct CancellableAction {
+    handles: StoredValue<Vec<AbortHandle>>,
+    pending: RwSignal<usize>,
+}
+
+impl CancellableAction {
+    /// Spawns the given future as a cancellable task.
+    pub fn dispatch(&self, future: impl Future<Output = ()> + 'static) {
+        let (handle, registration) = AbortHandle::new_pair();
+        self.handles.update_value(|handles| {
+            handles.retain(|handle| !handle.is_aborted());
+            handles.push(handle);
+        });
+        let pending = self.pending;
+        pending.update(|pending| *pending += 1);
+        spawn_local(async move {
+            // An aborted task was already counted down by `cancel`.
+            if Abortable::new(future, registration).await.is_ok() {
+                pending.update(|pending| *pending -= 1);
+            }
+        });
+    }
+
+    /// Aborts every in-flight task.
+    pub fn cancel(&self) {
+        self.handles.update_value(|handles| {
+            for handle in handles.drain(..) {
+                handle.abort();
+            }
+        });
+        self.pending.set(0);
+    }
+
+    /// Returns `true` as long as a task is in flight.
+    pub fn pending(&self) -> Signal<bool> {
+        let pending = self.pending;
+        Signal::derive(move || pending.get() > 0)
+    }
+}
+
+/// Creates a `CancellableAction`, which is cancelled on unmount and on logout.
+pub fn use_cancellable_action() -> CancellableAction {
+    let action = CancellableAction {
+        handles: store_value(Vec::new()),
+        pending: create_rw_signal(0),
+    };
+
+    if let Some(auth) = try_use_auth() {
+        create_effect(move |_| {
+            if !auth.loading() && !auth.authenticated() {
+                action.cancel();
+            }
+        });
+    }
+    on_cleanup(move || action.cancel());
+
+    action
+}
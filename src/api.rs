@@ -1,8 +1,140 @@
 use leptos::*;
-use crate::model::data::{Data, Datas};
+use leptos_oidc2::{use_auth, Auth};
+use reqwest::{Method, RequestBuilder};
+use serde::de::DeserializeOwned;
+use thiserror::Error;
 
+use crate::model::data::{Data, Datas, Page};
+use crate::profiles::Profile;
+
+/// The base URL of the backend, overridable at compile time.
+const BACKEND_URL: &str = match option_env!("BACKEND_URL") {
+    Some(url) => url,
+    None => "http://localhost:8080",
+};
+
+/// The errors of a backend request. They're `Clone`, so they can be kept in
+/// resources and signals.
+#[derive(Error, Debug, Clone, PartialEq)]
+pub enum ApiError {
+    #[error("You are not signed in")]
+    Unauthenticated,
+    #[error("The backend is unreachable: {0}")]
+    Request(String),
+    #[error("The backend responded with status {0}")]
+    Status(u16),
+}
+
+impl From<reqwest::Error> for ApiError {
+    fn from(error: reqwest::Error) -> Self {
+        ApiError::Request(error.to_string())
+    }
+}
+
+/// A client of the backend, which sends the access token of the current user
+/// with every request. It's shared via context, see `provide_api_client`.
+#[derive(Clone)]
+pub struct ApiClient {
+    auth: Auth,
+    http: reqwest::Client,
+}
+
+impl ApiClient {
+    pub fn new(auth: Auth) -> Self {
+        ApiClient {
+            auth,
+            http: reqwest::Client::new(),
+        }
+    }
+
+    /// Starts a request to the given backend path with the access token.
+    fn request(&self, method: Method, path: &str) -> Result<RequestBuilder, ApiError> {
+        let access_token = self.auth.access_token().ok_or(ApiError::Unauthenticated)?;
+
+        Ok(self
+            .http
+            .request(method, format!("{BACKEND_URL}{path}"))
+            .bearer_auth(access_token))
+    }
+
+    /// Sends the request and decodes the JSON response.
+    async fn send<T: DeserializeOwned>(request: RequestBuilder) -> Result<T, ApiError> {
+        let response = request.send().await?;
+        if !response.status().is_success() {
+            return Err(ApiError::Status(response.status().as_u16()));
+        }
+
+        Ok(response.json().await?)
+    }
+}
+
+/// Provides the `ApiClient` of the current `Auth` instance.
+pub fn provide_api_client() -> ApiClient {
+    let client = ApiClient::new(use_auth());
+    provide_context(client.clone());
+
+    client
+}
+
+/// Returns the `ApiClient` provided by `provide_api_client`.
+pub fn use_api_client() -> ApiClient {
+    expect_context::<ApiClient>()
+}
+
+/// Loads the messages of the current user.
+pub async fn list_datas(client: &ApiClient) -> Result<Datas, ApiError> {
+    // The demo has no backend, it loads the seeded messages.
+    #[cfg(feature = "demo")]
+    {
+        let _ = client;
+        Ok(crate::demo::seed_datas())
+    }
+
+    #[cfg(not(feature = "demo"))]
+    {
+        let request = client.request(Method::GET, "/datas")?;
+        ApiClient::send::<Page<Data>>(request)
+            .await
+            .map(Datas::from)
+    }
+}
+
+/// Stores a new message and returns it as stored by the backend.
+pub async fn create_data(client: &ApiClient, data: &Data) -> Result<Data, ApiError> {
+    #[cfg(feature = "demo")]
+    {
+        let _ = client;
+        Ok(data.clone())
+    }
+
+    #[cfg(not(feature = "demo"))]
+    {
+        let request = client.request(Method::POST, "/datas")?.json(data);
+        ApiClient::send(request).await
+    }
+}
+
+/// Resolves the profiles of the given subjects in one request. Unknown
+/// subjects are left out.
+pub async fn resolve_profiles(
+    client: &ApiClient,
+    subs: &[String],
+) -> Result<Vec<Profile>, ApiError> {
+    #[cfg(feature = "demo")]
+    {
+        let _ = client;
+        Ok(crate::demo::demo_profiles(subs))
+    }
+
+    #[cfg(not(feature = "demo"))]
+    {
+        let query = subs.iter().map(|sub| ("sub", sub)).collect::<Vec<_>>();
+        let request = client.request(Method::GET, "/profiles")?.query(&query);
+        ApiClient::send(request).await
+    }
+}
 
 #[server(Converse, "/api")]
- pub async fn converse( prompt: Datas) -> Result<String, ServerFnError> {
-todo!()
-} 
+pub async fn converse(prompt: Datas) -> Result<String, ServerFnError> {
+    todo!()
+}
@@ -1,80 +1,165 @@
-use crate::error_template::{AppError, ErrorTemplate};
+use crate::action::use_cancellable_action;
+use crate::api::{create_data, list_datas, provide_api_client, use_api_client};
+use crate::error_template::{AppError, AuthErrorBoundary, ErrorTemplate};
 use crate::model::data::{Data, Datas};
+use crate::profiles::{provide_profiles, Avatar};
+use crate::toast::{provide_toasts, use_toasts, ToastHost};
 use leptos::*;
 use leptos_meta::*;
-use leptos_oidc2::{Auth, AuthParameters};
+use leptos_oidc2::{use_auth, Auth, AuthParameters};
 use leptos_router::*;
 use std::collections::HashMap;
+
+/// Reads the given variable at compile time, like `BACKEND_URL`, falling back
+/// to the default.
+macro_rules! env_or {
+    ($key:literal, $default:literal) => {
+        match option_env!($key) {
+            Some(value) => value,
+            None => $default,
+        }
+    };
+}
+
+/// The OIDC parameters of the app, set at compile time. They default to the
+/// Auth0 sample tenant.
+const OIDC_AUTH_ENDPOINT: &str =
+    env_or!("OIDC_AUTH_ENDPOINT", "https://samples.auth0.com/authorize");
+const OIDC_TOKEN_ENDPOINT: &str = env_or!(
+    "OIDC_TOKEN_ENDPOINT",
+    "https://samples.auth0.com/oauth/token"
+);
+const OIDC_LOGOUT_ENDPOINT: &str = env_or!(
+    "OIDC_LOGOUT_ENDPOINT",
+    "https://ENDPOINT/auth/realms/REALM/protocol/openid-connect/logout"
+);
+const OIDC_CLIENT_ID: &str = env_or!("OIDC_CLIENT_ID", "kbyuFDidLLm280LIwVFiazOqjO3ty8KH");
+const OIDC_REDIRECT_URI: &str = env_or!("OIDC_REDIRECT_URI", "http://localhost:3000/profile");
+const OIDC_POST_LOGOUT_REDIRECT_URI: &str =
+    env_or!("OIDC_POST_LOGOUT_REDIRECT_URI", "http://localhost:3000/bye");
+const OIDC_SCOPE: &str = env_or!("OIDC_SCOPE", "openid profile email phone address");
  
 
 #[component]
 pub fn App() -> impl IntoView {
     // Provides context that manages stylesheets, titles, meta tags, etc.
     provide_meta_context();
+    provide_toasts();
 
-    let (datas, set_datas) = create_signal(Datas::new());
-
-    /* let send = create_action(|new_data: &String| async {
-        todo!()
-
-      //  set_datas.update(move |c| c.datas.push(user_data))
-    }); */
+    #[cfg(feature = "demo")]
+    let datas = create_rw_signal(crate::demo::seed_datas());
+    #[cfg(not(feature = "demo"))]
+    let datas = create_rw_signal(Datas::new());
     view! {
         <Stylesheet id="leptos" href="/pkg/backend-test.css"/>
 
         // sets the document title
         <Title text="Ux-ti.com"/>
+        <ToastHost/>
         <Router>
-            <AppWithRouter/>
-            <MyComponent/>
+            <AppWithRouter datas=datas/>
+            <MyComponent datas=datas/>
         </Router>
     }
 }
 
 #[component]
-pub fn AppWithRouter() -> impl IntoView {
-    // Specify OIDC authentication parameters here.
-    // Note: This is an example for keycloak, please change it to your needs
-    let auth_parameters = AuthParameters {
-        auth_endpoint: "https://samples.auth0.com/authorize".to_string(),
-        token_endpoint: "https://samples.auth0.com/oauth/token".to_string(),
-        logout_endpoint: "https://ENDPOINT/auth/realms/REALM/protocol/openid-connect/logout"
-            .to_string(),
-        client_id: "kbyuFDidLLm280LIwVFiazOqjO3ty8KH".to_string(),
-        redirect_uri: "http://localhost:3000/profile".to_string(),
-        post_logout_redirect_uri: "http://localhost:3000/bye".to_string(),
-        scope: Some("openid profile email phone address".to_owned()),
-    };
+pub fn AppWithRouter(datas: RwSignal<Datas>) -> impl IntoView {
+    // The OIDC authentication parameters are set at compile time, see the
+    // `OIDC_*` constants.
+    let auth_parameters = AuthParameters::builder()
+        .auth_endpoint(OIDC_AUTH_ENDPOINT)
+        .token_endpoint(OIDC_TOKEN_ENDPOINT)
+        .logout_endpoint(OIDC_LOGOUT_ENDPOINT)
+        .client_id(OIDC_CLIENT_ID)
+        .redirect_uri(OIDC_REDIRECT_URI)
+        .post_logout_redirect_uri(OIDC_POST_LOGOUT_REDIRECT_URI)
+        .scope(OIDC_SCOPE)
+        .build()
+        .expect("invalid auth parameters");
+    #[cfg(feature = "demo")]
+    crate::demo::start_demo_session();
     let auth = Auth::init(auth_parameters);
+    provide_api_client();
+    provide_profiles();
 
     view! {
-        <div></div>
-
-        <Routes>
-            <Route path="/" view=move || view! { <Home/> }/>
-
-            // This is an example route for your profile, it will render
-            // loading if it's still loading, render unauthenticated if it's
-            // unauthenticated and it will render the children, if it's
-            // authenticated
-            <Route
-                path="/profile"
-                view=move || {
-                    view! {}
+        <AuthErrorBoundary>
+            <Routes>
+                <Route path="/" view=move || view! { <Home datas=datas/> }/>
+
+                // This is an example route for your profile, it will render
+                // loading if it's still loading, render unauthenticated if it's
+                // unauthenticated and it will render the children, if it's
+                // authenticated
+                <Route
+                    path="/profile"
+                    view=move || {
+                        view! {}
+                    }
+                />
+
+            </Routes>
+        </AuthErrorBoundary>
+    }
+}
+
+/// Renders the messages with the avatar of their author.
+#[component]
+pub fn MessageList(#[prop(into)] datas: Signal<Datas>) -> impl IntoView {
+    view! {
+        <ul class="messages">
+            <For
+                each=move || datas.get().data.into_iter().enumerate()
+                key=|(index, _)| *index
+                children=|(_, data): (usize, Data)| {
+                    view! {
+                        <li class:user=data.user>
+                            {data.sub.map(|sub| view! { <Avatar sub=sub/> })}
+                            <span>{data.text}</span>
+                        </li>
+                    }
                 }
             />
-
-        </Routes>
+        </ul>
     }
 }
 
+/// Loads the messages of the current user from the backend into `datas`.
 #[component]
-pub fn Home() -> impl IntoView {
-    let auth = expect_context::<Auth>();
+pub fn Home(datas: RwSignal<Datas>) -> impl IntoView {
+    let auth = use_auth();
+    let client = use_api_client();
+    let loaded = create_local_resource(
+        move || auth.authenticated(),
+        move |authenticated| {
+            let client = client.clone();
+            async move {
+                if !authenticated {
+                    return Ok(Datas::new());
+                }
+                list_datas(&client).await
+            }
+        },
+    );
+    create_effect(move |_| {
+        if let Some(Ok(loaded)) = loaded.get() {
+            datas.set(loaded);
+        }
+    });
 
     view! {
         <Title text="Home"/>
         <h1>Home</h1>
+        <Transition fallback=move || view! { <p>"Loading messages..."</p> }>
+            {move || {
+                loaded
+                    .get()
+                    .and_then(Result::err)
+                    .map(|error| view! { <p class="error">{error.to_string()}</p> })
+            }}
+            <MessageList datas=datas/>
+        </Transition>
     }
 }
 
@@ -106,13 +191,16 @@ pub fn Profile() -> impl IntoView {
 }
 
 #[component]
-fn MyComponent() -> impl IntoView {
+fn MyComponent(datas: RwSignal<Datas>) -> impl IntoView {
     let (name, set_name) = create_signal("Uncontrolled".to_string());
     let (count, set_count) = create_signal(String::new());
-    let auth = expect_context::<Auth>();
+    let auth = use_auth();
+    let action = use_cancellable_action();
+    let send = use_send_message(datas);
+    let (message, set_message) = create_signal(String::new());
 
     // Generate the login URL to initiate the authentication process.
-    let login_url = auth.clone().login_url().clone();
+    let login_url = auth.clone().login_url().unwrap_or_default();
     //let login_url = move || login_url;
     //println!("{:?}", login_url);
 
@@ -124,19 +212,81 @@ fn MyComponent() -> impl IntoView {
     view! {
         <button>count()</button>
         <A href=name>Login</A>
-        <button on:click=move |_| {
-            spawn_local(async {
+        <a id="logout" href=logout_url>"Logout"</a>
+        <button id="refresh" on:click={
+            let auth = auth.clone();
+            move |_| auth.refresh_token()
+        }>"Refresh"</button>
+        <button disabled=action.pending() on:click=move |_| {
+            action.dispatch(async {
                 add_todo("So much to do!".to_string()).await;
             });
         }>"Add Todo"</button>
+        <button on:click=move |_| action.cancel()>"Cancel"</button>
+        <form on:submit=move |ev| {
+            ev.prevent_default();
+            let text = message.get_untracked();
+            if !text.trim().is_empty() {
+                send.dispatch(text);
+                set_message(String::new());
+            }
+        }>
+            <input
+                type="text"
+                placeholder="Message"
+                prop:value=message
+                on:input=move |ev| set_message(event_target_value(&ev))
+            />
+            <button type="submit">"Send"</button>
+        </form>
     }
 }
 
+/// Creates the action sending a message of the current user. The message is
+/// shown right away and replaced by the stored one once the backend confirms
+/// it. If sending fails, it's removed again and an error toast is shown.
+fn use_send_message(datas: RwSignal<Datas>) -> Action<String, ()> {
+    let auth = use_auth();
+    let client = use_api_client();
+    let toasts = use_toasts();
+
+    create_action(move |text: &String| {
+        let data = Data {
+            user: true,
+            text: text.clone(),
+            sub: auth.subject(),
+        };
+        datas.update(|datas| datas.data.push(data.clone()));
+        let client = client.clone();
+        async move {
+            let result = create_data(&client, &data).await;
+            datas.update(|datas| {
+                let Some(index) = datas.data.iter().rposition(|pending| *pending == data) else {
+                    return;
+                };
+                match &result {
+                    Ok(stored) => datas.data[index] = stored.clone(),
+                    Err(_) => {
+                        datas.data.remove(index);
+                    }
+                }
+            });
+            if let Err(error) = result {
+                toasts.error(format!("Unable to send the message: {error}"));
+            }
+        }
+    })
+}
+
 
 
 
 #[server(AddTodo, "/api")]
 pub async fn add_todo(title: String) -> Result<(), ServerFnError> {
+    // The demo doesn't send any request.
+    if cfg!(feature = "demo") {
+        return Ok(());
+    }
     let resp = reqwest::get("https://httpbin.org/ip")
         .await?
         .json::<HashMap<String, String>>()
@@ -0,0 +1,90 @@
+use chrono::{Duration, NaiveDateTime, Utc};
+use leptos_oidc2::{
+    preview::{set_preview, Preview},
+    storage::TokenStorage,
+    AuthState,
+};
+
+use crate::model::data::{Data, Datas};
+use crate::profiles::Profile;
+
+/// The `sub` claim of the faked demo user.
+pub const DEMO_SUB: &str = "demo-user";
+
+/// An unsigned ID token of the demo user, only its claims are read.
+const DEMO_ID_TOKEN: &str = "eyJhbGciOiJIUzI1NiIsInR5cCI6IkpXVCJ9.eyJzdWIiOiJkZW1vLXVzZXIiLCJuYW1lIjoiRGVtbyBVc2VyIiwicHJlZmVycmVkX3VzZXJuYW1lIjoiZGVtbyIsImVtYWlsIjoiZGVtb0BleGFtcGxlLmNvbSJ9.demo";
+
+/// The other authors of the seeded messages.
+const DEMO_AUTHORS: [(&str, &str); 2] =
+    [("demo-ada", "Ada Lovelace"), ("demo-alan", "Alan Turing")];
+
+/// The texts of the seeded messages.
+const DEMO_MESSAGES: [&str; 6] = [
+    "Welcome to the demo!",
+    "Nothing here talks to a backend or an identity provider.",
+    "Hi! Does the avatar show my initials?",
+    "It does, the profiles are faked as well.",
+    "Then let's add a todo.",
+    "Go ahead, it won't leave the browser.",
+];
+
+/// Fakes an authenticated session of the demo user, so no request is sent to
+/// the identity provider. Has to be called before `Auth::init`.
+pub fn start_demo_session() {
+    let now = Utc::now().naive_utc();
+    let tokens = demo_tokens(now);
+    set_preview(Preview {
+        state: AuthState::Authenticated(tokens.clone()),
+        now,
+        refresh_response: Ok(tokens),
+    });
+}
+
+fn demo_tokens(now: NaiveDateTime) -> TokenStorage {
+    TokenStorage {
+        id_token: DEMO_ID_TOKEN.to_string(),
+        access_token: "demo-access-token".to_string(),
+        expires_in: now + Duration::hours(1),
+        refresh_token: Some("demo-refresh-token".to_string()),
+        refresh_expires_in: Some(now + Duration::days(1)),
+        ..TokenStorage::default()
+    }
+}
+
+/// Returns generated messages, alternating between the demo user and the
+/// other authors.
+pub fn seed_datas() -> Datas {
+    let data = DEMO_MESSAGES
+        .iter()
+        .enumerate()
+        .map(|(index, text)| {
+            let user = index % 2 == 0;
+            let sub = if user {
+                DEMO_SUB
+            } else {
+                DEMO_AUTHORS[index / 2 % DEMO_AUTHORS.len()].0
+            };
+            Data {
+                user,
+                text: text.to_string(),
+                sub: Some(sub.to_string()),
+            }
+        })
+        .collect();
+
+    Datas { data }
+}
+
+/// Returns the profiles of the given demo authors, unknown subjects are
+/// skipped.
+pub fn demo_profiles(subs: &[String]) -> Vec<Profile> {
+    DEMO_AUTHORS
+        .iter()
+        .filter(|(sub, _)| subs.iter().any(|requested| requested == sub))
+        .map(|(sub, name)| Profile {
+            sub: sub.to_string(),
+            name: name.to_string(),
+            avatar_url: None,
+        })
+        .collect()
+}
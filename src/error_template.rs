@@ -1,6 +1,7 @@
 use cfg_if::cfg_if;
 use http::status::StatusCode;
 use leptos::*;
+use leptos_oidc2::{try_use_auth, use_auth, AuthError};
 use thiserror::Error;
 
 #[cfg(feature = "ssr")]
@@ -10,16 +11,64 @@ use leptos_axum::ResponseOptions;
 pub enum AppError {
     #[error("Not Found")]
     NotFound,
+    #[error("Authentication failed: {0}")]
+    Auth(AuthError),
 }
 
 impl AppError {
     pub fn status_code(&self) -> StatusCode {
         match self {
             AppError::NotFound => StatusCode::NOT_FOUND,
+            AppError::Auth(error) if error.is_transient() => StatusCode::SERVICE_UNAVAILABLE,
+            AppError::Auth(_) => StatusCode::UNAUTHORIZED,
         }
     }
 }
 
+/// Renders the children, unless loading the authentication state failed, e.g.
+/// because the code exchange was rejected. Then the `ErrorTemplate` is shown
+/// with a retry and a login action, instead of silently rendering the
+/// unauthenticated state.
+#[component]
+pub fn AuthErrorBoundary(children: ChildrenFn) -> impl IntoView {
+    let auth = use_auth();
+    // The children are only re-rendered if the state switches between failed
+    // and not failed, not on every refresh of the tokens.
+    let failed = create_memo({
+        let auth = auth.clone();
+        move |_| auth.err().is_some()
+    });
+
+    view! {
+        <ErrorBoundary fallback=|errors| view! { <ErrorTemplate errors=errors/> }>
+            {
+                let children = children.clone();
+                let auth = auth.clone();
+                move || match failed.get().then(|| untrack(|| auth.err())).flatten() {
+                    Some(error) => Err(AppError::Auth(error)),
+                    None => Ok(children()),
+                }
+            }
+        </ErrorBoundary>
+    }
+}
+
+/// The actions offered for a failed authentication: a retry, which reloads
+/// the page, for temporary failures and a new login in any case.
+#[component]
+fn AuthErrorActions(transient: bool) -> impl IntoView {
+    let auth = try_use_auth();
+
+    view! {
+        <Show when=move || transient>
+            <button on:click=move |_| {
+                let _ = window().location().reload();
+            }>"Retry"</button>
+        </Show>
+        {auth.map(|auth| view! { <button on:click=move |_| auth.login()>"Log in again"</button> })}
+    }
+}
+
 // A basic function to display errors served by the error boundaries.
 // Feel free to do more complicated things here than just displaying the error.
 #[component]
@@ -64,9 +113,14 @@ pub fn ErrorTemplate(
             children=move |error| {
                 let error_string = error.1.to_string();
                 let error_code= error.1.status_code();
+                let actions = match &error.1 {
+                    AppError::Auth(error) => Some(view! { <AuthErrorActions transient=error.is_transient()/> }),
+                    AppError::NotFound => None,
+                };
                 view! {
                     <h2>{error_code.to_string()}</h2>
                     <p>"Error: " {error_string}</p>
+                    {actions}
                 }
             }
         />
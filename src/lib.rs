@@ -1,9 +1,14 @@
 use cfg_if::cfg_if;
+pub mod action;
 pub mod api;
 pub mod app;
+#[cfg(feature = "demo")]
+pub mod demo;
 pub mod error_template;
 pub mod fileserv;
 pub mod model;
+pub mod profiles;
+pub mod toast;
 
 cfg_if! { if #[cfg(feature = "hydrate")] {
     use leptos::*;
@@ -12,8 +12,52 @@ impl Datas {
     }
 }
 
-#[derive(Serialize, Deserialize, Clone, Debug)]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
 pub struct Data {
     pub user: bool,
     pub text: String,
+    /// The `sub` claim of the author, resolved by the `profiles` module.
+    #[serde(default)]
+    pub sub: Option<String>,
+}
+
+/// A page of a paginated backend response. `next_cursor` is `None` on the
+/// last page, `total` is only sent by endpoints which know the total count.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    #[serde(default)]
+    pub next_cursor: Option<String>,
+    #[serde(default)]
+    pub total: Option<u64>,
+}
+
+impl<T> Page<T> {
+    pub fn has_more(&self) -> bool {
+        self.next_cursor.is_some()
+    }
+
+    pub fn map<U>(self, f: impl FnMut(T) -> U) -> Page<U> {
+        Page {
+            items: self.items.into_iter().map(f).collect(),
+            next_cursor: self.next_cursor,
+            total: self.total,
+        }
+    }
+}
+
+impl<T> Default for Page<T> {
+    fn default() -> Self {
+        Page {
+            items: Vec::new(),
+            next_cursor: None,
+            total: None,
+        }
+    }
+}
+
+impl From<Page<Data>> for Datas {
+    fn from(page: Page<Data>) -> Self {
+        Datas { data: page.items }
+    }
 }
@@ -0,0 +1,143 @@
+use std::collections::{HashMap, HashSet};
+
+use leptos::*;
+use leptos_oidc2::try_use_auth;
+use serde::{Deserialize, Serialize};
+
+use crate::api::{self, use_api_client, ApiClient};
+
+/// The display name and avatar of a user, keyed by the `sub` claim.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Profile {
+    pub sub: String,
+    pub name: String,
+    pub avatar_url: Option<String>,
+}
+
+/// A cache of the resolved profiles. Lookups are collected and resolved in a
+/// single batch on the next tick by the backend, see `api::resolve_profiles`.
+/// The current user is taken from the ID token, without a request.
+#[derive(Clone, Copy)]
+pub struct Profiles {
+    client: StoredValue<ApiClient>,
+    resolved: RwSignal<HashMap<String, Profile>>,
+    pending: StoredValue<HashSet<String>>,
+    requested: StoredValue<HashSet<String>>,
+}
+
+impl Profiles {
+    /// Returns the profile of the given subject, requesting it if unknown.
+    pub fn get(&self, sub: &str) -> Option<Profile> {
+        let profile = self.resolved.with(|resolved| resolved.get(sub).cloned());
+        if profile.is_none()
+            && !self
+                .requested
+                .with_value(|requested| requested.contains(sub))
+        {
+            self.request(sub);
+        }
+        profile
+    }
+
+    fn request(&self, sub: &str) {
+        self.requested.update_value(|requested| {
+            requested.insert(sub.to_string());
+        });
+        let first = self.pending.with_value(HashSet::is_empty);
+        self.pending.update_value(|pending| {
+            pending.insert(sub.to_string());
+        });
+        if first {
+            let profiles = *self;
+            set_timeout(move || profiles.flush(), std::time::Duration::ZERO);
+        }
+    }
+
+    fn flush(&self) {
+        let mut subs = Vec::new();
+        self.pending
+            .update_value(|pending| subs = pending.drain().collect());
+        let client = self.client.get_value();
+        let resolved = self.resolved;
+        spawn_local(async move {
+            match api::resolve_profiles(&client, &subs).await {
+                Ok(profiles) => resolved.update(|resolved| {
+                    for profile in profiles {
+                        resolved.insert(profile.sub.clone(), profile);
+                    }
+                }),
+                Err(error) => log::error!("Unable to resolve profiles: {error}"),
+            }
+        });
+    }
+
+    fn insert(&self, profile: Profile) {
+        self.resolved.update(|resolved| {
+            resolved.insert(profile.sub.clone(), profile);
+        });
+    }
+}
+
+/// Provides the profile cache, seeded with the current user. Needs the
+/// `ApiClient`, see `provide_api_client`.
+pub fn provide_profiles() -> Profiles {
+    let profiles = Profiles {
+        client: store_value(use_api_client()),
+        resolved: create_rw_signal(HashMap::new()),
+        pending: store_value(HashSet::new()),
+        requested: store_value(HashSet::new()),
+    };
+    if let Some(auth) = try_use_auth() {
+        create_effect(move |_| {
+            if let Some(sub) = auth.subject() {
+                let name = auth
+                    .name()
+                    .or_else(|| auth.preferred_username())
+                    .or_else(|| auth.email())
+                    .unwrap_or_else(|| sub.clone());
+                profiles.insert(Profile {
+                    sub,
+                    name,
+                    avatar_url: None,
+                });
+            }
+        });
+    }
+    provide_context(profiles);
+
+    profiles
+}
+
+/// Renders the avatar of the given subject, the initials as long as there is
+/// no avatar image.
+#[component]
+pub fn Avatar(#[prop(into)] sub: String) -> impl IntoView {
+    let profiles = expect_context::<Profiles>();
+    let profile = Signal::derive({
+        let sub = sub.clone();
+        move || profiles.get(&sub)
+    });
+    let name = Signal::derive(move || {
+        profile
+            .get()
+            .map_or_else(|| sub.clone(), |profile| profile.name)
+    });
+    let initials = move || {
+        name.get()
+            .split_whitespace()
+            .filter_map(|part| part.chars().next())
+            .take(2)
+            .collect::<String>()
+            .to_uppercase()
+    };
+    let avatar_url = move || profile.get().and_then(|profile| profile.avatar_url);
+
+    view! {
+        <span class="avatar" title=name>
+            {move || match avatar_url() {
+                Some(avatar_url) => view! { <img src=avatar_url alt=""/> }.into_view(),
+                None => initials().into_view(),
+            }}
+        </span>
+    }
+}
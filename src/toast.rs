@@ -0,0 +1,79 @@
+use std::time::Duration;
+
+use leptos::*;
+
+/// How long a toast is shown.
+const TOAST_DURATION: Duration = Duration::from_secs(5);
+
+/// A short notice shown on top of the page.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Toast {
+    id: usize,
+    pub message: String,
+}
+
+/// The toasts currently shown, shared via context, see `provide_toasts`.
+#[derive(Clone, Copy)]
+pub struct Toasts {
+    toasts: RwSignal<Vec<Toast>>,
+    next_id: StoredValue<usize>,
+}
+
+impl Toasts {
+    /// Shows the given error message, it's dismissed after a few seconds.
+    pub fn error(&self, message: impl Into<String>) {
+        let id = self.next_id.get_value();
+        self.next_id.set_value(id + 1);
+        self.toasts.update(|toasts| {
+            toasts.push(Toast {
+                id,
+                message: message.into(),
+            })
+        });
+        let toasts = *self;
+        set_timeout(move || toasts.dismiss(id), TOAST_DURATION);
+    }
+
+    fn dismiss(&self, id: usize) {
+        self.toasts
+            .update(|toasts| toasts.retain(|toast| toast.id != id));
+    }
+}
+
+/// Provides an empty toast list.
+pub fn provide_toasts() -> Toasts {
+    let toasts = Toasts {
+        toasts: create_rw_signal(Vec::new()),
+        next_id: store_value(0),
+    };
+    provide_context(toasts);
+
+    toasts
+}
+
+/// Returns the toasts provided by `provide_toasts`.
+pub fn use_toasts() -> Toasts {
+    expect_context::<Toasts>()
+}
+
+/// Renders the toasts, a click dismisses one right away.
+#[component]
+pub fn ToastHost() -> impl IntoView {
+    let toasts = use_toasts();
+
+    view! {
+        <div class="toasts" role="status">
+            <For
+                each=move || toasts.toasts.get()
+                key=|toast| toast.id
+                children=move |toast: Toast| {
+                    view! {
+                        <div class="toast error" on:click=move |_| toasts.dismiss(toast.id)>
+                            {toast.message}
+                        </div>
+                    }
+                }
+            />
+        </div>
+    }
+}
@@ -0,0 +1,71 @@
+use backend_test::model::data::{Data, Datas, Page};
+use serde_json::json;
+
+fn message(text: &str) -> Data {
+    Data {
+        user: true,
+        text: text.to_string(),
+        sub: Some("user-1".to_string()),
+    }
+}
+
+#[test]
+fn round_trips_a_page() {
+    let page = Page {
+        items: vec![message("hello"), message("world")],
+        next_cursor: Some("cursor-2".to_string()),
+        total: Some(5),
+    };
+
+    let json = serde_json::to_string(&page).expect("unable to serialize the page");
+    let decoded: Page<Data> = serde_json::from_str(&json).expect("unable to parse the page");
+
+    assert_eq!(decoded, page);
+    assert!(decoded.has_more());
+}
+
+#[test]
+fn parses_the_last_page_without_cursor_and_total() {
+    let page: Page<Data> = serde_json::from_value(json!({
+        "items": [{ "user": false, "text": "hello" }],
+    }))
+    .expect("unable to parse the page");
+
+    assert_eq!(
+        page,
+        Page {
+            items: vec![Data {
+                user: false,
+                text: "hello".to_string(),
+                sub: None,
+            }],
+            next_cursor: None,
+            total: None,
+        }
+    );
+    assert!(!page.has_more());
+}
+
+#[test]
+fn rejects_a_page_without_items() {
+    let page = serde_json::from_value::<Page<Data>>(json!({ "next_cursor": "cursor-2" }));
+
+    assert!(page.is_err());
+}
+
+#[test]
+fn maps_the_items_of_a_page() {
+    let page = Page {
+        items: vec![message("hello")],
+        next_cursor: Some("cursor-2".to_string()),
+        total: Some(1),
+    };
+
+    let datas = Datas::from(page.clone());
+    let texts = page.map(|data| data.text);
+
+    assert_eq!(datas.data, vec![message("hello")]);
+    assert_eq!(texts.items, vec!["hello".to_string()]);
+    assert_eq!(texts.next_cursor.as_deref(), Some("cursor-2"));
+    assert_eq!(texts.total, Some(1));
+}
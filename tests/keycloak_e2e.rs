@@ -0,0 +1,165 @@
+//! End-to-end tests of the authentication flow against Keycloak, driven
+//! through a browser. They are ignored by default, because they need running
+//! services:
+//!
+//! ```sh
+//! docker compose -f end2end/keycloak/docker-compose.yml up -d
+//! set -a; . end2end/keycloak/app.env; set +a   # the `backend-test` realm
+//! cargo leptos serve
+//! cargo test --test keycloak_e2e -- --ignored --test-threads 1
+//! ```
+//!
+//! `APP_URL`, `WEBDRIVER_URL`, `E2E_USERNAME` and `E2E_PASSWORD` override the
+//! defaults.
+
+use std::time::Duration;
+
+use fantoccini::{Client, ClientBuilder, Locator};
+use serde_json::{json, Value};
+
+/// The key of the token storage, `auth_<client_id>`.
+const TOKEN_STORAGE_KEY: &str = "auth_backend-test";
+
+fn env(key: &str, default: &str) -> String {
+    std::env::var(key).unwrap_or_else(|_| default.to_string())
+}
+
+async fn browser() -> Client {
+    let mut capabilities = serde_json::Map::new();
+    capabilities.insert(
+        "goog:chromeOptions".to_string(),
+        json!({ "args": ["--headless", "--no-sandbox"] }),
+    );
+    ClientBuilder::native()
+        .capabilities(capabilities)
+        .connect(&env("WEBDRIVER_URL", "http://localhost:4444"))
+        .await
+        .expect("unable to connect to the WebDriver")
+}
+
+/// Returns the stored token storage, if any.
+async fn stored_tokens(client: &Client) -> Option<Value> {
+    let value = client
+        .execute(
+            "return window.localStorage.getItem(arguments[0]);",
+            vec![json!(TOKEN_STORAGE_KEY)],
+        )
+        .await
+        .expect("unable to read the local storage");
+    value
+        .as_str()
+        .map(|value| serde_json::from_str(value).expect("invalid token storage"))
+}
+
+/// Waits until the tokens are stored, or fails after a few seconds.
+async fn wait_for_tokens(client: &Client, previous: Option<&Value>) -> Value {
+    for _ in 0..50 {
+        if let Some(tokens) = stored_tokens(client).await {
+            if Some(&tokens) != previous {
+                return tokens;
+            }
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    panic!("no (new) tokens were stored");
+}
+
+/// Logs in through the Keycloak login form and returns the stored tokens.
+async fn login(client: &Client) -> Value {
+    client
+        .goto(&env("APP_URL", "http://localhost:3000"))
+        .await
+        .unwrap();
+    client
+        .wait()
+        .for_element(Locator::LinkText("Login"))
+        .await
+        .unwrap()
+        .click()
+        .await
+        .unwrap();
+
+    let form = client
+        .wait()
+        .for_element(Locator::Id("kc-form-login"))
+        .await
+        .unwrap();
+    form.find(Locator::Id("username"))
+        .await
+        .unwrap()
+        .send_keys(&env("E2E_USERNAME", "test"))
+        .await
+        .unwrap();
+    form.find(Locator::Id("password"))
+        .await
+        .unwrap()
+        .send_keys(&env("E2E_PASSWORD", "test"))
+        .await
+        .unwrap();
+    form.find(Locator::Id("kc-login"))
+        .await
+        .unwrap()
+        .click()
+        .await
+        .unwrap();
+
+    wait_for_tokens(client, None).await
+}
+
+#[tokio::test]
+#[ignore = "needs Keycloak, a WebDriver and the running app"]
+async fn login_persists_the_tokens() {
+    let client = browser().await;
+
+    let tokens = login(&client).await;
+    assert!(tokens["access_token"].is_string());
+    assert!(tokens["id_token"].is_string());
+
+    // The callback parameters are removed after the code exchange.
+    let url = client.current_url().await.unwrap();
+    assert!(url.query_pairs().all(|(key, _)| key != "code"));
+
+    client.close().await.unwrap();
+}
+
+#[tokio::test]
+#[ignore = "needs Keycloak, a WebDriver and the running app"]
+async fn refresh_rotates_the_access_token() {
+    let client = browser().await;
+    let tokens = login(&client).await;
+
+    client
+        .find(Locator::Id("refresh"))
+        .await
+        .unwrap()
+        .click()
+        .await
+        .unwrap();
+    let refreshed = wait_for_tokens(&client, Some(&tokens)).await;
+    assert_ne!(refreshed["access_token"], tokens["access_token"]);
+
+    client.close().await.unwrap();
+}
+
+#[tokio::test]
+#[ignore = "needs Keycloak, a WebDriver and the running app"]
+async fn logout_clears_the_tokens() {
+    let client = browser().await;
+    login(&client).await;
+
+    client
+        .find(Locator::Id("logout"))
+        .await
+        .unwrap()
+        .click()
+        .await
+        .unwrap();
+    for _ in 0..50 {
+        if stored_tokens(&client).await.is_none() {
+            client.close().await.unwrap();
+            return;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+    panic!("the tokens weren't removed on logout");
+}